@@ -0,0 +1,54 @@
+//! Measures `DataSet::add_data_set` for cumulative data sets with many distinct datagrams.
+//!
+//! ```text
+//! cargo bench --bench data_set
+//! ```
+extern crate resol_vbus;
+
+
+use std::time::{Duration, Instant};
+
+use resol_vbus::{Data, DataSet, Datagram, Header};
+use resol_vbus::utils::utc_timestamp;
+
+
+fn data_set_with_datagrams(count: usize) -> DataSet {
+    let timestamp = utc_timestamp(1485688933);
+
+    let data = (0..count).map(|index| {
+        Data::Datagram(Datagram {
+            header: Header {
+                timestamp,
+                channel: 0x00,
+                destination_address: 0x0000,
+                source_address: 0x7E11,
+                protocol_version: 0x20,
+            },
+            command: 0x0900,
+            param16: index as i16,
+            param32: 0,
+        })
+    }).collect();
+
+    DataSet::from_data(timestamp, data)
+}
+
+
+fn main() {
+    for &count in &[ 100, 1_000, 10_000 ] {
+        let data_set = data_set_with_datagrams(count);
+
+        let rounds = 10;
+        let mut cumulative_data_set = DataSet::new();
+        let start = Instant::now();
+        for _ in 0..rounds {
+            cumulative_data_set.add_data_set(data_set.clone());
+        }
+        let elapsed = start.elapsed();
+
+        let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+        let per_data = Duration::from_nanos(nanos / (rounds * count) as u64);
+
+        println!("DataSet::add_data_set with {:>6} datagrams {:>10?} per data ({} kept)", count, per_data, cumulative_data_set.len());
+    }
+}
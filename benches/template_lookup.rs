@@ -0,0 +1,76 @@
+//! Compares the linear template lookup of `SpecificationFile` with the `TemplateIndex`.
+//!
+//! ```text
+//! cargo bench --bench template_lookup
+//! ```
+extern crate resol_vbus;
+
+
+use std::time::{Duration, Instant};
+
+use resol_vbus::{Language, Specification, SpecificationFile};
+use resol_vbus::specification_file::TemplateIndex;
+
+
+fn bench<F: FnMut() -> usize>(name: &str, lookups: usize, mut f: F) {
+    // warm up
+    let mut found = f();
+
+    let rounds = 20;
+    let start = Instant::now();
+    for _ in 0..rounds {
+        found += f();
+    }
+    let elapsed = start.elapsed();
+
+    let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+    let per_lookup = Duration::from_nanos(nanos / (rounds * lookups) as u64);
+
+    println!("{:<40} {:>10?} per lookup ({} found)", name, per_lookup, found);
+}
+
+
+fn main() {
+    let spec_file = SpecificationFile::new_default();
+    let index = TemplateIndex::new(&spec_file);
+
+    let packet_ids = spec_file.packet_templates.iter().map(|packet_template| {
+        (packet_template.destination_address, packet_template.source_address, packet_template.command)
+    }).collect::<Vec<_>>();
+
+    let device_ids = spec_file.device_templates.iter().map(|device_template| {
+        (device_template.self_address, device_template.peer_address)
+    }).collect::<Vec<_>>();
+
+    bench("SpecificationFile::find_device_template", device_ids.len(), || {
+        device_ids.iter().filter(|&&(self_address, peer_address)| {
+            spec_file.find_device_template(self_address, peer_address).is_some()
+        }).count()
+    });
+
+    bench("TemplateIndex::find_device_template", device_ids.len(), || {
+        device_ids.iter().filter(|&&(self_address, peer_address)| {
+            index.find_device_template(&spec_file, self_address, peer_address).is_some()
+        }).count()
+    });
+
+    bench("SpecificationFile::find_packet_template", packet_ids.len(), || {
+        packet_ids.iter().filter(|&&(destination_address, source_address, command)| {
+            spec_file.find_packet_template(destination_address, source_address, command).is_some()
+        }).count()
+    });
+
+    bench("TemplateIndex::find_packet_template", packet_ids.len(), || {
+        packet_ids.iter().filter(|&&(destination_address, source_address, command)| {
+            index.find_packet_template(&spec_file, destination_address, source_address, command).is_some()
+        }).count()
+    });
+
+    let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+    bench("Specification::get_packet_spec", packet_ids.len(), || {
+        packet_ids.iter().filter(|&&(destination_address, source_address, command)| {
+            !spec.get_packet_spec(0x00, destination_address, source_address, command).fields.is_empty()
+        }).count()
+    });
+}
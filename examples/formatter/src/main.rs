@@ -205,6 +205,9 @@ fn run() -> Result<()> {
         Some("en") => Language::En,
         Some("de") => Language::De,
         Some("fr") => Language::Fr,
+        Some("it") => Language::It,
+        Some("es") => Language::Es,
+        Some("nl") => Language::Nl,
         Some(lang) => panic!("Unexpected language {}", lang),
     };
 
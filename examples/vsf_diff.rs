@@ -0,0 +1,51 @@
+//! Prints the differences between two VSF files.
+//!
+//! ```text
+//! cargo run --example vsf_diff -- [OLD.vsf] NEW.vsf
+//! ```
+//!
+//! If no old VSF file is given, the new VSF file is compared to the embedded VSF file. The
+//! process exits with status 1 if any differences were found.
+extern crate resol_vbus;
+
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+
+use resol_vbus::SpecificationFile;
+use resol_vbus::specification_diff::diff;
+
+
+fn read_spec_file(filename: &str) -> SpecificationFile {
+    let mut buf = Vec::new();
+    File::open(filename).unwrap().read_to_end(&mut buf).unwrap();
+    SpecificationFile::from_bytes(&buf).unwrap()
+}
+
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+
+    let (old_spec_file, new_spec_file) = match &args [..] {
+        [new_filename] => (SpecificationFile::new_default(), read_spec_file(new_filename)),
+        [old_filename, new_filename] => (read_spec_file(old_filename), read_spec_file(new_filename)),
+        _ => {
+            eprintln!("Usage: vsf_diff [OLD.vsf] NEW.vsf");
+            process::exit(2);
+        },
+    };
+
+    println!("Datecode: {} -> {}", old_spec_file.datecode, new_spec_file.datecode);
+
+    let changes = diff(&old_spec_file, &new_spec_file);
+    for change in &changes {
+        println!("{}", change);
+    }
+
+    if !changes.is_empty() {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,69 @@
+//! Renders the packet reference documentation of a VSF file as Markdown or HTML.
+//!
+//! ```text
+//! cargo run --example vsf_docs -- markdown|html LANGUAGE [INPUT.vsf] OUTPUT
+//! ```
+//!
+//! The `LANGUAGE` is one of `en`, `de`, `fr`, `it`, `es` or `nl`. If no input VSF file is given,
+//! the embedded VSF file is used.
+extern crate resol_vbus;
+
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+
+use resol_vbus::{Language, Specification, SpecificationFile};
+use resol_vbus::specification_docs::{DocFormat, write_docs};
+
+
+fn read_file(filename: &str) -> Vec<u8> {
+    let mut buf = Vec::new();
+    File::open(filename).unwrap().read_to_end(&mut buf).unwrap();
+    buf
+}
+
+
+fn usage() -> ! {
+    eprintln!("Usage: vsf_docs markdown|html LANGUAGE [INPUT.vsf] OUTPUT");
+    process::exit(1);
+}
+
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+
+    let (format, language, input_filename, output_filename) = match &args [..] {
+        [format, language, output_filename] => (*format, *language, None, *output_filename),
+        [format, language, input_filename, output_filename] => (*format, *language, Some(*input_filename), *output_filename),
+        _ => usage(),
+    };
+
+    let format = match format {
+        "markdown" => DocFormat::Markdown,
+        "html" => DocFormat::Html,
+        _ => usage(),
+    };
+
+    let language = match language {
+        "en" => Language::En,
+        "de" => Language::De,
+        "fr" => Language::Fr,
+        "it" => Language::It,
+        "es" => Language::Es,
+        "nl" => Language::Nl,
+        _ => usage(),
+    };
+
+    let spec_file = match input_filename {
+        Some(input_filename) => SpecificationFile::from_bytes(&read_file(input_filename)).unwrap(),
+        None => SpecificationFile::new_default(),
+    };
+
+    let spec = Specification::from_file(spec_file, language);
+
+    let mut output = File::create(output_filename).unwrap();
+    write_docs(&mut output, &spec, format).unwrap();
+}
@@ -0,0 +1,83 @@
+//! Compiles a JSON specification source into a VSF file or decompiles a VSF file into JSON.
+//!
+//! ```text
+//! cargo run --features serde --example vsf_source -- decompile [INPUT.vsf] OUTPUT.json
+//! cargo run --features serde --example vsf_source -- compile INPUT.json OUTPUT.vsf
+//! cargo run --features serde --example vsf_source -- lint [INPUT.vsf]
+//! ```
+//!
+//! If no input VSF file is given to the `decompile` or `lint` command, the embedded VSF file is
+//! used. The `compile` and `lint` commands print all issues found by the linter and fail if at
+//! least one of them is an error.
+extern crate resol_vbus;
+
+
+use std::env;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::process;
+
+use resol_vbus::SpecificationFile;
+use resol_vbus::specification_lint::{lint, Severity};
+use resol_vbus::specification_source::SpecificationSource;
+
+
+fn read_file(filename: &str) -> Vec<u8> {
+    let mut buf = Vec::new();
+    File::open(filename).unwrap().read_to_end(&mut buf).unwrap();
+    buf
+}
+
+
+fn write_file(filename: &str, buf: &[u8]) {
+    File::create(filename).unwrap().write_all(buf).unwrap();
+}
+
+
+fn lint_file(spec_file: &SpecificationFile) {
+    let issues = lint(spec_file);
+    for issue in &issues {
+        eprintln!("{:?}: {}", issue.severity(), issue);
+    }
+    if issues.iter().any(|issue| issue.severity() == Severity::Error) {
+        process::exit(1);
+    }
+}
+
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let args = args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>();
+
+    match &args [..] {
+        ["decompile", output_filename] => {
+            let spec_file = SpecificationFile::new_default();
+            let source = SpecificationSource::from_file(&spec_file);
+            write_file(output_filename, source.to_json().unwrap().as_bytes());
+        },
+        ["decompile", input_filename, output_filename] => {
+            let spec_file = SpecificationFile::from_bytes(&read_file(input_filename)).unwrap();
+            let source = SpecificationSource::from_file(&spec_file);
+            write_file(output_filename, source.to_json().unwrap().as_bytes());
+        },
+        ["compile", input_filename, output_filename] => {
+            let json = String::from_utf8(read_file(input_filename)).unwrap();
+            let source = SpecificationSource::from_json(&json).unwrap();
+            let spec_file = source.to_file().unwrap();
+            lint_file(&spec_file);
+            write_file(output_filename, &spec_file.to_bytes());
+        },
+        ["lint"] => {
+            lint_file(&SpecificationFile::new_default());
+        },
+        ["lint", input_filename] => {
+            lint_file(&SpecificationFile::from_bytes(&read_file(input_filename)).unwrap());
+        },
+        _ => {
+            eprintln!("Usage: vsf_source decompile [INPUT.vsf] OUTPUT.json");
+            eprintln!("       vsf_source compile INPUT.json OUTPUT.vsf");
+            eprintln!("       vsf_source lint [INPUT.vsf]");
+            process::exit(1);
+        },
+    }
+}
@@ -0,0 +1,310 @@
+//! A module that generates typed Rust structs for selected packets of a `Specification`.
+//!
+//! Each generated struct wraps the frame data of a packet and provides one accessor per field
+//! that decodes the field's value directly from the frame data. Field IDs are resolved while
+//! generating the code, so a misspelled accessor is a compile error instead of a `None` at
+//! runtime.
+//!
+//! The generator is intended to be used from a build script. The accessor names are derived
+//! from the field names, so the `Specification` should use `Language::En`.
+//!
+//! ```rust,no_run
+//! // build.rs
+//! extern crate resol_vbus;
+//!
+//! use std::env;
+//! use std::fs::File;
+//! use std::path::Path;
+//!
+//! use resol_vbus::{Specification, SpecificationFile, Language, PacketId};
+//! use resol_vbus::code_generator::{PacketStruct, write_packet_structs};
+//!
+//! fn main() {
+//!     let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+//!
+//!     let packet_structs = [
+//!         PacketStruct::new("DeltaSolMx", PacketId(0x00, 0x0010, 0x7E11, 0x0100)),
+//!     ];
+//!
+//!     let path = Path::new(&env::var("OUT_DIR").unwrap()).join("packets.rs");
+//!     let mut file = File::create(path).unwrap();
+//!     write_packet_structs(&mut file, &spec, &packet_structs).unwrap();
+//! }
+//!
+//! // src/main.rs
+//! //
+//! // include!(concat!(env!("OUT_DIR"), "/packets.rs"));
+//! //
+//! // if let Some(packet) = DeltaSolMx::from_packet(&packet) {
+//! //     println!("{:?}", packet.temperature_sensor_1());
+//! // }
+//! ```
+use std::collections::HashSet;
+use std::io::{Result, Write};
+
+use packet::PacketId;
+use specification::{PacketFieldSpec, Specification};
+use specification_file::Type;
+
+
+/// A packet to generate a struct for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacketStruct {
+    /// The name of the generated struct.
+    pub name: String,
+
+    /// The `PacketId` of the packet. The channel is ignored.
+    pub packet_id: PacketId,
+}
+
+
+impl PacketStruct {
+
+    /// Construct a new `PacketStruct`.
+    pub fn new(name: &str, packet_id: PacketId) -> PacketStruct {
+        PacketStruct {
+            name: name.to_owned(),
+            packet_id,
+        }
+    }
+
+}
+
+
+const KEYWORDS: [&str; 38] = [
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "async", "await", "dyn",
+];
+
+
+/// Convert a field name into a snake case identifier.
+fn identifier_from_name(name: &str) -> String {
+    let mut identifier = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            identifier.push(c.to_ascii_lowercase());
+        } else if !identifier.is_empty() && !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+    }
+
+    while identifier.ends_with('_') {
+        identifier.pop();
+    }
+
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert_str(0, "field_");
+    }
+
+    if KEYWORDS.contains(&identifier.as_str()) {
+        identifier.push('_');
+    }
+
+    identifier
+}
+
+
+fn escape_doc(text: &str) -> String {
+    text.replace('\n', " ")
+}
+
+
+fn write_raw_accessor<W: Write>(w: &mut W, field_spec: &PacketFieldSpec, identifier: &str) -> Result<()> {
+    let min_length = field_spec.parts.iter().map(|part| part.offset as usize + 1).max().unwrap_or(0);
+
+    writeln!(w, "    /// The raw value of \"{}\" (`{}`).", escape_doc(&field_spec.name), field_spec.field_id)?;
+    writeln!(w, "    pub fn {}_raw(&self) -> Option<i64> {{", identifier)?;
+    writeln!(w, "        let buf = self.frame_data;")?;
+    writeln!(w, "        if buf.len() < {} {{", min_length)?;
+    writeln!(w, "            return None;")?;
+    writeln!(w, "        }}")?;
+    writeln!(w, "        Some(0")?;
+    for part in &field_spec.parts {
+        let mut term = if part.is_signed {
+            format!("(buf [{}] as i8 as i64)", part.offset)
+        } else {
+            format!("(buf [{}] as i64)", part.offset)
+        };
+        if part.mask != 0xFF {
+            term = format!("({} & 0x{:02X})", term, part.mask);
+        }
+        if part.bit_pos > 0 {
+            term = format!("({} >> {})", term, part.bit_pos);
+        }
+        writeln!(w, "            + {} * {}", term, part.factor)?;
+    }
+    writeln!(w, "        )")?;
+    writeln!(w, "    }}")?;
+
+    Ok(())
+}
+
+
+fn write_value_accessor<W: Write>(w: &mut W, field_spec: &PacketFieldSpec, identifier: &str) -> Result<()> {
+    let (typ, conversion) = match field_spec.typ {
+        _ if field_spec.enum_type.is_some() => ("i64", None),
+        Type::Number if field_spec.precision == 0 => ("i64", None),
+        Type::Number => ("f64", Some(format!("|raw_value| raw_value as f64 * ::resol_vbus::specification::power_of_ten_f64({})", - field_spec.precision))),
+        Type::Time => ("::resol_vbus::chrono::Duration", Some("::resol_vbus::field_value::time_from_raw_value".to_owned())),
+        Type::WeekTime => ("(::resol_vbus::chrono::Weekday, ::resol_vbus::chrono::NaiveTime)", Some("::resol_vbus::field_value::week_time_from_raw_value".to_owned())),
+        Type::DateTime => ("::resol_vbus::chrono::DateTime<::resol_vbus::chrono::UTC>", Some("::resol_vbus::field_value::date_time_from_raw_value".to_owned())),
+    };
+
+    let unit_text = field_spec.unit_text.trim();
+    if unit_text.is_empty() {
+        writeln!(w, "    /// The value of \"{}\" (`{}`).", escape_doc(&field_spec.name), field_spec.field_id)?;
+    } else {
+        writeln!(w, "    /// The value of \"{}\" (`{}`) in {}.", escape_doc(&field_spec.name), field_spec.field_id, unit_text)?;
+    }
+    writeln!(w, "    pub fn {}(&self) -> Option<{}> {{", identifier, typ)?;
+    match conversion {
+        Some(conversion) => writeln!(w, "        self.{}_raw().map({})", identifier, conversion)?,
+        None => writeln!(w, "        self.{}_raw()", identifier)?,
+    }
+    writeln!(w, "    }}")?;
+
+    Ok(())
+}
+
+
+fn write_packet_struct<W: Write>(w: &mut W, spec: &Specification, packet_struct: &PacketStruct) -> Result<()> {
+    let PacketId(_, destination_address, source_address, command) = packet_struct.packet_id;
+
+    let packet_spec = spec.get_packet_spec(0, destination_address, source_address, command);
+
+    let name = &packet_struct.name;
+
+    writeln!(w, "/// {}", escape_doc(&packet_spec.name))?;
+    writeln!(w, "///")?;
+    writeln!(w, "/// Generated from the specification of packet `{}`.", &packet_spec.packet_id [3..])?;
+    writeln!(w, "#[derive(Debug, Clone, Copy)]")?;
+    writeln!(w, "pub struct {}<'a> {{", name)?;
+    writeln!(w, "    frame_data: &'a [u8],")?;
+    writeln!(w, "}}")?;
+    writeln!(w)?;
+    writeln!(w)?;
+    writeln!(w, "#[allow(dead_code)]")?;
+    writeln!(w, "impl<'a> {}<'a> {{", name)?;
+    writeln!(w)?;
+    writeln!(w, "    /// The destination address of the packet.")?;
+    writeln!(w, "    pub const DESTINATION_ADDRESS: u16 = 0x{:04X};", destination_address)?;
+    writeln!(w)?;
+    writeln!(w, "    /// The source address of the packet.")?;
+    writeln!(w, "    pub const SOURCE_ADDRESS: u16 = 0x{:04X};", source_address)?;
+    writeln!(w)?;
+    writeln!(w, "    /// The command of the packet.")?;
+    writeln!(w, "    pub const COMMAND: u16 = 0x{:04X};", command)?;
+    writeln!(w)?;
+    writeln!(w, "    /// Wrap the frame data of a packet.")?;
+    writeln!(w, "    pub fn new(frame_data: &'a [u8]) -> {}<'a> {{", name)?;
+    writeln!(w, "        {} {{ frame_data }}", name)?;
+    writeln!(w, "    }}")?;
+    writeln!(w)?;
+    writeln!(w, "    /// Wrap the frame data of a `Packet` if its addresses and command match.")?;
+    writeln!(w, "    pub fn from_packet(packet: &'a ::resol_vbus::Packet) -> Option<{}<'a>> {{", name)?;
+    writeln!(w, "        if packet.header.destination_address == Self::DESTINATION_ADDRESS && packet.header.source_address == Self::SOURCE_ADDRESS && packet.command == Self::COMMAND {{")?;
+    writeln!(w, "            Some({}::new(packet.valid_frame_data()))", name)?;
+    writeln!(w, "        }} else {{")?;
+    writeln!(w, "            None")?;
+    writeln!(w, "        }}")?;
+    writeln!(w, "    }}")?;
+
+    let mut identifiers = HashSet::new();
+    identifiers.insert("new".to_owned());
+    identifiers.insert("from_packet".to_owned());
+
+    for field_spec in &packet_spec.fields {
+        let mut identifier = identifier_from_name(&field_spec.name);
+        if identifiers.contains(&identifier) || identifiers.contains(&format!("{}_raw", identifier)) {
+            identifier = format!("{}_{}", identifier, field_spec.field_id.to_ascii_lowercase());
+        }
+        identifiers.insert(format!("{}_raw", identifier));
+        identifiers.insert(identifier.clone());
+
+        writeln!(w)?;
+        write_raw_accessor(w, field_spec, &identifier)?;
+        writeln!(w)?;
+        write_value_accessor(w, field_spec, &identifier)?;
+    }
+
+    writeln!(w)?;
+    writeln!(w, "}}")?;
+
+    Ok(())
+}
+
+
+/// Write a typed struct for each of the given `PacketStruct` values.
+pub fn write_packet_structs<W: Write>(w: &mut W, spec: &Specification, packet_structs: &[PacketStruct]) -> Result<()> {
+    writeln!(w, "// This file was generated by `resol_vbus::code_generator`, do not edit it manually.")?;
+    for packet_struct in packet_structs {
+        writeln!(w)?;
+        writeln!(w)?;
+        write_packet_struct(w, spec, packet_struct)?;
+    }
+    Ok(())
+}
+
+
+/// Generate a typed struct for each of the given `PacketStruct` values.
+pub fn packet_structs_to_string(spec: &Specification, packet_structs: &[PacketStruct]) -> String {
+    let mut buf = Vec::new();
+    write_packet_structs(&mut buf, spec, packet_structs).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use specification_file::{Language, SpecificationFile};
+
+    use test_data::SPEC_FILE_1;
+
+    use super::*;
+
+    #[test]
+    fn test_identifier_from_name() {
+        assert_eq!("temperature_sensor_1", identifier_from_name("Temperature sensor 1"));
+        assert_eq!("heat_quantity_today", identifier_from_name(" Heat-quantity (today) "));
+        assert_eq!("w_rmemenge", identifier_from_name("Wärmemenge"));
+        assert_eq!("field_1st_relay", identifier_from_name("1st relay"));
+        assert_eq!("field_", identifier_from_name("°"));
+        assert_eq!("type_", identifier_from_name("Type"));
+    }
+
+    #[test]
+    fn test_packet_structs_to_string() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_file(spec_file, Language::En);
+
+        let code = packet_structs_to_string(&spec, &[
+            PacketStruct::new("Wmz1", PacketId(0x01, 0x0010, 0x7E31, 0x0100)),
+        ]);
+
+        assert!(code.contains("/// Generated from the specification of packet `0010_7E31_10_0100`.\n#[derive(Debug, Clone, Copy)]\npub struct Wmz1<'a> {\n"));
+        assert!(code.contains("    pub const SOURCE_ADDRESS: u16 = 0x7E31;\n"));
+        assert!(code.contains(concat!(
+            "    /// The raw value of \"Heat quantity today\" (`008_4_0`).\n",
+            "    pub fn heat_quantity_today_raw(&self) -> Option<i64> {\n",
+            "        let buf = self.frame_data;\n",
+            "        if buf.len() < 12 {\n",
+            "            return None;\n",
+            "        }\n",
+            "        Some(0\n",
+            "            + (buf [8] as i64) * 1\n",
+            "            + (buf [9] as i64) * 256\n",
+            "            + (buf [10] as i64) * 65536\n",
+            "            + (buf [11] as i8 as i64) * 16777216\n",
+            "        )\n",
+            "    }\n",
+            "\n",
+            "    /// The value of \"Heat quantity today\" (`008_4_0`) in Wh.\n",
+            "    pub fn heat_quantity_today(&self) -> Option<i64> {\n",
+            "        self.heat_quantity_today_raw()\n",
+            "    }\n",
+        )));
+    }
+}
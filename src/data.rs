@@ -33,6 +33,8 @@ use telegram::Telegram;
 /// }
 /// ```
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
 pub enum Data {
     /// Contains a `Packet` conforming to VBus protocol version 1.x.
     Packet(Packet),
@@ -1,16 +1,21 @@
 use std::cmp::{Ord, Ordering};
+use std::collections::HashMap;
 use std::hash::{Hasher};
 use std::slice::{Iter, IterMut};
 
 use chrono::{DateTime, UTC};
 
-use id_hash::IdHash;
+use id_hash::{IdHash, id_hash};
 use packet::PacketId;
 use data::Data;
+use staleness::StalenessPolicy;
 
 
 /// A `DataSet` contains a set of unique (non-identical) `Data` values.
 ///
+/// The `Data` values are stored in insertion order. An index keyed by their `IdHash` allows
+/// `add_data` to find identical values in constant time.
+///
 /// # Examples
 ///
 /// ```rust
@@ -43,6 +48,12 @@ pub struct DataSet {
     /// The timestamp that corresponds to the contained set of `Data` values.
     pub timestamp: DateTime<UTC>,
     set: Vec<Data>,
+
+    /// Maps the `IdHash` of each `Data` value to its positions in `set`.
+    index: HashMap<u64, Vec<usize>>,
+
+    /// Whether `index` must be rebuilt because `set` was mutated through `iter_mut`.
+    index_dirty: bool,
 }
 
 
@@ -53,15 +64,29 @@ impl DataSet {
         DataSet {
             timestamp: UTC::now(),
             set: Vec::new(),
+            index: HashMap::new(),
+            index_dirty: false,
         }
     }
 
     /// Construct a `DataSet` from a list of `Data` values.
     pub fn from_data(timestamp: DateTime<UTC>, set: Vec<Data>) -> DataSet {
-        DataSet {
-            timestamp: timestamp,
-            set: set,
+        let mut data_set = DataSet {
+            timestamp,
+            set,
+            index: HashMap::new(),
+            index_dirty: false,
+        };
+        data_set.rebuild_index();
+        data_set
+    }
+
+    fn rebuild_index(&mut self) {
+        self.index.clear();
+        for (position, data) in self.set.iter().enumerate() {
+            self.index.entry(id_hash(data)).or_default().push(position);
         }
+        self.index_dirty = false;
     }
 
     /// Return the amount of `Data` values contained in this `DataSet`.
@@ -78,13 +103,19 @@ impl DataSet {
     pub fn add_data(&mut self, data: Data) {
         let timestamp = data.as_header().timestamp;
 
-        let position = self.set.iter().position(|d| {
-            d.eq(&data)
-        });
+        if self.index_dirty {
+            self.rebuild_index();
+        }
+
+        let positions = self.index.entry(id_hash(&data)).or_default();
 
-        match position {
-            Some(index) => self.set [index] = data,
-            None => self.set.push(data),
+        let set = &mut self.set;
+        match positions.iter().find(|&&position| set [position].eq(&data)) {
+            Some(&position) => set [position] = data,
+            None => {
+                positions.push(set.len());
+                set.push(data);
+            }
         };
 
         if self.timestamp < timestamp {
@@ -92,6 +123,17 @@ impl DataSet {
         }
     }
 
+    /// Find the `Data` value that is identical to the given one.
+    pub fn find_data(&self, data: &Data) -> Option<&Data> {
+        if self.index_dirty {
+            self.set.iter().find(|&other| other == data)
+        } else {
+            self.index.get(&id_hash(data))?.iter()
+                .map(|&position| &self.set [position])
+                .find(|&other| other == data)
+        }
+    }
+
     /// Add all `Data` values from one `DataSet` into another.
     pub fn add_data_set(&mut self, data_set: DataSet) {
         let timestamp = data_set.timestamp;
@@ -108,11 +150,21 @@ impl DataSet {
     /// Remove all `Data` values.
     pub fn remove_all_data(&mut self) {
         self.set.clear();
+        self.index.clear();
+        self.index_dirty = false;
     }
 
     /// Remove `Data` values with timestamps older than `min_timestamp`.
     pub fn remove_data_older_than(&mut self, min_timestamp: DateTime<UTC>) {
         self.set.retain(|data| data.as_header().timestamp >= min_timestamp);
+        self.rebuild_index();
+    }
+
+    /// Remove `Data` values that are stale at the given point in time according to the
+    /// `StalenessPolicy`.
+    pub fn remove_stale_data(&mut self, policy: &StalenessPolicy, now: DateTime<UTC>) {
+        self.set.retain(|data| !policy.is_stale(data, now));
+        self.rebuild_index();
     }
 
     /// Find all `Packet` values and set their `frame_count` to zero effectively hiding
@@ -129,6 +181,8 @@ impl DataSet {
 
     /// Find all `Packet` values with timestamps older than `min_timestamp` and set their
     /// `frame_count` to zero effectively hiding their `frame_data` payload.
+    ///
+    /// See the `staleness` module for an alternative that keeps the last known values.
     pub fn clear_packets_older_than(&mut self, min_timestamp: DateTime<UTC>) {
         for data in self.set.iter_mut() {
             if let Data::Packet(ref mut packet) = *data {
@@ -145,18 +199,23 @@ impl DataSet {
     }
 
     /// Returns an iterator over the `Data` values.
+    ///
+    /// Since the identity of the `Data` values may be changed through the iterator, the index
+    /// is rebuilt on the next call to `add_data`.
     pub fn iter_mut(&mut self) -> IterMut<Data> {
+        self.index_dirty = true;
         self.set.iter_mut()
     }
 
     /// Sort the `Data` values contained in this `DataSet`.
     pub fn sort(&mut self) {
-        self.set.sort_by(|l, r| { l.partial_cmp(r).unwrap() });
+        self.sort_by(|l, r| { l.partial_cmp(r).unwrap() });
     }
 
     /// Sort the `Data` values contained in this `DataSet`.
     pub fn sort_by<F>(&mut self, f: F) where F: FnMut(&Data, &Data) -> Ordering {
         self.set.sort_by(f);
+        self.rebuild_index();
     }
 
     /// Sort the `Data` values contained in this `DataSet` by a list of known `PacketId` values.
@@ -226,6 +285,7 @@ mod tests {
 
     use id_hash::id_hash;
     use live_data_decoder::data_from_checked_bytes;
+    use staleness::DataKind;
 
     use super::*;
 
@@ -348,6 +408,32 @@ mod tests {
         assert_eq!("11_7771_2011_30_25", data_slice [2].id_string());
     }
 
+    #[test]
+    fn test_remove_stale_data() {
+        let timestamp = UTC.timestamp(1485688933, 0);
+        let channel = 0x11;
+
+        let mut data_set = DataSet::new();
+        data_set.timestamp = UTC.timestamp(0, 0);
+        data_set.add_data(data_from_checked_bytes(timestamp + Duration::seconds(10), channel, &LIVE_DATA_1 [0..]));
+        data_set.add_data(data_from_checked_bytes(timestamp + Duration::seconds(20), channel, &LIVE_DATA_1 [352..]));
+        data_set.add_data(data_from_checked_bytes(timestamp + Duration::seconds(30), channel, &LIVE_TELEGRAM_1 [0..]));
+
+        let mut policy = StalenessPolicy::new();
+        policy.set_ttl(DataKind::Datagram, Duration::seconds(5));
+        policy.set_ttl(DataKind::Telegram, Duration::seconds(5));
+        data_set.remove_stale_data(&policy, timestamp + Duration::seconds(30));
+
+        let data_slice = data_set.as_data_slice();
+        assert_eq!(2, data_slice.len());
+        assert_eq!("11_0010_7E11_10_0100", data_slice [0].id_string());
+        assert_eq!(timestamp + Duration::seconds(10), data_slice [0].as_header().timestamp);
+        assert_eq!("11_7771_2011_30_25", data_slice [1].id_string());
+
+        data_set.add_data(data_from_checked_bytes(timestamp + Duration::seconds(40), channel, &LIVE_TELEGRAM_1 [0..]));
+        assert_eq!(2, data_set.len());
+    }
+
     #[test]
     fn test_sort() {
         let timestamp = UTC.timestamp(1485688933, 0);
@@ -424,6 +510,49 @@ mod tests {
         assert_eq!("11_0010_7E22_10_0100", data_set.as_data_slice() [6].id_string());
     }
 
+    #[test]
+    fn test_index() {
+        let timestamp = UTC.timestamp(1485688933, 0);
+        let other_timestamp = timestamp + Duration::seconds(1);
+        let channel = 0x11;
+
+        let mut data_set = DataSet::from_data(timestamp, vec![
+            data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [0..]),
+            data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [352..]),
+            data_from_checked_bytes(timestamp, channel, &LIVE_TELEGRAM_1 [0..]),
+        ]);
+
+        // the index must follow the positions after sorting
+        data_set.sort();
+        data_set.add_data(data_from_checked_bytes(other_timestamp, channel, &LIVE_DATA_1 [0..]));
+        assert_eq!(3, data_set.len());
+        assert_eq!("11_0010_7E11_10_0100", data_set.as_data_slice() [1].id_string());
+        assert_eq!(other_timestamp, data_set.as_data_slice() [1].as_header().timestamp);
+
+        // the index must follow identity changes through `iter_mut`
+        for data in data_set.iter_mut() {
+            if let Data::Packet(ref mut packet) = *data {
+                packet.header.channel = channel + 1;
+            }
+        }
+        data_set.add_data(data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [0..]));
+        assert_eq!(4, data_set.len());
+        data_set.add_data(data_from_checked_bytes(other_timestamp, channel + 1, &LIVE_DATA_1 [0..]));
+        assert_eq!(4, data_set.len());
+        assert_eq!("12_0010_7E11_10_0100", data_set.as_data_slice() [1].id_string());
+        assert_eq!("11_0010_7E11_10_0100", data_set.as_data_slice() [3].id_string());
+
+        // the index must follow removals
+        data_set.remove_data_older_than(other_timestamp);
+        assert_eq!(1, data_set.len());
+        data_set.add_data(data_from_checked_bytes(other_timestamp, channel + 1, &LIVE_DATA_1 [0..]));
+        assert_eq!(1, data_set.len());
+
+        data_set.remove_all_data();
+        data_set.add_data(data_from_checked_bytes(other_timestamp, channel + 1, &LIVE_DATA_1 [0..]));
+        assert_eq!(1, data_set.len());
+    }
+
     #[test]
     fn test_id_hash() {
         let timestamp = UTC.timestamp(1485688933, 0);
@@ -0,0 +1,319 @@
+//! A module that compares two `DataSet`s to find out what has changed between them.
+//!
+//! `Data` values are matched by their identity (see `Data::eq`). Values that are only contained
+//! in one of the `DataSet`s are reported as added or removed. For `Packet` values contained in
+//! both `DataSet`s, the raw values of all fields described by the `Specification` are compared
+//! and reported individually. `Datagram` and `Telegram` values are only reported as added or
+//! removed.
+//!
+//! Small changes can be suppressed using `Deadbands` per `UnitFamily`. The deadband is applied to
+//! the difference between the old and new value, so to avoid drifting away slowly, the old
+//! `DataSet` should contain the values that were reported last instead of the values received
+//! last.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{DataSet, Language, Specification, SpecificationFile};
+//! use resol_vbus::data_set_diff::{diff, Change, Deadbands};
+//! use resol_vbus::specification_file::UnitFamily;
+//!
+//! # #[allow(dead_code)]
+//! fn publish_changes(spec: &Specification, published: &mut DataSet, received: &DataSet) {
+//!     let mut deadbands = Deadbands::new();
+//!     deadbands.set(UnitFamily::Temperature, 0.5);
+//!
+//!     let mut changed = false;
+//!     for change in diff(spec, published, received, &deadbands) {
+//!         println!("{}", change);
+//!         changed = true;
+//!     }
+//!
+//!     if changed {
+//!         *published = received.clone();
+//!     }
+//! }
+//! ```
+use std::fmt;
+use std::sync::Arc;
+
+use data::Data;
+use data_set::DataSet;
+use field_value::FieldValue;
+use packet::{PacketFieldId, PacketId};
+use specification::{PacketFieldSpec, PacketSpec, Specification, power_of_ten_f64};
+use specification_file::UnitFamily;
+
+
+/// A set of deadbands per `UnitFamily`.
+///
+/// A deadband is given in the unit of the respective field. A change of a field's value is
+/// only reported if its absolute difference exceeds the deadband.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::data_set_diff::Deadbands;
+/// use resol_vbus::specification_file::UnitFamily;
+///
+/// let mut deadbands = Deadbands::new();
+/// deadbands.set(UnitFamily::Temperature, 0.5);
+///
+/// assert_eq!(Some(0.5), deadbands.get(UnitFamily::Temperature));
+/// assert_eq!(None, deadbands.get(UnitFamily::Energy));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Deadbands {
+    deadbands: Vec<(UnitFamily, f64)>,
+}
+
+
+impl Deadbands {
+
+    /// Construct an empty `Deadbands` value that reports every change.
+    pub fn new() -> Deadbands {
+        Deadbands::default()
+    }
+
+    /// Set the deadband for a `UnitFamily`, replacing any previously set one.
+    pub fn set(&mut self, unit_family: UnitFamily, deadband: f64) {
+        match self.deadbands.iter_mut().find(|(family, _)| *family == unit_family) {
+            Some(entry) => entry.1 = deadband,
+            None => self.deadbands.push((unit_family, deadband)),
+        }
+    }
+
+    /// Get the deadband for a `UnitFamily`, if any.
+    pub fn get(&self, unit_family: UnitFamily) -> Option<f64> {
+        self.deadbands.iter()
+            .find(|(family, _)| *family == unit_family)
+            .map(|(_, deadband)| *deadband)
+    }
+
+    /// Check whether the change between two raw values of a field has to be reported.
+    pub fn is_significant(&self, field_spec: &PacketFieldSpec, old_raw_value: Option<i64>, new_raw_value: Option<i64>) -> bool {
+        match (old_raw_value, new_raw_value) {
+            (Some(old_raw_value), Some(new_raw_value)) => {
+                if old_raw_value == new_raw_value {
+                    false
+                } else if let Some(deadband) = self.get(field_spec.unit_family) {
+                    let delta = (new_raw_value - old_raw_value).abs() as f64 * power_of_ten_f64(- field_spec.precision);
+                    delta > deadband
+                } else {
+                    true
+                }
+            },
+            (old_raw_value, new_raw_value) => old_raw_value != new_raw_value,
+        }
+    }
+
+}
+
+
+/// A changed value of a packet field.
+#[derive(Debug)]
+pub struct FieldChange<'a> {
+    /// The `Data` value from the new `DataSet` that contains the field.
+    pub data: &'a Data,
+
+    /// The `PacketSpec` associated with the `data`.
+    pub packet_spec: Arc<PacketSpec>,
+
+    /// The index of the `PacketFieldSpec` within the `packet_spec`.
+    pub field_index: usize,
+
+    /// The raw value in the old `DataSet`.
+    pub old_raw_value: Option<i64>,
+
+    /// The raw value in the new `DataSet`.
+    pub new_raw_value: Option<i64>,
+}
+
+
+impl<'a> FieldChange<'a> {
+
+    /// Return the `PacketFieldSpec` associated with this change.
+    pub fn field_spec(&self) -> &PacketFieldSpec {
+        &self.packet_spec.fields [self.field_index]
+    }
+
+    /// Return the `PacketId` associated with this change.
+    pub fn packet_id(&self) -> PacketId {
+        self.data.as_packet().packet_id()
+    }
+
+    /// Return the `PacketFieldId` associated with this change.
+    pub fn packet_field_id(&self) -> PacketFieldId<'_> {
+        PacketFieldId(self.packet_id(), &self.field_spec().field_id)
+    }
+
+    /// Get the old value, interpreted according to the field's `Type`.
+    pub fn old_value(&self) -> Option<FieldValue<'_>> {
+        self.old_raw_value.map(|raw_value| self.field_spec().field_value(raw_value))
+    }
+
+    /// Get the new value, interpreted according to the field's `Type`.
+    pub fn new_value(&self) -> Option<FieldValue<'_>> {
+        self.new_raw_value.map(|raw_value| self.field_spec().field_value(raw_value))
+    }
+
+}
+
+
+/// A single difference between two `DataSet`s.
+#[derive(Debug)]
+pub enum Change<'a> {
+    /// A `Data` value is only contained in the new `DataSet`.
+    DataAdded(&'a Data),
+
+    /// A `Data` value is only contained in the old `DataSet`.
+    DataRemoved(&'a Data),
+
+    /// The value of a packet field has changed.
+    FieldChanged(FieldChange<'a>),
+}
+
+
+impl<'a> fmt::Display for Change<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Change::DataAdded(data) => write!(f, "+ {}", data.id_string()),
+            Change::DataRemoved(data) => write!(f, "- {}", data.id_string()),
+            Change::FieldChanged(ref change) => {
+                let field_spec = change.field_spec();
+                write!(f, "~ {}: {} -> {}",
+                    change.packet_field_id().packet_field_id_string(),
+                    field_spec.fmt_raw_value(change.old_raw_value, true),
+                    field_spec.fmt_raw_value(change.new_raw_value, true))
+            },
+        }
+    }
+}
+
+
+/// Compare two `DataSet`s and return the list of `Change`s between them.
+///
+/// Changes are reported in the order of the `Data` values in the new `DataSet`, followed by the
+/// `Data` values that were removed in the order of the old `DataSet`.
+pub fn diff<'a>(spec: &Specification, old: &'a DataSet, new: &'a DataSet, deadbands: &Deadbands) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+
+    for new_data in new.iter() {
+        let old_data = match old.find_data(new_data) {
+            Some(old_data) => old_data,
+            None => {
+                changes.push(Change::DataAdded(new_data));
+                continue;
+            }
+        };
+
+        if let (Data::Packet(old_packet), Data::Packet(new_packet)) = (old_data, new_data) {
+            let packet_spec = spec.get_packet_spec(new_packet.header.channel, new_packet.header.destination_address, new_packet.header.source_address, new_packet.command);
+
+            let old_frame_data = old_packet.valid_frame_data();
+            let new_frame_data = new_packet.valid_frame_data();
+
+            for (field_index, field_spec) in packet_spec.fields.iter().enumerate() {
+                let old_raw_value = field_spec.raw_value_i64(old_frame_data);
+                let new_raw_value = field_spec.raw_value_i64(new_frame_data);
+
+                if deadbands.is_significant(field_spec, old_raw_value, new_raw_value) {
+                    changes.push(Change::FieldChanged(FieldChange {
+                        data: new_data,
+                        packet_spec: packet_spec.clone(),
+                        field_index,
+                        old_raw_value,
+                        new_raw_value,
+                    }));
+                }
+            }
+        }
+    }
+
+    for old_data in old.iter() {
+        if new.find_data(old_data).is_none() {
+            changes.push(Change::DataRemoved(old_data));
+        }
+    }
+
+    changes
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, UTC};
+
+    use live_data_decoder::data_from_checked_bytes;
+    use specification_file::{Language, SpecificationFile};
+
+    use super::*;
+
+    use test_data::{data_set_with_temperature, LIVE_DATA_1, LIVE_TELEGRAM_1};
+
+    #[test]
+    fn test_deadbands() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+        let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+        let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+
+        let mut deadbands = Deadbands::new();
+        assert!(!deadbands.is_significant(field_spec, Some(215), Some(215)));
+        assert!(deadbands.is_significant(field_spec, Some(215), Some(216)));
+        assert!(!deadbands.is_significant(field_spec, None, None));
+        assert!(deadbands.is_significant(field_spec, Some(215), None));
+        assert!(deadbands.is_significant(field_spec, None, Some(215)));
+
+        deadbands.set(UnitFamily::Temperature, 1.0);
+        deadbands.set(UnitFamily::Temperature, 0.5);
+        assert_eq!(Some(0.5), deadbands.get(UnitFamily::Temperature));
+        assert!(!deadbands.is_significant(field_spec, Some(215), Some(220)));
+        assert!(!deadbands.is_significant(field_spec, Some(215), Some(210)));
+        assert!(deadbands.is_significant(field_spec, Some(215), Some(221)));
+        assert!(deadbands.is_significant(field_spec, Some(215), Some(209)));
+        assert!(deadbands.is_significant(field_spec, Some(215), None));
+    }
+
+    #[test]
+    fn test_diff() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let timestamp = UTC.timestamp(1485688933, 0);
+        let channel = 0x11;
+
+        let mut old = data_set_with_temperature(&spec, channel, timestamp, Some(215));
+        old.add_data(data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [352..]));
+
+        let mut new = DataSet::from_data(timestamp, vec![
+            data_from_checked_bytes(timestamp, channel, &LIVE_TELEGRAM_1 [0..]),
+        ]);
+        new.add_data_set(data_set_with_temperature(&spec, channel, timestamp, Some(215)));
+
+        let mut deadbands = Deadbands::new();
+        deadbands.set(UnitFamily::Temperature, 0.5);
+
+        let changes = diff(&spec, &old, &new, &deadbands);
+        assert_eq!(2, changes.len());
+        assert_eq!("+ 11_7771_2011_30_25", format!("{}", changes [0]));
+        assert_eq!("- 11_0000_7E11_20_0500_0000", format!("{}", changes [1]));
+
+        new.add_data_set(data_set_with_temperature(&spec, channel, timestamp, Some(219)));
+        let changes = diff(&spec, &old, &new, &deadbands);
+        assert_eq!(2, changes.len());
+
+        new.add_data_set(data_set_with_temperature(&spec, channel, timestamp, Some(221)));
+        let changes = diff(&spec, &old, &new, &deadbands);
+        assert_eq!(3, changes.len());
+        assert_eq!("~ 11_0010_7E11_10_0100_000_2_0: 21.5 °C -> 22.1 °C", format!("{}", changes [1]));
+
+        match changes [1] {
+            Change::FieldChanged(ref change) => {
+                assert_eq!("11_0010_7E11_10_0100_000_2_0", change.packet_field_id().packet_field_id_string());
+                assert_eq!(Some(215), change.old_raw_value);
+                assert_eq!(Some(221), change.new_raw_value);
+                assert_eq!(Some(22.1), change.new_value().unwrap().as_f64());
+            },
+            _ => panic!("Unexpected change {:?}", changes [1]),
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! A module that keeps a bounded in-memory history of `DataSet` snapshots.
+//!
+//! The `DataSetHistory` stores a snapshot of each `DataSet` pushed into it and allows to query
+//! the `DataSet` or the value of a single packet field at a given point in time as well as all
+//! values of a field within a time range, e.g. to draw a sparkline.
+//!
+//! The history can be bounded by the number of snapshots and by their age relative to the most
+//! recent snapshot. The frame data of `Packet` values is shared between consecutive snapshots
+//! as long as it does not change, so that snapshots of a slowly changing `DataSet` only require
+//! little memory.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{DataSet, PacketFieldId, PacketId, Specification};
+//! use resol_vbus::chrono::Duration;
+//! use resol_vbus::data_set_history::DataSetHistory;
+//!
+//! # #[allow(dead_code)]
+//! fn print_sparkline(spec: &Specification, history: &mut DataSetHistory, data_set: &DataSet) {
+//!     history.push(data_set);
+//!
+//!     let id = PacketFieldId(PacketId(0x00, 0x0010, 0x7E11, 0x0100), "000_2_0");
+//!     let end = data_set.timestamp;
+//!     let start = end - Duration::minutes(10);
+//!     for (timestamp, raw_value) in history.raw_values_in_range(spec, id, start, end) {
+//!         println!("{}: {:?}", timestamp, raw_value);
+//!     }
+//! }
+//!
+//! let _history = DataSetHistory::new().with_max_len(600).with_max_age(Duration::hours(1));
+//! ```
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, UTC};
+
+use data::Data;
+use data_set::DataSet;
+use datagram::Datagram;
+use header::Header;
+use packet::{Packet, PacketFieldId, PacketId};
+use specification::Specification;
+use telegram::Telegram;
+
+
+#[derive(Debug)]
+enum StoredData {
+    Packet {
+        header: Header,
+        command: u16,
+        frame_data: Arc<[u8]>,
+    },
+    Datagram(Datagram),
+    Telegram(Telegram),
+}
+
+
+impl StoredData {
+
+    fn packet_id(&self) -> Option<PacketId> {
+        match *self {
+            StoredData::Packet { ref header, command, .. } => Some(PacketId(header.channel, header.destination_address, header.source_address, command)),
+            _ => None,
+        }
+    }
+
+    fn to_data(&self) -> Data {
+        match *self {
+            StoredData::Packet { ref header, command, ref frame_data } => {
+                let mut packet = Packet {
+                    header: header.clone(),
+                    command,
+                    frame_count: (frame_data.len() / 4) as u8,
+                    frame_data: [0u8; 508],
+                };
+                packet.frame_data [0..frame_data.len()].copy_from_slice(frame_data);
+                Data::Packet(packet)
+            },
+            StoredData::Datagram(ref dgram) => Data::Datagram(dgram.clone()),
+            StoredData::Telegram(ref tgram) => Data::Telegram(tgram.clone()),
+        }
+    }
+
+}
+
+
+#[derive(Debug)]
+struct Snapshot {
+    timestamp: DateTime<UTC>,
+    data: Vec<StoredData>,
+}
+
+
+impl Snapshot {
+
+    fn frame_data(&self, packet_id: PacketId) -> Option<&Arc<[u8]>> {
+        self.data.iter().find_map(|stored_data| match *stored_data {
+            StoredData::Packet { ref frame_data, .. } if stored_data.packet_id() == Some(packet_id) => Some(frame_data),
+            _ => None,
+        })
+    }
+
+}
+
+
+/// A bounded in-memory history of `DataSet` snapshots.
+#[derive(Debug, Default)]
+pub struct DataSetHistory {
+    snapshots: VecDeque<Snapshot>,
+    max_len: Option<usize>,
+    max_age: Option<Duration>,
+}
+
+
+impl DataSetHistory {
+
+    /// Construct an empty and unbounded `DataSetHistory`.
+    pub fn new() -> DataSetHistory {
+        DataSetHistory::default()
+    }
+
+    /// Limit the number of snapshots kept in the history.
+    pub fn with_max_len(mut self, max_len: usize) -> DataSetHistory {
+        self.max_len = Some(max_len);
+        self.prune();
+        self
+    }
+
+    /// Limit the age of the snapshots kept in the history, relative to the most recent one.
+    pub fn with_max_age(mut self, max_age: Duration) -> DataSetHistory {
+        self.max_age = Some(max_age);
+        self.prune();
+        self
+    }
+
+    /// Return the number of snapshots in the history.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Return whether the history is empty.
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Return the timestamps of the oldest and the most recent snapshot, if any.
+    pub fn time_range(&self) -> Option<(DateTime<UTC>, DateTime<UTC>)> {
+        let first = self.snapshots.front()?;
+        let last = self.snapshots.back()?;
+        Some((first.timestamp, last.timestamp))
+    }
+
+    fn prune(&mut self) {
+        if let Some(max_len) = self.max_len {
+            while self.snapshots.len() > max_len {
+                self.snapshots.pop_front();
+            }
+        }
+
+        if let Some(max_age) = self.max_age {
+            if let Some(newest) = self.snapshots.back().map(|snapshot| snapshot.timestamp) {
+                while self.snapshots.front().is_some_and(|snapshot| newest.signed_duration_since(snapshot.timestamp) > max_age) {
+                    self.snapshots.pop_front();
+                }
+            }
+        }
+    }
+
+    /// Add a snapshot of a `DataSet` to the history.
+    ///
+    /// The snapshot is recorded at the `DataSet`'s timestamp. Snapshots must be pushed in
+    /// chronological order, so a `DataSet` older than the most recent snapshot is ignored. A
+    /// `DataSet` with the same timestamp as the most recent snapshot replaces it.
+    pub fn push(&mut self, data_set: &DataSet) {
+        let timestamp = data_set.timestamp;
+
+        match self.snapshots.back() {
+            Some(last) if last.timestamp > timestamp => return,
+            Some(last) if last.timestamp == timestamp => {
+                self.snapshots.pop_back();
+            },
+            _ => {},
+        }
+
+        let data = {
+            let previous = self.snapshots.back();
+
+            data_set.iter().map(|data| match *data {
+                Data::Packet(ref packet) => {
+                    let valid_frame_data = packet.valid_frame_data();
+
+                    let frame_data = match previous.and_then(|previous| previous.frame_data(packet.packet_id())) {
+                        Some(frame_data) if &frame_data [..] == valid_frame_data => frame_data.clone(),
+                        _ => Arc::from(valid_frame_data),
+                    };
+
+                    StoredData::Packet {
+                        header: packet.header.clone(),
+                        command: packet.command,
+                        frame_data,
+                    }
+                },
+                Data::Datagram(ref dgram) => StoredData::Datagram(dgram.clone()),
+                Data::Telegram(ref tgram) => StoredData::Telegram(tgram.clone()),
+            }).collect()
+        };
+
+        self.snapshots.push_back(Snapshot {
+            timestamp,
+            data,
+        });
+
+        self.prune();
+    }
+
+    /// Remove all snapshots from the history.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    fn snapshot_at(&self, timestamp: DateTime<UTC>) -> Option<&Snapshot> {
+        let index = self.snapshots.partition_point(|snapshot| snapshot.timestamp <= timestamp);
+        if index > 0 {
+            self.snapshots.get(index - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Get the `DataSet` as it was at the given point in time.
+    ///
+    /// Returns `None` if the point in time is before the oldest snapshot.
+    pub fn data_set_at(&self, timestamp: DateTime<UTC>) -> Option<DataSet> {
+        let snapshot = self.snapshot_at(timestamp)?;
+        let data = snapshot.data.iter().map(StoredData::to_data).collect();
+        Some(DataSet::from_data(snapshot.timestamp, data))
+    }
+
+    /// Get the raw value of a packet field as it was at the given point in time.
+    pub fn raw_value_at(&self, spec: &Specification, id: PacketFieldId<'_>, timestamp: DateTime<UTC>) -> Option<i64> {
+        let packet_spec = spec.get_packet_spec_by_id(id.0);
+        let field_spec = packet_spec.get_field_spec(id.1)?;
+
+        let frame_data = self.snapshot_at(timestamp)?.frame_data(id.0)?;
+        field_spec.raw_value_i64(frame_data)
+    }
+
+    /// Get the raw values of a packet field for all snapshots within a time range (inclusive).
+    ///
+    /// The raw value is `None` for snapshots that do not contain the field's packet.
+    pub fn raw_values_in_range(&self, spec: &Specification, id: PacketFieldId<'_>, start: DateTime<UTC>, end: DateTime<UTC>) -> Vec<(DateTime<UTC>, Option<i64>)> {
+        let packet_spec = spec.get_packet_spec_by_id(id.0);
+        let field_spec = match packet_spec.get_field_spec(id.1) {
+            Some(field_spec) => field_spec,
+            None => return Vec::new(),
+        };
+
+        let first = self.snapshots.partition_point(|snapshot| snapshot.timestamp < start);
+
+        self.snapshots.iter()
+            .skip(first)
+            .take_while(|snapshot| snapshot.timestamp <= end)
+            .map(|snapshot| {
+                let raw_value = snapshot.frame_data(id.0).and_then(|frame_data| field_spec.raw_value_i64(frame_data));
+                (snapshot.timestamp, raw_value)
+            })
+            .collect()
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, UTC};
+
+    use live_data_decoder::data_from_checked_bytes;
+    use specification_file::{Language, SpecificationFile};
+
+    use super::*;
+
+    use test_data::{data_set_with_temperature, LIVE_TELEGRAM_1};
+
+    const FIELD_ID: PacketFieldId<'static> = PacketFieldId(PacketId(0x11, 0x0010, 0x7E11, 0x0100), "000_2_0");
+
+    fn shared_frame_data(history: &DataSetHistory, left: usize, right: usize) -> bool {
+        let left = history.snapshots [left].frame_data(FIELD_ID.0).unwrap();
+        let right = history.snapshots [right].frame_data(FIELD_ID.0).unwrap();
+        Arc::ptr_eq(left, right)
+    }
+
+    #[test]
+    fn test_queries() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let t0 = UTC.timestamp(1485688933, 0);
+
+        let mut history = DataSetHistory::new();
+        assert!(history.is_empty());
+        assert_eq!(None, history.time_range());
+
+        history.push(&data_set_with_temperature(&spec, 0x11, t0, Some(215)));
+        history.push(&data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(10), Some(215)));
+
+        let mut data_set = data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(20), Some(220));
+        data_set.add_data(data_from_checked_bytes(t0 + Duration::seconds(20), 0x11, &LIVE_TELEGRAM_1 [0..]));
+        history.push(&data_set);
+
+        history.push(&data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(30), Some(225)));
+
+        // older than the most recent snapshot
+        history.push(&data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(5), Some(0)));
+
+        assert_eq!(4, history.len());
+        assert_eq!(Some((t0, t0 + Duration::seconds(30))), history.time_range());
+
+        assert!(shared_frame_data(&history, 0, 1));
+        assert!(!shared_frame_data(&history, 1, 2));
+
+        assert_eq!(None, history.raw_value_at(&spec, FIELD_ID, t0 - Duration::seconds(1)));
+        assert_eq!(Some(215), history.raw_value_at(&spec, FIELD_ID, t0));
+        assert_eq!(Some(215), history.raw_value_at(&spec, FIELD_ID, t0 + Duration::seconds(19)));
+        assert_eq!(Some(220), history.raw_value_at(&spec, FIELD_ID, t0 + Duration::seconds(20)));
+        assert_eq!(Some(225), history.raw_value_at(&spec, FIELD_ID, t0 + Duration::days(1)));
+        assert_eq!(None, history.raw_value_at(&spec, PacketFieldId(FIELD_ID.0, "999_2_0"), t0));
+
+        let values = history.raw_values_in_range(&spec, FIELD_ID, t0 + Duration::seconds(5), t0 + Duration::seconds(20));
+        assert_eq!(vec![
+            (t0 + Duration::seconds(10), Some(215)),
+            (t0 + Duration::seconds(20), Some(220)),
+        ], values);
+
+        let data_set = history.data_set_at(t0 + Duration::seconds(25)).unwrap();
+        assert_eq!(t0 + Duration::seconds(20), data_set.timestamp);
+        assert_eq!(2, data_set.len());
+        let fields = spec.fields_in_data_set(&data_set).collect::<Vec<_>>();
+        assert_eq!(&Some(220), fields [0].raw_value_i64());
+        assert_eq!("11_7771_2011_30_25", data_set.as_data_slice() [1].id_string());
+
+        // same timestamp replaces the most recent snapshot
+        history.push(&data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(30), Some(230)));
+        assert_eq!(4, history.len());
+        assert_eq!(Some(230), history.raw_value_at(&spec, FIELD_ID, t0 + Duration::seconds(30)));
+
+        history.clear();
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn test_bounds() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let t0 = UTC.timestamp(1485688933, 0);
+
+        let mut history = DataSetHistory::new().with_max_len(3);
+        for i in 0..5 {
+            history.push(&data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(i * 10), Some(200 + i)));
+        }
+        assert_eq!(3, history.len());
+        assert_eq!(Some((t0 + Duration::seconds(20), t0 + Duration::seconds(40))), history.time_range());
+
+        let mut history = DataSetHistory::new().with_max_age(Duration::seconds(15));
+        for i in 0..5 {
+            history.push(&data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(i * 10), Some(200 + i)));
+        }
+        assert_eq!(2, history.len());
+        assert_eq!(Some((t0 + Duration::seconds(30), t0 + Duration::seconds(40))), history.time_range());
+
+        let history = history.with_max_len(1);
+        assert_eq!(Some((t0 + Duration::seconds(40), t0 + Duration::seconds(40))), history.time_range());
+    }
+}
@@ -21,6 +21,7 @@ use header::Header;
 ///
 /// [1]: struct.Header.html#the-identity-of-header-values
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Datagram {
     /// The shared `Header` of all VBus protocol types.
     pub header: Header,
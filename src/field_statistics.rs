@@ -0,0 +1,556 @@
+//! A module that aggregates the values of packet fields over fixed time windows.
+//!
+//! The `StatisticsAggregator` consumes a stream of `DataSet`s and calculates the minimum,
+//! maximum, mean and last value as well as the number of samples for each packet field. Once a
+//! `DataSet` falls into a new window, the `StatisticsRow` of the completed window is returned, so
+//! that it can be written to e.g. a CSV file or a database.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::io::{Read, Result};
+//!
+//! use resol_vbus::{Language, RecordingReader, Specification, SpecificationFile};
+//! use resol_vbus::chrono::{Duration, Local};
+//! use resol_vbus::field_statistics::{StatisticsAggregator, StatisticsRow};
+//!
+//! fn print_row(spec: &Specification, row: &StatisticsRow<Local>) {
+//!     for field in &row.fields {
+//!         let packet_spec = spec.get_packet_spec_by_id(field.packet_id);
+//!         let field_spec = packet_spec.get_field_spec(&field.field_id).unwrap();
+//!         println!("{} {}: min = {}, max = {}, mean = {}", row.start, field_spec.name, field.min, field.max, field.mean());
+//!     }
+//! }
+//!
+//! # #[allow(dead_code)]
+//! fn print_hourly_statistics<R: Read>(r: R) -> Result<()> {
+//!     let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+//!
+//!     let mut aggregator = StatisticsAggregator::new(Local, Duration::hours(1));
+//!
+//!     let mut rr = RecordingReader::new(r);
+//!     while let Some(data_set) = rr.read_data_set()? {
+//!         if let Some(row) = aggregator.add_data_set(&spec, &data_set) {
+//!             print_row(&spec, &row);
+//!         }
+//!     }
+//!
+//!     if let Some(row) = aggregator.flush() {
+//!         print_row(&spec, &row);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, Offset, TimeZone, Timelike, UTC};
+
+use data_set::DataSet;
+use packet::{PacketFieldId, PacketId};
+use specification::Specification;
+
+
+/// The statistics of a single packet field within a window.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FieldStatistics {
+    /// The `PacketId` of the packet containing the field.
+    pub packet_id: PacketId,
+
+    /// The field ID.
+    pub field_id: String,
+
+    /// The number of values that were aggregated.
+    pub count: usize,
+
+    /// The minimum value.
+    pub min: f64,
+
+    /// The maximum value.
+    pub max: f64,
+
+    /// The sum of all values.
+    pub sum: f64,
+
+    /// The most recent value.
+    pub last: f64,
+}
+
+
+impl FieldStatistics {
+
+    fn new(packet_id: PacketId, field_id: &str, value: f64) -> FieldStatistics {
+        FieldStatistics {
+            packet_id,
+            field_id: field_id.to_owned(),
+            count: 1,
+            min: value,
+            max: value,
+            sum: value,
+            last: value,
+        }
+    }
+
+    fn add_value(&mut self, value: f64) {
+        self.count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.last = value;
+    }
+
+    /// Return the `PacketFieldId` of the field.
+    pub fn packet_field_id(&self) -> PacketFieldId<'_> {
+        PacketFieldId(self.packet_id, &self.field_id)
+    }
+
+    /// Return the mean of all values.
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+
+}
+
+
+/// The aggregated statistics of all packet fields within a window.
+#[derive(Clone, Debug)]
+pub struct StatisticsRow<Tz: TimeZone> {
+    /// The start of the window (inclusive).
+    pub start: DateTime<Tz>,
+
+    /// The end of the window (exclusive).
+    pub end: DateTime<Tz>,
+
+    /// The statistics of each field in the order they were first seen within the window.
+    pub fields: Vec<FieldStatistics>,
+}
+
+
+#[derive(Debug)]
+struct Window {
+    start: DateTime<UTC>,
+    end: DateTime<UTC>,
+    fields: Vec<FieldStatistics>,
+    positions: HashMap<(PacketId, usize), usize>,
+}
+
+
+/// Aggregates the values of packet fields over fixed time windows.
+///
+/// Windows are aligned to the local midnight of the given timezone, so for example one-hour
+/// windows start at the full hour and one-day windows start at midnight in local time. The
+/// window boundaries are UTC instants: windows spanning a DST transition are shortened or
+/// extended accordingly, and local times that are repeated at the end of DST fall into separate
+/// windows if the interval fits into the repeated time range.
+///
+/// Each `DataSet` passed to `add_data_set` counts as one sample for all of its fields that have
+/// a value, using the `DataSet`'s timestamp to select the window. `DataSet`s that are older than
+/// the current window are ignored. Windows without any `DataSet` are skipped.
+#[derive(Debug)]
+pub struct StatisticsAggregator<Tz: TimeZone> {
+    timezone: Tz,
+    interval: Duration,
+    window: Option<Window>,
+}
+
+
+impl<Tz: TimeZone> StatisticsAggregator<Tz> {
+
+    /// Construct a new `StatisticsAggregator`.
+    ///
+    /// # Panics
+    ///
+    /// The `interval` must be a positive number of seconds that evenly divides a day.
+    pub fn new(timezone: Tz, interval: Duration) -> StatisticsAggregator<Tz> {
+        let interval_secs = interval.num_seconds();
+        assert!(interval_secs > 0 && 86400 % interval_secs == 0, "Interval must evenly divide a day");
+
+        StatisticsAggregator {
+            timezone,
+            interval,
+            window: None,
+        }
+    }
+
+    fn utc_offset(&self, timestamp: &DateTime<UTC>) -> i32 {
+        timestamp.with_timezone(&self.timezone).offset().fix().local_minus_utc()
+    }
+
+    fn local_window_start(&self, local: &NaiveDateTime) -> NaiveDateTime {
+        let seconds = local.num_seconds_from_midnight() as i64;
+        let interval_secs = self.interval.num_seconds();
+        local.date().and_hms(0, 0, 0) + Duration::seconds(seconds - seconds % interval_secs)
+    }
+
+    fn window_start(&self, timestamp: &DateTime<UTC>) -> DateTime<UTC> {
+        let local_start = self.local_window_start(&timestamp.with_timezone(&self.timezone).naive_local());
+
+        match self.timezone.from_local_datetime(&local_start) {
+            LocalResult::Single(start) => start.with_timezone(&UTC),
+            // a local time repeated at the end of DST, use the offset in effect at the timestamp
+            LocalResult::Ambiguous(earliest, latest) => {
+                let latest = latest.with_timezone(&UTC);
+                if latest <= *timestamp {
+                    latest
+                } else {
+                    earliest.with_timezone(&UTC)
+                }
+            }
+            LocalResult::None => self.resolve_local(local_start),
+        }
+    }
+
+    fn window_end(&self, start: &DateTime<UTC>) -> DateTime<UTC> {
+        let local = start.with_timezone(&self.timezone).naive_local();
+        let local_start = self.local_window_start(&local);
+
+        let end = *start + self.interval;
+        if local == local_start && self.utc_offset(start) == self.utc_offset(&end) {
+            return end;
+        }
+
+        // the UTC offset changes within the window (or its start was moved out of a skipped
+        // local time range), so the window ends at the next aligned local time, unless the
+        // whole window lies within a repeated local time range
+        let local_end = local_start + self.interval;
+        match self.timezone.from_local_datetime(&(local_end - Duration::seconds(1))) {
+            LocalResult::Ambiguous(..) => end,
+            _ => self.resolve_local(local_end),
+        }
+    }
+
+    fn resolve_local(&self, local: NaiveDateTime) -> DateTime<UTC> {
+        // local times skipped by a DST transition are moved forward to the next valid local time
+        let mut local = local;
+        loop {
+            if let Some(timestamp) = self.timezone.from_local_datetime(&local).earliest() {
+                return timestamp.with_timezone(&UTC);
+            }
+            local = local + Duration::minutes(1);
+        }
+    }
+
+    /// Add the field values of a `DataSet`, returning the `StatisticsRow` of the previous window
+    /// if the `DataSet` belongs to a newer one.
+    pub fn add_data_set(&mut self, spec: &Specification, data_set: &DataSet) -> Option<StatisticsRow<Tz>> {
+        let timestamp = data_set.timestamp;
+
+        let row = match self.window {
+            Some(ref window) if timestamp < window.start => return None,
+            Some(ref window) if timestamp < window.end => None,
+            _ => self.flush(),
+        };
+
+        let window = match self.window {
+            Some(ref mut window) => window,
+            None => {
+                let start = self.window_start(&timestamp);
+                let end = self.window_end(&start);
+                self.window.get_or_insert(Window {
+                    start,
+                    end,
+                    fields: Vec::new(),
+                    positions: HashMap::new(),
+                })
+            }
+        };
+
+        for field in spec.fields_in_data_set(data_set) {
+            let value = match field.raw_value_f64() {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let key = (field.packet_id(), field.field_index());
+            match window.positions.get(&key) {
+                Some(&position) => window.fields [position].add_value(value),
+                None => {
+                    window.positions.insert(key, window.fields.len());
+                    window.fields.push(FieldStatistics::new(key.0, field.field_id(), value));
+                }
+            }
+        }
+
+        row
+    }
+
+    /// Return the `StatisticsRow` of the current window, if any, and start over.
+    pub fn flush(&mut self) -> Option<StatisticsRow<Tz>> {
+        let window = self.window.take()?;
+        Some(StatisticsRow {
+            start: window.start.with_timezone(&self.timezone),
+            end: window.end.with_timezone(&self.timezone),
+            fields: window.fields,
+        })
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, NaiveDate};
+
+    use specification_file::{Language, SpecificationFile};
+
+    use super::*;
+
+    use test_data::data_set_with_temperature;
+
+    #[test]
+    fn test_aggregate() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let mut aggregator = StatisticsAggregator::new(UTC, Duration::minutes(5));
+
+        let t0 = UTC.ymd(2017, 1, 29).and_hms(11, 22, 13);
+        assert!(aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0, Some(215))).is_none());
+        assert!(aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(60), Some(225))).is_none());
+        assert!(aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(90), None)).is_none());
+        assert!(aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(120), Some(205))).is_none());
+
+        // older than the current window
+        assert!(aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 - Duration::seconds(300), Some(0))).is_none());
+
+        let row = aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(600), Some(300))).unwrap();
+        assert_eq!(UTC.ymd(2017, 1, 29).and_hms(11, 20, 0), row.start);
+        assert_eq!(UTC.ymd(2017, 1, 29).and_hms(11, 25, 0), row.end);
+
+        let field = &row.fields [0];
+        assert_eq!("11_0010_7E11_10_0100_000_2_0", field.packet_field_id().packet_field_id_string());
+        assert_eq!(3, field.count);
+        assert_eq!(20.5, field.min);
+        assert_eq!(22.5, field.max);
+        assert!((field.mean() - 21.5).abs() < 0.000001);
+        assert_eq!(20.5, field.last);
+
+        let row = aggregator.flush().unwrap();
+        assert_eq!(UTC.ymd(2017, 1, 29).and_hms(11, 30, 0), row.start);
+        assert_eq!(1, row.fields [0].count);
+        assert_eq!(30.0, row.fields [0].last);
+
+        assert!(aggregator.flush().is_none());
+    }
+
+    #[test]
+    fn test_timezone() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let timezone = FixedOffset::east(3600);
+        let mut aggregator = StatisticsAggregator::new(timezone, Duration::days(1));
+
+        let t0 = UTC.ymd(2017, 1, 29).and_hms(22, 30, 0);
+        assert!(aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0, Some(215))).is_none());
+
+        let row = aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::hours(1), Some(225))).unwrap();
+        assert_eq!(timezone.ymd(2017, 1, 29).and_hms(0, 0, 0), row.start);
+        assert_eq!(timezone.ymd(2017, 1, 30).and_hms(0, 0, 0), row.end);
+        assert_eq!(UTC.ymd(2017, 1, 28).and_hms(23, 0, 0), row.start);
+    }
+
+    /// A timezone that switches from UTC+2 to UTC+1 at 2017-10-29 01:00:00 UTC.
+    #[derive(Clone, Copy, Debug)]
+    struct FallBack;
+
+    impl FallBack {
+        fn switch() -> NaiveDateTime {
+            NaiveDate::from_ymd(2017, 10, 29).and_hms(1, 0, 0)
+        }
+    }
+
+    impl TimeZone for FallBack {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> FallBack {
+            FallBack
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let summer = FixedOffset::east(7200);
+            let winter = FixedOffset::east(3600);
+            match (*local - summer < FallBack::switch(), *local - winter >= FallBack::switch()) {
+                (true, true) => LocalResult::Ambiguous(summer, winter),
+                (true, false) => LocalResult::Single(summer),
+                (false, true) => LocalResult::Single(winter),
+                (false, false) => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < FallBack::switch() {
+                FixedOffset::east(7200)
+            } else {
+                FixedOffset::east(3600)
+            }
+        }
+    }
+
+    /// A timezone that switches from UTC+1 to UTC+2 at 2017-03-26 01:00:00 UTC.
+    #[derive(Clone, Copy, Debug)]
+    struct SpringForward;
+
+    impl SpringForward {
+        fn switch() -> NaiveDateTime {
+            NaiveDate::from_ymd(2017, 3, 26).and_hms(1, 0, 0)
+        }
+    }
+
+    impl TimeZone for SpringForward {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> SpringForward {
+            SpringForward
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let winter = FixedOffset::east(3600);
+            let summer = FixedOffset::east(7200);
+            match (*local - winter < SpringForward::switch(), *local - summer >= SpringForward::switch()) {
+                (true, true) => LocalResult::Ambiguous(winter, summer),
+                (true, false) => LocalResult::Single(winter),
+                (false, true) => LocalResult::Single(summer),
+                (false, false) => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms(0, 0, 0))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            if *utc < SpringForward::switch() {
+                FixedOffset::east(3600)
+            } else {
+                FixedOffset::east(7200)
+            }
+        }
+    }
+
+    #[test]
+    fn test_dst_transition() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let add = |aggregator: &mut StatisticsAggregator<FallBack>, timestamp, raw_value| {
+            aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, timestamp, Some(raw_value)))
+        };
+
+        // local times 02:00 to 03:00 occur twice, first at UTC+2, then at UTC+1
+        let mut aggregator = StatisticsAggregator::new(FallBack, Duration::minutes(5));
+
+        assert!(add(&mut aggregator, UTC.ymd(2017, 10, 29).and_hms(0, 57, 0), 215).is_none());
+
+        let row = add(&mut aggregator, UTC.ymd(2017, 10, 29).and_hms(1, 1, 0), 225).unwrap();
+        assert_eq!(UTC.ymd(2017, 10, 29).and_hms(0, 55, 0), row.start);
+        assert_eq!(UTC.ymd(2017, 10, 29).and_hms(1, 0, 0), row.end);
+
+        let row = aggregator.flush().unwrap();
+        assert_eq!(UTC.ymd(2017, 10, 29).and_hms(1, 0, 0), row.start);
+        assert_eq!("2017-10-29 02:00:00 +01:00", format!("{}", row.start));
+        assert_eq!(22.5, row.fields [0].last);
+
+        let mut aggregator = StatisticsAggregator::new(FallBack, Duration::hours(1));
+
+        assert!(add(&mut aggregator, UTC.ymd(2017, 10, 29).and_hms(0, 30, 0), 215).is_none());
+
+        let row = add(&mut aggregator, UTC.ymd(2017, 10, 29).and_hms(1, 30, 0), 225).unwrap();
+        assert_eq!("2017-10-29 02:00:00 +02:00", format!("{}", row.start));
+        assert_eq!("2017-10-29 02:00:00 +01:00", format!("{}", row.end));
+
+        let row = aggregator.flush().unwrap();
+        assert_eq!("2017-10-29 02:00:00 +01:00", format!("{}", row.start));
+        assert_eq!("2017-10-29 03:00:00 +01:00", format!("{}", row.end));
+
+        // windows larger than the repeated time range are extended
+        let mut aggregator = StatisticsAggregator::new(FallBack, Duration::hours(2));
+
+        assert!(add(&mut aggregator, UTC.ymd(2017, 10, 29).and_hms(0, 30, 0), 215).is_none());
+        assert!(add(&mut aggregator, UTC.ymd(2017, 10, 29).and_hms(1, 30, 0), 225).is_none());
+
+        let row = aggregator.flush().unwrap();
+        assert_eq!("2017-10-29 02:00:00 +02:00", format!("{}", row.start));
+        assert_eq!("2017-10-29 04:00:00 +01:00", format!("{}", row.end));
+        assert_eq!(2, row.fields [0].count);
+
+        let mut aggregator = StatisticsAggregator::new(FallBack, Duration::days(1));
+
+        assert!(add(&mut aggregator, UTC.ymd(2017, 10, 28).and_hms(22, 0, 0), 215).is_none());
+        assert!(add(&mut aggregator, UTC.ymd(2017, 10, 29).and_hms(22, 59, 0), 225).is_none());
+
+        let row = aggregator.flush().unwrap();
+        assert_eq!(UTC.ymd(2017, 10, 28).and_hms(22, 0, 0), row.start);
+        assert_eq!(UTC.ymd(2017, 10, 29).and_hms(23, 0, 0), row.end);
+        assert_eq!(2, row.fields [0].count);
+    }
+
+    #[test]
+    fn test_dst_gap() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let add = |aggregator: &mut StatisticsAggregator<SpringForward>, timestamp, raw_value| {
+            aggregator.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, timestamp, Some(raw_value)))
+        };
+
+        // local times 02:00 to 03:00 do not exist
+        let mut aggregator = StatisticsAggregator::new(SpringForward, Duration::hours(1));
+
+        assert!(add(&mut aggregator, UTC.ymd(2017, 3, 26).and_hms(0, 30, 0), 215).is_none());
+
+        let row = add(&mut aggregator, UTC.ymd(2017, 3, 26).and_hms(1, 30, 0), 225).unwrap();
+        assert_eq!("2017-03-26 01:00:00 +01:00", format!("{}", row.start));
+        assert_eq!("2017-03-26 03:00:00 +02:00", format!("{}", row.end));
+        assert_eq!(UTC.ymd(2017, 3, 26).and_hms(1, 0, 0), row.end);
+
+        let row = aggregator.flush().unwrap();
+        assert_eq!("2017-03-26 03:00:00 +02:00", format!("{}", row.start));
+        assert_eq!("2017-03-26 04:00:00 +02:00", format!("{}", row.end));
+
+        // windows starting within the skipped time range are moved forward, but still end aligned
+        let mut aggregator = StatisticsAggregator::new(SpringForward, Duration::hours(2));
+
+        assert!(add(&mut aggregator, UTC.ymd(2017, 3, 26).and_hms(0, 30, 0), 215).is_none());
+
+        let row = add(&mut aggregator, UTC.ymd(2017, 3, 26).and_hms(1, 30, 0), 225).unwrap();
+        assert_eq!("2017-03-26 00:00:00 +01:00", format!("{}", row.start));
+        assert_eq!("2017-03-26 03:00:00 +02:00", format!("{}", row.end));
+
+        assert!(add(&mut aggregator, UTC.ymd(2017, 3, 26).and_hms(1, 59, 0), 235).is_none());
+
+        let row = add(&mut aggregator, UTC.ymd(2017, 3, 26).and_hms(2, 30, 0), 245).unwrap();
+        assert_eq!("2017-03-26 03:00:00 +02:00", format!("{}", row.start));
+        assert_eq!("2017-03-26 04:00:00 +02:00", format!("{}", row.end));
+        assert_eq!(2, row.fields [0].count);
+
+        let row = aggregator.flush().unwrap();
+        assert_eq!("2017-03-26 04:00:00 +02:00", format!("{}", row.start));
+        assert_eq!("2017-03-26 06:00:00 +02:00", format!("{}", row.end));
+
+        let mut aggregator = StatisticsAggregator::new(SpringForward, Duration::days(1));
+
+        assert!(add(&mut aggregator, UTC.ymd(2017, 3, 25).and_hms(23, 0, 0), 215).is_none());
+        assert!(add(&mut aggregator, UTC.ymd(2017, 3, 26).and_hms(21, 59, 0), 225).is_none());
+
+        let row = add(&mut aggregator, UTC.ymd(2017, 3, 26).and_hms(22, 0, 0), 235).unwrap();
+        assert_eq!(UTC.ymd(2017, 3, 25).and_hms(23, 0, 0), row.start);
+        assert_eq!(UTC.ymd(2017, 3, 26).and_hms(22, 0, 0), row.end);
+        assert_eq!(2, row.fields [0].count);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_interval() {
+        StatisticsAggregator::new(UTC, Duration::minutes(7));
+    }
+}
@@ -0,0 +1,159 @@
+//! A module that provides typed values of packet fields.
+//!
+//! Raw values are plain integers whose meaning depends on the `Type` of the field. A
+//! `FieldValue` interprets the raw value according to that `Type`, so that programs can consume
+//! numbers, times and dates without parsing formatted strings.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{Specification, SpecificationFile, Language, FieldValue};
+//! use resol_vbus::specification_file::UnitFamily;
+//!
+//! let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+//!
+//! let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+//! let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+//!
+//! match field_spec.field_value(1234) {
+//!     FieldValue::Number { value, precision, unit_family, unit_code, .. } => {
+//!         assert_eq!(123.4, value);
+//!         assert_eq!(1, precision);
+//!         assert_eq!(UnitFamily::Temperature, unit_family);
+//!         assert_eq!("DegreesCelsius", unit_code);
+//!     }
+//!     other => panic!("Unexpected value {:?}", other),
+//! }
+//! ```
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, UTC, Weekday};
+
+use specification_extension::EnumType;
+use specification_file::UnitFamily;
+
+
+/// A raw value of a packet field, interpreted according to its `Type`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue<'a> {
+    /// A number (`Type::Number`), scaled by its precision.
+    Number {
+        /// The scaled value.
+        value: f64,
+
+        /// The number of fractional digits of the value.
+        precision: i32,
+
+        /// The `UnitFamily` of the value.
+        unit_family: UnitFamily,
+
+        /// The unit code of the value.
+        unit_code: &'a str,
+
+        /// The unit text of the value.
+        unit_text: &'a str,
+    },
+
+    /// A time of day or duration (`Type::Time`).
+    Time(Duration),
+
+    /// A weekday and time of day (`Type::WeekTime`).
+    WeekTime(Weekday, NaiveTime),
+
+    /// A date and time (`Type::DateTime`).
+    DateTime(DateTime<UTC>),
+
+    /// An enumeration value and its `EnumType` (`EnumKind::Enum`).
+    Enum(i64, &'a EnumType),
+
+    /// A set of bit flags and its `EnumType` (`EnumKind::BitFlags`).
+    BitFlags(i64, &'a EnumType),
+}
+
+
+/// The offset between the UNIX epoch and the VBus epoch (2001-01-01 00:00:00 UTC) in seconds.
+const VBUS_EPOCH_OFFSET: i64 = 978307200;
+
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+
+/// Convert a `Type::Time` raw value in minutes into a `Duration`.
+pub fn time_from_raw_value(raw_value: i64) -> Duration {
+    Duration::minutes(raw_value)
+}
+
+
+/// Convert a `Type::WeekTime` raw value in minutes since Monday 00:00 into a weekday and time of day.
+pub fn week_time_from_raw_value(raw_value: i64) -> (Weekday, NaiveTime) {
+    let weekday = WEEKDAYS [raw_value.div_euclid(1440).rem_euclid(7) as usize];
+    let minutes = raw_value.rem_euclid(1440) as u32;
+    (weekday, NaiveTime::from_hms(minutes / 60, minutes % 60, 0))
+}
+
+
+/// Convert a `Type::DateTime` raw value in seconds since 2001-01-01 00:00:00 UTC into a `DateTime<UTC>`.
+pub fn date_time_from_raw_value(raw_value: i64) -> DateTime<UTC> {
+    UTC.timestamp(raw_value + VBUS_EPOCH_OFFSET, 0)
+}
+
+
+impl<'a> FieldValue<'a> {
+
+    /// Construct a `FieldValue::Time` from a raw value in minutes.
+    pub fn from_time_raw_value(raw_value: i64) -> FieldValue<'a> {
+        FieldValue::Time(time_from_raw_value(raw_value))
+    }
+
+    /// Construct a `FieldValue::WeekTime` from a raw value in minutes since Monday 00:00.
+    pub fn from_week_time_raw_value(raw_value: i64) -> FieldValue<'a> {
+        let (weekday, time) = week_time_from_raw_value(raw_value);
+        FieldValue::WeekTime(weekday, time)
+    }
+
+    /// Construct a `FieldValue::DateTime` from a raw value in seconds since 2001-01-01 00:00:00 UTC.
+    pub fn from_date_time_raw_value(raw_value: i64) -> FieldValue<'a> {
+        FieldValue::DateTime(date_time_from_raw_value(raw_value))
+    }
+
+    /// Get the value of a `FieldValue::Number` as a `f64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            FieldValue::Number { value, .. } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Get the value of a `FieldValue::DateTime`.
+    pub fn as_date_time(&self) -> Option<DateTime<UTC>> {
+        match *self {
+            FieldValue::DateTime(date_time) => Some(date_time),
+            _ => None,
+        }
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_raw_value() {
+        assert_eq!(FieldValue::Time(Duration::minutes(721)), FieldValue::from_time_raw_value(721));
+
+        assert_eq!(FieldValue::WeekTime(Weekday::Thu, NaiveTime::from_hms(12, 1, 0)), FieldValue::from_week_time_raw_value(3 * 1440 + 721));
+        assert_eq!(FieldValue::WeekTime(Weekday::Mon, NaiveTime::from_hms(0, 0, 0)), FieldValue::from_week_time_raw_value(7 * 1440));
+
+        let value = FieldValue::from_date_time_raw_value(409418262);
+        assert_eq!(Some(UTC.timestamp(1387725462, 0)), value.as_date_time());
+        assert_eq!(None, value.as_f64());
+    }
+}
@@ -0,0 +1,183 @@
+//! A module that provides options to format raw values independently of the `Language`.
+//!
+//! By default the `RawValueFormatter` and `PacketFieldFormatter` derive the decimal separator and
+//! the date format from the `Language` of the `Specification`. A `FormatOptions` value replaces
+//! those defaults while the `Language` is still used for names, weekdays and labels.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{Specification, SpecificationFile, Language, FormatOptions};
+//! use resol_vbus::utils::utc_timestamp;
+//!
+//! let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+//!
+//! // English texts, but ISO dates and German number formats
+//! let options = FormatOptions {
+//!     decimal_separator: ",".to_owned(),
+//!     grouping_separator: Some(".".to_owned()),
+//!     date_time_pattern: "%Y-%m-%dT%H:%M:%S".to_owned(),
+//!     ..FormatOptions::for_language(Language::En)
+//! };
+//!
+//! let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+//! let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+//!
+//! assert_eq!("1.234,5 °C", format!("{}", field_spec.fmt_raw_value(Some(12345), true).with_options(&options)));
+//! assert_eq!("2017-01-29T11:22:13", format!("{}", spec.fmt_timestamp(utc_timestamp(1485688933)).with_options(&options)));
+//! ```
+use chrono::{FixedOffset, Local, NaiveTime, UTC};
+
+use specification_file::Language;
+
+
+/// The timezone used to format date and time values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timezone {
+    /// Coordinated Universal Time.
+    Utc,
+
+    /// The local timezone of the system.
+    Local,
+
+    /// A fixed offset from UTC.
+    Fixed(FixedOffset),
+}
+
+
+/// Options to format numbers, times and dates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatOptions {
+    /// The separator between the integer and fractional part of numbers.
+    pub decimal_separator: String,
+
+    /// The separator between groups of three digits in the integer part of numbers, if any.
+    pub grouping_separator: Option<String>,
+
+    /// The `strftime`-like pattern used for times of day (`Type::Time` and `Type::WeekTime`).
+    pub time_pattern: String,
+
+    /// The `strftime`-like pattern used for dates and times (`Type::DateTime` and timestamps).
+    pub date_time_pattern: String,
+
+    /// The timezone used for dates and times.
+    pub timezone: Timezone,
+}
+
+
+impl FormatOptions {
+
+    /// Construct the `FormatOptions` used by default for the given `Language`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{Language, FormatOptions};
+    /// use resol_vbus::format_options::Timezone;
+    ///
+    /// let options = FormatOptions::for_language(Language::De);
+    /// assert_eq!(",", options.decimal_separator);
+    /// assert_eq!(None, options.grouping_separator);
+    /// assert_eq!("%H:%M", options.time_pattern);
+    /// assert_eq!("%d.%m.%Y %H:%M:%S", options.date_time_pattern);
+    /// assert_eq!(Timezone::Utc, options.timezone);
+    /// ```
+    pub fn for_language(language: Language) -> FormatOptions {
+        FormatOptions {
+            decimal_separator: decimal_separator(language).to_owned(),
+            grouping_separator: None,
+            time_pattern: "%H:%M".to_owned(),
+            date_time_pattern: date_time_pattern(language).to_owned(),
+            timezone: Timezone::Utc,
+        }
+    }
+
+    /// Construct `FormatOptions` that use "." as decimal separator and ISO 8601 dates in UTC.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::FormatOptions;
+    ///
+    /// let options = FormatOptions::iso();
+    /// assert_eq!(".", options.decimal_separator);
+    /// assert_eq!("%Y-%m-%d %H:%M:%S", options.date_time_pattern);
+    /// ```
+    pub fn iso() -> FormatOptions {
+        FormatOptions {
+            decimal_separator: ".".to_owned(),
+            grouping_separator: None,
+            time_pattern: "%H:%M".to_owned(),
+            date_time_pattern: "%Y-%m-%d %H:%M:%S".to_owned(),
+            timezone: Timezone::Utc,
+        }
+    }
+
+    pub(crate) fn fmt_time(&self, hours: i64, minutes: i64) -> String {
+        match NaiveTime::from_hms_opt(hours as u32, minutes as u32, 0) {
+            Some(time) if hours >= 0 && minutes >= 0 => format!("{}", time.format(&self.time_pattern)),
+            _ => format!("{:02}:{:02}", hours, minutes),
+        }
+    }
+
+    pub(crate) fn fmt_date_time(&self, timestamp: i64) -> String {
+        let timestamp = ::chrono::TimeZone::timestamp(&UTC, timestamp, 0);
+        match self.timezone {
+            Timezone::Utc => format!("{}", timestamp.format(&self.date_time_pattern)),
+            Timezone::Local => format!("{}", timestamp.with_timezone(&Local).format(&self.date_time_pattern)),
+            Timezone::Fixed(offset) => format!("{}", timestamp.with_timezone(&offset).format(&self.date_time_pattern)),
+        }
+    }
+
+}
+
+
+/// Get the default decimal separator for the given `Language`.
+pub fn decimal_separator(language: Language) -> &'static str {
+    match language {
+        Language::En => ".",
+        Language::De | Language::Fr | Language::It | Language::Es | Language::Nl => ",",
+    }
+}
+
+
+/// Get the default date and time pattern for the given `Language`.
+pub fn date_time_pattern(language: Language) -> &'static str {
+    match language {
+        Language::En | Language::Fr | Language::It | Language::Es => "%d/%m/%Y %H:%M:%S",
+        Language::De => "%d.%m.%Y %H:%M:%S",
+        Language::Nl => "%d-%m-%Y %H:%M:%S",
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+
+    use super::*;
+
+    #[test]
+    fn test_fmt_time() {
+        let options = FormatOptions {
+            time_pattern: "%I:%M %p".to_owned(),
+            ..FormatOptions::iso()
+        };
+
+        assert_eq!("12:01 PM", options.fmt_time(12, 1));
+        assert_eq!("25:01", options.fmt_time(25, 1));
+    }
+
+    #[test]
+    fn test_fmt_date_time() {
+        let options = FormatOptions::iso();
+        assert_eq!("2017-01-29 11:22:13", options.fmt_date_time(1485688933));
+
+        let options = FormatOptions {
+            timezone: Timezone::Fixed(FixedOffset::east(3600)),
+            date_time_pattern: "%Y-%m-%dT%H:%M:%S%z".to_owned(),
+            ..FormatOptions::iso()
+        };
+        assert_eq!("2017-01-29T12:22:13+0100", options.fmt_date_time(1485688933));
+    }
+}
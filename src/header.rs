@@ -33,8 +33,10 @@ use id_hash::IdHash;
 /// This is also respected by the `id_hash` and `id_string` functions. They return the same result
 /// for VBus data values that are considered "identical", allowing some fields to differ.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Header {
     /// The timestamp when this `Header` was received.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_impls::rfc3339"))]
     pub timestamp: DateTime<UTC>,
 
     /// The channel number on which this `Header` was received.
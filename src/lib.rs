@@ -181,6 +181,13 @@
 extern crate byteorder;
 pub extern crate chrono;
 
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
 
 #[cfg(test)]
 mod test_data;
@@ -217,6 +224,19 @@ pub use data::Data;
 mod data_set;
 pub use data_set::DataSet;
 
+pub mod data_set_diff;
+
+pub mod data_set_history;
+
+pub mod field_statistics;
+
+pub mod staleness;
+
+pub mod resampler;
+
+#[cfg(feature = "serde")]
+mod serde_impls;
+
 pub mod live_data_decoder;
 
 pub mod live_data_encoder;
@@ -232,7 +252,7 @@ pub mod recording_decoder;
 pub mod recording_encoder;
 
 mod recording_reader;
-pub use recording_reader::RecordingReader;
+pub use recording_reader::{RecordingReader, ChecksumMismatch};
 
 mod recording_writer;
 pub use recording_writer::RecordingWriter;
@@ -246,9 +266,37 @@ pub use live_data_recording_writer::LiveDataRecordingWriter;
 pub mod specification_file;
 pub use specification_file::{Language, SpecificationFile};
 
+#[cfg(feature = "serde")]
+pub mod specification_source;
+
+pub mod field_value;
+pub use field_value::FieldValue;
+
+pub mod format_options;
+pub use format_options::FormatOptions;
+
+pub mod parameter_catalogue;
+pub use parameter_catalogue::ParameterCatalogue;
+
 pub mod specification;
 pub use specification::{Specification};
 
+pub mod specification_docs;
+
+pub mod code_generator;
+
+pub mod specification_diff;
+
+pub mod specification_lint;
+
+pub mod specification_extension;
+pub use specification_extension::SpecificationExtension;
+
+pub mod translation_table;
+pub use translation_table::TranslationTable;
+
+pub mod unit_conversion;
+
 mod file_list_reader;
 pub use file_list_reader::FileListReader;
 
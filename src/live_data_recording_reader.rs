@@ -125,6 +125,8 @@ impl<T: Read> LiveDataRecordingReader<T> {
                 } else {
                     panic!("Record type 0x88 too small: {}", len);
                 }
+            } else if record [1] == 0xCC {
+                // nop, checksum records are verified by the `RecordingReader`
             } else {
                 panic!("Unexpected record type 0x{:02X}", record [1]);
             }
@@ -223,6 +225,8 @@ impl<T: Read> LiveDataRecordingReader<T> {
                     } else {
                         panic!("Record type 0x88 too small: {}", len);
                     }
+                } else if record [1] == 0xCC {
+                    // nop, checksum records are verified by the `RecordingReader`
                 } else {
                     panic!("Unexpected record type 0x{:02X}", record [1]);
                 }
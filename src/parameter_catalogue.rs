@@ -0,0 +1,457 @@
+//! A module that describes the parameters of controllers that are accessible using `Datagram`s.
+//!
+//! Datagrams that get or set a controller value carry the index of the value in their `param16`
+//! field and the raw value in their `param32` field. A `ParameterCatalogue` maps those indices
+//! to their name, unit, precision and valid range per device address, similar to what the VSF
+//! does for the fields of `Packet`s.
+//!
+//! Catalogue files consist of `[device <address>]` sections containing one parameter per line.
+//! Empty lines and lines starting with `#` are ignored. Each line has the form
+//! `<index> = <name>; <unit code>; <precision>; <min>; <max>; <rw|ro>`, where the unit code and
+//! the minimum and maximum raw values may be empty. Names for other languages are added by
+//! appending the language code to the index (e.g. `0x0123.de = Maximale Speichertemperatur`).
+//! Addresses and indices are either decimal or hexadecimal with a `0x` prefix.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{Specification, SpecificationFile, Language, ParameterCatalogue};
+//! use resol_vbus::{Header, Datagram};
+//! use resol_vbus::utils::utc_timestamp;
+//!
+//! let catalogue = ParameterCatalogue::parse("
+//!     [device 0x7E11]
+//!     0x0123 = Max tank temperature; DegreesCelsius; 1; 200; 950; rw
+//!     0x0123.de = Maximale Speichertemperatur
+//! ").unwrap();
+//!
+//! let mut spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+//! spec.push_parameter_catalogue(catalogue);
+//!
+//! let dgram = Datagram {
+//!     header: Header {
+//!         timestamp: utc_timestamp(1485688933),
+//!         channel: 0x00,
+//!         destination_address: 0x0020,
+//!         source_address: 0x7E11,
+//!         protocol_version: 0x20,
+//!     },
+//!     command: 0x0100,
+//!     param16: 0x0123,
+//!     param32: 600,
+//! };
+//!
+//! assert_eq!("Max tank temperature = 60.0 °C", format!("{}", spec.fmt_datagram(&dgram)));
+//! ```
+use datagram::Datagram;
+use specification_extension::language_by_code;
+use specification_file::Language;
+
+
+/// The datagram command of an answer from a module with a value.
+pub const COMMAND_ANSWER_VALUE: u16 = 0x0100;
+
+/// The datagram command to write a value, requiring an acknowledgement.
+pub const COMMAND_WRITE_VALUE: u16 = 0x0200;
+
+/// The datagram command to read a value, requiring an acknowledgement.
+pub const COMMAND_READ_VALUE: u16 = 0x0300;
+
+
+/// A list of errors that can occur if a `ParameterCatalogue` cannot be parsed.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The line with the given 1-based number is neither a section header nor a valid parameter.
+    InvalidLine(usize),
+
+    /// The line with the given 1-based number appears outside of a section.
+    MissingSection(usize),
+
+    /// The line with the given 1-based number uses an unknown language code.
+    UnknownLanguage(usize),
+
+    /// The line with the given 1-based number adds a name to a parameter that is not defined.
+    UnknownParameter(usize),
+
+    /// The line with the given 1-based number defines a parameter whose index is already used.
+    DuplicateParameter(usize),
+}
+
+
+/// A specialized Result for this module.
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+
+/// A controller value that is accessible using `Datagram`s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameter {
+    /// The index of the value, transmitted in the `param16` field of a `Datagram`.
+    pub index: u16,
+
+    /// The names of the parameter by `Language`.
+    pub names: Vec<(Language, String)>,
+
+    /// The unit code of the value (e.g. "DegreesCelsius"), or an empty string.
+    pub unit_code: String,
+
+    /// The number of fractional digits of the value.
+    pub precision: i32,
+
+    /// The minimum raw value, if any.
+    pub min: Option<i64>,
+
+    /// The maximum raw value, if any.
+    pub max: Option<i64>,
+
+    /// Whether the value can be changed.
+    pub writable: bool,
+}
+
+
+/// A set of `Parameter`s per device address.
+#[derive(Debug, Clone, Default)]
+pub struct ParameterCatalogue {
+    devices: Vec<(u16, Vec<Parameter>)>,
+}
+
+
+fn parse_number(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+
+    let value = match text.strip_prefix("0x") {
+        Some(hex) => i64::from_str_radix(hex, 16).ok()?,
+        None => text.parse().ok()?,
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+
+fn parse_optional_number(text: &str) -> Option<Option<i64>> {
+    if text.trim().is_empty() {
+        Some(None)
+    } else {
+        parse_number(text).map(Some)
+    }
+}
+
+
+fn parse_u16(text: &str) -> Option<u16> {
+    match parse_number(text) {
+        Some(value) if (0..=0xFFFF).contains(&value) => Some(value as u16),
+        _ => None,
+    }
+}
+
+
+fn parse_parameter(index: u16, value: &str) -> Option<Parameter> {
+    let columns = value.split(';').map(|column| column.trim()).collect::<Vec<_>>();
+    if columns.len() != 6 {
+        return None;
+    }
+
+    let writable = match columns [5] {
+        "rw" => true,
+        "ro" => false,
+        _ => return None,
+    };
+
+    Some(Parameter {
+        index,
+        names: vec![ (Language::En, columns [0].to_owned()) ],
+        unit_code: columns [1].to_owned(),
+        precision: columns [2].parse().ok()?,
+        min: parse_optional_number(columns [3])?,
+        max: parse_optional_number(columns [4])?,
+        writable,
+    })
+}
+
+
+impl Parameter {
+
+    /// Get the name for the given `Language`, falling back to English.
+    pub fn name(&self, language: Language) -> &str {
+        let name = self.names.iter().find(|(name_language, _)| *name_language == language)
+            .or_else(|| self.names.iter().find(|(name_language, _)| *name_language == Language::En))
+            .or_else(|| self.names.first());
+
+        match name {
+            Some((_, name)) => name,
+            None => "",
+        }
+    }
+
+    /// Check whether a raw value is within the minimum and maximum of this `Parameter`.
+    pub fn is_in_range(&self, raw_value: i64) -> bool {
+        self.min.is_none_or(|min| raw_value >= min) && self.max.is_none_or(|max| raw_value <= max)
+    }
+
+}
+
+
+impl ParameterCatalogue {
+
+    /// Construct an empty `ParameterCatalogue`.
+    pub fn new() -> ParameterCatalogue {
+        ParameterCatalogue::default()
+    }
+
+    /// Parse a `ParameterCatalogue` from its text representation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{Language, ParameterCatalogue};
+    /// use resol_vbus::parameter_catalogue::Error;
+    ///
+    /// let catalogue = ParameterCatalogue::parse("
+    ///     [device 0x7E11]
+    ///     0x0123 = Max tank temperature; DegreesCelsius; 1; 200; 950; rw
+    ///     0x0124 = Operating hours relay 1; Hours; 0; ; ; ro
+    ///     0x0124.fr = Heures de fonctionnement relais 1
+    /// ").unwrap();
+    ///
+    /// let parameter = catalogue.parameter(0x7E11, 0x0124).unwrap();
+    /// assert_eq!("Heures de fonctionnement relais 1", parameter.name(Language::Fr));
+    /// assert_eq!("Operating hours relay 1", parameter.name(Language::De));
+    /// assert_eq!(None, parameter.max);
+    /// assert!(!parameter.writable);
+    ///
+    /// assert_eq!(Error::UnknownParameter(2), ParameterCatalogue::parse("[device 0x7E11]\n0x0125.de = Unbekannt").unwrap_err());
+    /// ```
+    pub fn parse(input: &str) -> Result<ParameterCatalogue> {
+        let mut catalogue = ParameterCatalogue::new();
+
+        let mut address = None;
+
+        for (index, line) in input.lines().enumerate() {
+            let line_number = index + 1;
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                if !line.ends_with(']') {
+                    return Err(Error::InvalidLine(line_number));
+                }
+
+                let header = line [1..(line.len() - 1)].trim();
+                let mut words = header.split_whitespace();
+                address = match (words.next(), words.next().and_then(parse_u16), words.next()) {
+                    (Some("device"), Some(address), None) => Some(address),
+                    _ => return Err(Error::InvalidLine(line_number)),
+                };
+                continue;
+            }
+
+            let separator = match line.find('=') {
+                Some(separator) => separator,
+                None => return Err(Error::InvalidLine(line_number)),
+            };
+
+            let key = line [..separator].trim();
+            let value = line [(separator + 1)..].trim();
+
+            let address = match address {
+                Some(address) => address,
+                None => return Err(Error::MissingSection(line_number)),
+            };
+
+            let (parameter_index, language) = match key.find('.') {
+                Some(dot) => match language_by_code(&key [(dot + 1)..]) {
+                    Some(language) => (&key [..dot], Some(language)),
+                    None => return Err(Error::UnknownLanguage(line_number)),
+                },
+                None => (key, None),
+            };
+
+            let parameter_index = match parse_u16(parameter_index) {
+                Some(parameter_index) => parameter_index,
+                None => return Err(Error::InvalidLine(line_number)),
+            };
+
+            match language {
+                Some(language) => match catalogue.parameter_mut(address, parameter_index) {
+                    Some(parameter) => {
+                        match parameter.names.iter().position(|(name_language, _)| *name_language == language) {
+                            Some(index) => parameter.names [index].1 = value.to_owned(),
+                            None => parameter.names.push((language, value.to_owned())),
+                        }
+                    }
+                    None => return Err(Error::UnknownParameter(line_number)),
+                },
+                None => {
+                    if catalogue.parameter(address, parameter_index).is_some() {
+                        return Err(Error::DuplicateParameter(line_number));
+                    }
+                    match parse_parameter(parameter_index, value) {
+                        Some(parameter) => catalogue.insert(address, parameter),
+                        None => return Err(Error::InvalidLine(line_number)),
+                    }
+                }
+            }
+        }
+
+        Ok(catalogue)
+    }
+
+    /// Add a `Parameter` for a device address, replacing any parameter with the same index.
+    pub fn insert(&mut self, address: u16, parameter: Parameter) {
+        let device_index = match self.devices.iter().position(|(device_address, _)| *device_address == address) {
+            Some(device_index) => device_index,
+            None => {
+                self.devices.push((address, Vec::new()));
+                self.devices.len() - 1
+            }
+        };
+
+        let parameters = &mut self.devices [device_index].1;
+        match parameters.iter().position(|other| other.index == parameter.index) {
+            Some(index) => parameters [index] = parameter,
+            None => parameters.push(parameter),
+        }
+    }
+
+    /// Get the list of device addresses that have parameters.
+    pub fn addresses(&self) -> Vec<u16> {
+        self.devices.iter().map(|(address, _)| *address).collect()
+    }
+
+    /// Get the list of `Parameter`s of a device address.
+    pub fn parameters(&self, address: u16) -> &[Parameter] {
+        match self.devices.iter().find(|(device_address, _)| *device_address == address) {
+            Some((_, parameters)) => parameters,
+            None => &[],
+        }
+    }
+
+    /// Get the `Parameter` with the given index of a device address.
+    pub fn parameter(&self, address: u16, index: u16) -> Option<&Parameter> {
+        self.parameters(address).iter().find(|parameter| parameter.index == index)
+    }
+
+    fn parameter_mut(&mut self, address: u16, index: u16) -> Option<&mut Parameter> {
+        self.devices.iter_mut()
+            .find(|(device_address, _)| *device_address == address)
+            .and_then(|(_, parameters)| parameters.iter_mut().find(|parameter| parameter.index == index))
+    }
+
+    /// Get the `Parameter` a `Datagram` refers to.
+    ///
+    /// Answers (`COMMAND_ANSWER_VALUE`) are looked up using the source address of the
+    /// `Datagram`, read and write requests using its destination address. All other commands do
+    /// not refer to a parameter.
+    pub fn parameter_for_datagram(&self, dgram: &Datagram) -> Option<&Parameter> {
+        datagram_parameter_address(dgram).and_then(|address| self.parameter(address, dgram.param16 as u16))
+    }
+
+}
+
+
+/// Get the address of the device whose parameter a `Datagram` refers to, if any.
+pub(crate) fn datagram_parameter_address(dgram: &Datagram) -> Option<u16> {
+    match dgram.command {
+        COMMAND_ANSWER_VALUE => Some(dgram.header.source_address),
+        COMMAND_WRITE_VALUE | COMMAND_READ_VALUE => Some(dgram.header.destination_address),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use header::Header;
+    use utils::utc_timestamp;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let catalogue = ParameterCatalogue::parse("
+            # comment
+            [device 0x7E11]
+            0x0123 = Max tank temperature; DegreesCelsius; 1; 200; 950; rw
+            0x0123.de = Maximale Speichertemperatur
+            291.de = Maximale Speichertemperatur 1
+            2 = Minimum; ; 0; -10; 0x10; ro
+
+            [ device  32274 ]
+            0x0001 = Hysteresis; DegreesKelvin; 1; ; ; rw
+        ").unwrap();
+
+        assert_eq!(vec![ 0x7E11, 0x7E12 ], catalogue.addresses());
+        assert_eq!(2, catalogue.parameters(0x7E11).len());
+        assert_eq!(0, catalogue.parameters(0x7E13).len());
+
+        let parameter = catalogue.parameter(0x7E11, 0x0123).unwrap();
+        assert_eq!(Parameter {
+            index: 0x0123,
+            names: vec![
+                (Language::En, "Max tank temperature".to_owned()),
+                (Language::De, "Maximale Speichertemperatur 1".to_owned()),
+            ],
+            unit_code: "DegreesCelsius".to_owned(),
+            precision: 1,
+            min: Some(200),
+            max: Some(950),
+            writable: true,
+        }, *parameter);
+        assert!(parameter.is_in_range(200));
+        assert!(!parameter.is_in_range(951));
+
+        let parameter = catalogue.parameter(0x7E11, 2).unwrap();
+        assert_eq!("", parameter.unit_code);
+        assert_eq!((Some(-10), Some(16)), (parameter.min, parameter.max));
+
+        let parameter = catalogue.parameter(0x7E12, 1).unwrap();
+        assert_eq!((None, None), (parameter.min, parameter.max));
+        assert!(parameter.is_in_range(i64::MIN));
+
+        let parse_err = |input| ParameterCatalogue::parse(input).unwrap_err();
+
+        assert_eq!(Error::MissingSection(1), parse_err("1 = Name; ; 0; ; ; rw"));
+        assert_eq!(Error::InvalidLine(1), parse_err("[device]"));
+        assert_eq!(Error::InvalidLine(1), parse_err("[device 0x10000]"));
+        assert_eq!(Error::InvalidLine(2), parse_err("[device 0x7E11]\n-1 = Name; ; 0; ; ; rw"));
+        assert_eq!(Error::InvalidLine(1), parse_err("[unknown 0x7E11]"));
+        assert_eq!(Error::InvalidLine(2), parse_err("[device 0x7E11]\n1 = Name"));
+        assert_eq!(Error::InvalidLine(2), parse_err("[device 0x7E11]\n1 = Name; ; x; ; ; rw"));
+        assert_eq!(Error::InvalidLine(2), parse_err("[device 0x7E11]\n1 = Name; ; 0; ; ; wo"));
+        assert_eq!(Error::UnknownLanguage(3), parse_err("[device 0x7E11]\n1 = Name; ; 0; ; ; rw\n1.xx = Name"));
+        assert_eq!(Error::DuplicateParameter(3), parse_err("[device 0x7E11]\n1 = Name; ; 0; ; ; rw\n0x1 = Name; ; 0; ; ; rw"));
+    }
+
+    #[test]
+    fn test_parameter_for_datagram() {
+        let catalogue = ParameterCatalogue::parse("
+            [device 0x7E11]
+            0x0123 = Max tank temperature; DegreesCelsius; 1; 200; 950; rw
+        ").unwrap();
+
+        let dgram = |destination_address, source_address, command| Datagram {
+            header: Header {
+                timestamp: utc_timestamp(1485688933),
+                channel: 0x00,
+                destination_address,
+                source_address,
+                protocol_version: 0x20,
+            },
+            command,
+            param16: 0x0123,
+            param32: 600,
+        };
+
+        assert_eq!(0x0123, catalogue.parameter_for_datagram(&dgram(0x0020, 0x7E11, COMMAND_ANSWER_VALUE)).unwrap().index);
+        assert_eq!(0x0123, catalogue.parameter_for_datagram(&dgram(0x7E11, 0x0020, COMMAND_WRITE_VALUE)).unwrap().index);
+        assert_eq!(0x0123, catalogue.parameter_for_datagram(&dgram(0x7E11, 0x0020, COMMAND_READ_VALUE)).unwrap().index);
+        assert_eq!(None, catalogue.parameter_for_datagram(&dgram(0x7E11, 0x0020, COMMAND_ANSWER_VALUE)));
+        assert_eq!(None, catalogue.parameter_for_datagram(&dgram(0x0020, 0x7E11, 0x0500)));
+    }
+}
@@ -48,6 +48,14 @@ pub fn timestamp_from_checked_bytes(buf: &[u8]) -> DateTime<UTC> {
 }
 
 
+/// Convert slice of bytes of a "checksum" record to the covered length and CRC16 checksum.
+pub fn checksum_from_checked_bytes(buf: &[u8]) -> (u32, u16) {
+    let length = LittleEndian::read_u32(&buf [14..18]);
+    let crc = LittleEndian::read_u16(&buf [18..20]);
+    (length, crc)
+}
+
+
 /// Convert slice of bytes to respective `Data` variant.
 pub fn data_from_checked_bytes(channel: u8, buf: &[u8]) -> Data {
     let timestamp = timestamp_from_checked_bytes(&buf [6..14]);
@@ -207,6 +215,14 @@ mod tests {
         assert_eq!("2017-01-09T09:57:26.080+00:00", timestamp.to_rfc3339());
     }
 
+    #[test]
+    fn test_checksum_from_checked_bytes() {
+        let buf = &[ 0xA5, 0xCC, 0x14, 0x00, 0x14, 0x00, 0x88, 0x0A, 0xF6, 0xE9, 0x59, 0x01, 0x00, 0x00, 0x78, 0x56, 0x34, 0x12, 0xBC, 0x9A ];
+
+        assert_eq!(BlobLength(20), length_from_bytes(buf));
+        assert_eq!((0x12345678, 0x9ABC), checksum_from_checked_bytes(buf));
+    }
+
     #[test]
     fn test_data_from_checked_bytes() {
         let data = data_from_checked_bytes(0x00, &RECORDING_1 [14..]);
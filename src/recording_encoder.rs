@@ -46,6 +46,17 @@ pub fn bytes_from_channel(channel: u8, buf: &mut [u8]) {
 }
 
 
+/// Stores a "checksum" record in the provided byte slice.
+///
+/// The record covers the `length` bytes of all records written since the previous checksum
+/// record (or the start of the stream), `crc` is the CRC16 checksum calculated over those bytes.
+pub fn bytes_from_checksum(timestamp: DateTime<UTC>, length: u32, crc: u16, buf: &mut [u8]) {
+    bytes_from_record(0xCC, 20, timestamp, buf);
+    LittleEndian::write_u32(&mut buf [14..18], length);
+    LittleEndian::write_u16(&mut buf [18..20], crc);
+}
+
+
 /// Stores the recorded representation of the Data in the provided byte slice.
 pub fn bytes_from_data(data: &Data, buf: &mut [u8]) {
     let length = length_from_data(data);
@@ -146,6 +157,16 @@ mod tests {
         assert_eq!("a5771000100000000000000000001100", to_hex_string(&buf));
     }
 
+    #[test]
+    fn test_bytes_from_checksum() {
+        let timestamp = UTC.timestamp(1485688933, 0);
+
+        let mut buf = [0u8; 20];
+
+        bytes_from_checksum(timestamp, 0x12345678, 0x9ABC, &mut buf);
+        assert_eq!("a5cc14001400880af6e95901000078563412bc9a", to_hex_string(&buf));
+    }
+
     #[test]
     fn test_bytes_from_data() {
         let channel = 0x11;
@@ -6,7 +6,24 @@ use chrono::{DateTime, UTC};
 use blob_reader::BlobReader;
 use stream_blob_length::StreamBlobLength::{BlobLength, Partial, Malformed};
 use data_set::DataSet;
-use recording_decoder::{length_from_bytes, timestamp_from_checked_bytes, data_from_bytes};
+use recording_decoder::{length_from_bytes, timestamp_from_checked_bytes, checksum_from_checked_bytes, data_from_bytes};
+use utils::update_crc16;
+
+
+/// Describes a range of `DataSet` values that failed verification against a checksum record.
+///
+/// See `RecordingReader::checksum_mismatches` for details.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChecksumMismatch {
+    /// The timestamp of the first data set record covered by the checksum record.
+    pub first_timestamp: Option<DateTime<UTC>>,
+
+    /// The timestamp of the last data set record covered by the checksum record.
+    pub last_timestamp: Option<DateTime<UTC>>,
+
+    /// The number of data set records covered by the checksum record.
+    pub data_set_count: usize,
+}
 
 
 /// Allows reading `Data` variants from a `Read` trait object.
@@ -31,6 +48,12 @@ use recording_decoder::{length_from_bytes, timestamp_from_checked_bytes, data_fr
 ///     }
 /// }
 /// ```
+///
+/// # Integrity checksums
+///
+/// If the recording contains checksum records (see `RecordingWriter::set_checksum_interval`)
+/// the `RecordingReader` verifies all records it reads against them. Data sets covered by a
+/// checksum record that does not match are reported by `checksum_mismatches`.
 #[derive(Debug)]
 pub struct RecordingReader<R: Read> {
     current_channel: u8,
@@ -38,6 +61,13 @@ pub struct RecordingReader<R: Read> {
     previous_length: usize,
     min_timestamp: Option<DateTime<UTC>>,
     max_timestamp: Option<DateTime<UTC>>,
+    checksum_length: u32,
+    checksum_crc: u16,
+    checksum_data_set_count: usize,
+    checksum_first_timestamp: Option<DateTime<UTC>>,
+    checksum_last_timestamp: Option<DateTime<UTC>>,
+    checksum_count: usize,
+    checksum_mismatches: Vec<ChecksumMismatch>,
 }
 
 
@@ -51,6 +81,13 @@ impl<R: Read> RecordingReader<R> {
             previous_length: 0,
             min_timestamp: None,
             max_timestamp: None,
+            checksum_length: 0,
+            checksum_crc: 0,
+            checksum_data_set_count: 0,
+            checksum_first_timestamp: None,
+            checksum_last_timestamp: None,
+            checksum_count: 0,
+            checksum_mismatches: Vec::new(),
         }
     }
 
@@ -60,11 +97,70 @@ impl<R: Read> RecordingReader<R> {
         self.max_timestamp = max_timestamp;
     }
 
+    /// Return the number of checksum records that were verified so far.
+    pub fn checksum_count(&self) -> usize {
+        self.checksum_count
+    }
+
+    /// Return the list of data set ranges that failed verification against a checksum record.
+    ///
+    /// Checksum records are verified as soon as they are read, so the list only contains
+    /// ranges of data sets that were already returned by `read_data_set`. Data sets that are
+    /// not followed by a checksum record yet have not been verified at all.
+    pub fn checksum_mismatches(&self) -> &[ChecksumMismatch] {
+        &self.checksum_mismatches
+    }
+
+    fn consume_record(&mut self) {
+        let length = self.previous_length;
+
+        {
+            let record = &self.reader.as_bytes() [0..length];
+
+            if record [1] == 0xCC {
+                if length >= 20 {
+                    let (expected_length, expected_crc) = checksum_from_checked_bytes(record);
+
+                    if expected_length != self.checksum_length || expected_crc != self.checksum_crc {
+                        self.checksum_mismatches.push(ChecksumMismatch {
+                            first_timestamp: self.checksum_first_timestamp,
+                            last_timestamp: self.checksum_last_timestamp,
+                            data_set_count: self.checksum_data_set_count,
+                        });
+                    }
+
+                    self.checksum_count += 1;
+                }
+
+                self.checksum_length = 0;
+                self.checksum_crc = 0;
+                self.checksum_data_set_count = 0;
+                self.checksum_first_timestamp = None;
+                self.checksum_last_timestamp = None;
+            } else {
+                self.checksum_length = self.checksum_length.wrapping_add(length as u32);
+                self.checksum_crc = update_crc16(self.checksum_crc, record);
+
+                if record [1] == 0x44 {
+                    let timestamp = timestamp_from_checked_bytes(&record [6..14]);
+
+                    if self.checksum_first_timestamp.is_none() {
+                        self.checksum_first_timestamp = Some(timestamp);
+                    }
+                    self.checksum_last_timestamp = Some(timestamp);
+                    self.checksum_data_set_count += 1;
+                }
+            }
+        }
+
+        self.reader.consume(length);
+        self.previous_length = 0;
+    }
+
     /// Read from the stream until a valid blob of data is found.
     pub fn read_record(&mut self) -> Result<&[u8]> {
         if self.previous_length > 0 {
-            self.reader.consume(self.previous_length);
-            self.previous_length = 0;
+            self.consume_record();
         }
 
         loop {
@@ -145,6 +241,8 @@ impl<R: Read> RecordingReader<R> {
                     if length >= 16 {
                         current_channel = bytes [14];
                     }
+                } else if bytes [1] == 0xCC {
+                    // nop, checksum records are verified in `read_record`
                 } else {
                     panic!("Unsupported record type 0x{:02X}", bytes [1]);
                 }
@@ -216,6 +314,8 @@ impl<R: Read> RecordingReader<R> {
                 if length >= 16 {
                     current_channel = record [14];
                 }
+            } else if record [1] == 0xCC {
+                // nop
             } else {
                 panic!("Unsupported record type 0x{:02X}", record [1]);
             }
@@ -266,6 +366,10 @@ impl<R: Read> RecordingReader<R> {
 
 #[cfg(test)]
 mod tests {
+    use chrono::Duration;
+
+    use recording_writer::RecordingWriter;
+
     use super::*;
 
     use test_data::{RECORDING_1};
@@ -313,4 +417,84 @@ mod tests {
 
         assert_eq!(true, rr.read_data_set().unwrap().is_none());
     }
+
+    fn write_checksummed_recording(data_set_count: usize, interval: usize) -> Vec<u8> {
+        let mut rr = RecordingReader::new(RECORDING_1);
+        let mut data_set = rr.read_data_set().unwrap().unwrap();
+
+        let mut bytes = Vec::new();
+
+        {
+            let mut rw = RecordingWriter::new(&mut bytes);
+            rw.set_checksum_interval(Some(interval));
+
+            for _ in 0..data_set_count {
+                rw.write_data_set(&data_set).unwrap();
+                data_set.timestamp = data_set.timestamp + Duration::seconds(1);
+            }
+
+            rw.write_checksum().unwrap();
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_checksums() {
+        let bytes = write_checksummed_recording(5, 2);
+
+        let mut rr = RecordingReader::new(&bytes [..]);
+
+        let mut data_set_count = 0;
+        while let Some(data_set) = rr.read_data_set().unwrap() {
+            assert_eq!(9, data_set.as_data_slice().len());
+            data_set_count += 1;
+        }
+
+        assert_eq!(5, data_set_count);
+        assert_eq!(3, rr.checksum_count());
+        assert_eq!(0, rr.checksum_mismatches().len());
+
+        let mut rr = RecordingReader::new(&bytes [..]);
+
+        let data_set = rr.read_topology_data_set().unwrap();
+
+        assert_eq!(9, data_set.as_data_slice().len());
+        assert_eq!(3, rr.checksum_count());
+        assert_eq!(0, rr.checksum_mismatches().len());
+    }
+
+    #[test]
+    fn test_checksum_mismatches() {
+        let mut bytes = write_checksummed_recording(5, 2);
+
+        // corrupt a byte of frame data in the third data set
+        bytes [1480 + 20 + 140] ^= 0x01;
+
+        let mut rr = RecordingReader::new(&bytes [..]);
+
+        let data_set = rr.read_data_set().unwrap().unwrap();
+        assert_eq!("2017-01-09T09:57:29.009+00:00", data_set.timestamp.to_rfc3339());
+        assert_eq!(0, rr.checksum_mismatches().len());
+
+        let data_set = rr.read_data_set().unwrap().unwrap();
+        assert_eq!("2017-01-09T09:57:30.009+00:00", data_set.timestamp.to_rfc3339());
+        assert_eq!(1, rr.checksum_count());
+        assert_eq!(0, rr.checksum_mismatches().len());
+
+        let _data_set = rr.read_data_set().unwrap().unwrap();
+        let _data_set = rr.read_data_set().unwrap().unwrap();
+        assert_eq!(2, rr.checksum_count());
+        assert_eq!(1, rr.checksum_mismatches().len());
+
+        let mismatch = &rr.checksum_mismatches() [0];
+        assert_eq!("2017-01-09T09:57:31.009+00:00", mismatch.first_timestamp.unwrap().to_rfc3339());
+        assert_eq!("2017-01-09T09:57:32.009+00:00", mismatch.last_timestamp.unwrap().to_rfc3339());
+        assert_eq!(2, mismatch.data_set_count);
+
+        let _data_set = rr.read_data_set().unwrap().unwrap();
+        assert!(rr.read_data_set().unwrap().is_none());
+        assert_eq!(3, rr.checksum_count());
+        assert_eq!(1, rr.checksum_mismatches().len());
+    }
 }
@@ -1,14 +1,50 @@
 use std::cmp::max;
 use std::io::{Result, Write};
 
+use chrono::{DateTime, UTC};
+
 use data_set::DataSet;
-use recording_encoder::{length_from_data, bytes_from_record, bytes_from_channel, bytes_from_data};
+use recording_encoder::{length_from_data, bytes_from_record, bytes_from_channel, bytes_from_data, bytes_from_checksum};
+use utils::update_crc16;
 
 
 /// Allows writing the recorded representation of `DataSet` values to a `Write` trait object.
+///
+/// # Integrity checksums
+///
+/// If a checksum interval is configured using `set_checksum_interval`, the `RecordingWriter`
+/// periodically writes an additional "checksum" record (type 0xCC) into the stream. That
+/// record contains the length and CRC16 checksum of all records written since the previous
+/// checksum record, allowing the `RecordingReader` to detect corrupted data. Tools that do
+/// not know about this record type can safely skip it.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::{DataSet, RecordingWriter};
+///
+/// let mut bytes: Vec<u8> = Vec::new();
+///
+/// {
+///     let mut rw = RecordingWriter::new(&mut bytes);
+///
+///     // Write a checksum record after every 10 data sets
+///     rw.set_checksum_interval(Some(10));
+///
+///     rw.write_data_set(&DataSet::new()).unwrap();
+///
+///     // Cover the remaining data sets with a final checksum record
+///     rw.write_checksum().unwrap();
+/// }
+/// ```
 #[derive(Debug)]
 pub struct RecordingWriter<W: Write> {
     writer: W,
+    checksum_interval: Option<usize>,
+    checksum_data_set_count: usize,
+    checksum_length: u32,
+    checksum_crc: u16,
+    checksum_timestamp: Option<DateTime<UTC>>,
 }
 
 
@@ -17,7 +53,12 @@ impl<W: Write> RecordingWriter<W> {
     /// Construct a new `RecordingWriter`.
     pub fn new(writer: W) -> RecordingWriter<W> {
         RecordingWriter {
-            writer: writer,
+            writer,
+            checksum_interval: None,
+            checksum_data_set_count: 0,
+            checksum_length: 0,
+            checksum_crc: 0,
+            checksum_timestamp: None,
         }
     }
 
@@ -31,6 +72,13 @@ impl<W: Write> RecordingWriter<W> {
         &mut self.writer
     }
 
+    /// Set the optional number of `DataSet` values after which a checksum record is written.
+    ///
+    /// Passing `None` disables writing checksum records periodically.
+    pub fn set_checksum_interval(&mut self, interval: Option<usize>) {
+        self.checksum_interval = interval;
+    }
+
     /// Write the recorded representation of the `DataSet`.
     pub fn write_data_set(&mut self, data_set: &DataSet) -> Result<()> {
         let timestamp = data_set.timestamp;
@@ -49,7 +97,7 @@ impl<W: Write> RecordingWriter<W> {
         let buf = &mut bytes [..];
         bytes_from_record(0x44, 14, timestamp, buf);
 
-        self.writer.write_all(&buf [0..14])?;
+        self.write_record(&buf [0..14])?;
 
         let mut current_channel = 0;
         for data in data_set.iter() {
@@ -58,23 +106,64 @@ impl<W: Write> RecordingWriter<W> {
                 current_channel = channel;
 
                 bytes_from_channel(channel, buf);
-                self.writer.write_all(&buf [0..16])?;
+                self.write_record(&buf [0..16])?;
             }
 
             let length = length_from_data(data);
             bytes_from_data(data, buf);
-            self.writer.write_all(&buf [0..length])?;
+            self.write_record(&buf [0..length])?;
+        }
+
+        self.checksum_data_set_count += 1;
+        self.checksum_timestamp = Some(timestamp);
+
+        if let Some(interval) = self.checksum_interval {
+            if self.checksum_data_set_count >= interval {
+                self.write_checksum()?;
+            }
         }
 
         Ok(())
     }
 
+    /// Write a checksum record covering all records written since the previous checksum record.
+    ///
+    /// This function does nothing if no records were written since the previous checksum
+    /// record. It should be called after writing the last `DataSet` to make sure that all
+    /// `DataSet` values are covered by a checksum.
+    pub fn write_checksum(&mut self) -> Result<()> {
+        if let Some(timestamp) = self.checksum_timestamp {
+            let mut buf = [0u8; 20];
+            bytes_from_checksum(timestamp, self.checksum_length, self.checksum_crc, &mut buf);
+
+            self.writer.write_all(&buf)?;
+
+            self.checksum_data_set_count = 0;
+            self.checksum_length = 0;
+            self.checksum_crc = 0;
+            self.checksum_timestamp = None;
+        }
+
+        Ok(())
+    }
+
+    fn write_record(&mut self, buf: &[u8]) -> Result<()> {
+        self.writer.write_all(buf)?;
+
+        self.checksum_length = self.checksum_length.wrapping_add(buf.len() as u32);
+        self.checksum_crc = update_crc16(self.checksum_crc, buf);
+
+        Ok(())
+    }
+
 }
 
 
 #[cfg(test)]
 mod tests {
+    use recording_decoder::checksum_from_checked_bytes;
     use recording_reader::RecordingReader;
+    use utils::calc_crc16;
 
     use super::*;
 
@@ -98,4 +187,43 @@ mod tests {
         assert_eq!(740, writer.len());
         assert_eq!(&RECORDING_1 [0..740], &writer [0..740]);
     }
+
+    #[test]
+    fn test_write_checksum() {
+        let mut rr = RecordingReader::new(RECORDING_1);
+
+        let data_set = rr.read_data_set().unwrap().unwrap();
+
+        let mut writer: Vec<u8> = Vec::new();
+
+        {
+            let mut rw = RecordingWriter::new(&mut writer);
+            rw.set_checksum_interval(Some(2));
+
+            rw.write_data_set(&data_set).unwrap();
+            assert_eq!(740, rw.get_ref().len());
+
+            rw.write_data_set(&data_set).unwrap();
+            assert_eq!(1500, rw.get_ref().len());
+
+            rw.write_data_set(&data_set).unwrap();
+            assert_eq!(2240, rw.get_ref().len());
+
+            rw.write_checksum().unwrap();
+            assert_eq!(2260, rw.get_ref().len());
+
+            rw.write_checksum().unwrap();
+            assert_eq!(2260, rw.get_ref().len());
+        }
+
+        assert_eq!(&RECORDING_1 [0..740], &writer [0..740]);
+        assert_eq!(&RECORDING_1 [0..740], &writer [740..1480]);
+        assert_eq!(&RECORDING_1 [0..740], &writer [1500..2240]);
+
+        let crc = calc_crc16(&writer [0..1480]);
+        assert_eq!((1480, crc), checksum_from_checked_bytes(&writer [1480..1500]));
+
+        let crc = calc_crc16(&writer [1500..2240]);
+        assert_eq!((740, crc), checksum_from_checked_bytes(&writer [2240..2260]));
+    }
 }
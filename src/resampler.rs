@@ -0,0 +1,357 @@
+//! A module that resamples the values of packet fields onto a regular time grid.
+//!
+//! The `Resampler` collects the values of all packet fields from a stream of `DataSet`s, using
+//! the timestamp of each `Data` value rather than the `DataSet`'s one, so that `DataSet`s from
+//! several sources (e.g. multiple controllers or loggers) can be merged. It then produces rows
+//! for a regular time grid using one of the `Strategy` variants.
+//!
+//! A `StalenessPolicy` can be used to limit how far values may be carried forward or
+//! interpolated: a value is not used beyond the TTL of the `Data` value it originated from.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{DataSet, Specification};
+//! use resol_vbus::chrono::Duration;
+//! use resol_vbus::resampler::{Resampler, Strategy};
+//! use resol_vbus::staleness::{DataKind, StalenessPolicy};
+//!
+//! # #[allow(dead_code)]
+//! fn print_table(spec: &Specification, data_sets: &[DataSet]) {
+//!     let mut policy = StalenessPolicy::new();
+//!     policy.set_ttl(DataKind::Packet, Duration::minutes(5));
+//!
+//!     let mut resampler = Resampler::new(Strategy::Linear).with_staleness(policy);
+//!     for data_set in data_sets {
+//!         resampler.add_data_set(spec, data_set);
+//!     }
+//!
+//!     let start = data_sets [0].timestamp;
+//!     let end = data_sets [data_sets.len() - 1].timestamp;
+//!     for row in resampler.resample(start, end, Duration::minutes(1)) {
+//!         println!("{}: {:?}", row.timestamp, row.values);
+//!     }
+//! }
+//! ```
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, UTC};
+
+use data_set::DataSet;
+use packet::{PacketFieldId, PacketId};
+use specification::Specification;
+use specification_file::Type;
+use staleness::StalenessPolicy;
+
+
+/// The strategy used to calculate a value at a point on the time grid.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    /// Use the most recent value at or before the point in time.
+    SampleAndHold,
+
+    /// Interpolate linearly between the values before and after the point in time.
+    ///
+    /// Fields that are not plain numbers (like times or enumerations) use `SampleAndHold`
+    /// instead.
+    Linear,
+
+    /// Use the value closest to the point in time.
+    Nearest,
+}
+
+
+/// The resampled values of all fields at a point on the time grid.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResampledRow {
+    /// The point in time on the grid.
+    pub timestamp: DateTime<UTC>,
+
+    /// The value of each column returned by `Resampler::columns`, if available.
+    pub values: Vec<Option<f64>>,
+}
+
+
+#[derive(Debug)]
+struct Sample {
+    timestamp: DateTime<UTC>,
+    value: f64,
+    ttl: Option<Duration>,
+}
+
+
+impl Sample {
+
+    fn is_valid_for(&self, distance: Duration) -> bool {
+        self.ttl.is_none_or(|ttl| distance <= ttl)
+    }
+
+}
+
+
+#[derive(Debug)]
+struct Series {
+    packet_id: PacketId,
+    field_id: String,
+    interpolate: bool,
+    samples: Vec<Sample>,
+}
+
+
+impl Series {
+
+    fn add_sample(&mut self, sample: Sample) {
+        let index = self.samples.partition_point(|other| other.timestamp < sample.timestamp);
+        match self.samples.get_mut(index) {
+            Some(other) if other.timestamp == sample.timestamp => *other = sample,
+            _ => self.samples.insert(index, sample),
+        }
+    }
+
+    fn value_at(&self, strategy: Strategy, timestamp: DateTime<UTC>) -> Option<f64> {
+        let index = self.samples.partition_point(|sample| sample.timestamp <= timestamp);
+        let before = if index > 0 { self.samples.get(index - 1) } else { None };
+        let after = self.samples.get(index);
+
+        let hold = || {
+            let before = before?;
+            if before.is_valid_for(timestamp.signed_duration_since(before.timestamp)) {
+                Some(before.value)
+            } else {
+                None
+            }
+        };
+
+        match strategy {
+            Strategy::SampleAndHold => hold(),
+            Strategy::Linear if !self.interpolate => hold(),
+            Strategy::Linear => {
+                let before = before?;
+                if before.timestamp == timestamp {
+                    return Some(before.value);
+                }
+
+                let after = after?;
+                let gap = after.timestamp.signed_duration_since(before.timestamp);
+                if !before.is_valid_for(gap) {
+                    return None;
+                }
+
+                let offset = timestamp.signed_duration_since(before.timestamp);
+                let ratio = offset.num_milliseconds() as f64 / gap.num_milliseconds() as f64;
+                Some(before.value + (after.value - before.value) * ratio)
+            },
+            Strategy::Nearest => {
+                let before = before.map(|sample| (timestamp.signed_duration_since(sample.timestamp), sample));
+                let after = after.map(|sample| (sample.timestamp.signed_duration_since(timestamp), sample));
+
+                let nearest = match (before, after) {
+                    (Some(before), Some(after)) => if after.0 < before.0 { after } else { before },
+                    (Some(before), None) => before,
+                    (None, Some(after)) => after,
+                    (None, None) => return None,
+                };
+
+                if nearest.1.is_valid_for(nearest.0) {
+                    Some(nearest.1.value)
+                } else {
+                    None
+                }
+            },
+        }
+    }
+
+}
+
+
+/// Resamples the values of packet fields onto a regular time grid.
+#[derive(Debug)]
+pub struct Resampler {
+    strategy: Strategy,
+    policy: StalenessPolicy,
+    series: Vec<Series>,
+    positions: HashMap<(PacketId, usize), usize>,
+}
+
+
+impl Resampler {
+
+    /// Construct a new `Resampler` using the given `Strategy`.
+    pub fn new(strategy: Strategy) -> Resampler {
+        Resampler {
+            strategy,
+            policy: StalenessPolicy::new(),
+            series: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    /// Limit the use of values according to the TTLs of the `StalenessPolicy`.
+    pub fn with_staleness(mut self, policy: StalenessPolicy) -> Resampler {
+        self.policy = policy;
+        self
+    }
+
+    /// Add the field values of all `Packet` values in a `DataSet`.
+    ///
+    /// Each value is recorded at the timestamp of its `Packet`. `DataSet`s may be added in any
+    /// order, a value with the same timestamp as an already recorded one replaces it.
+    pub fn add_data_set(&mut self, spec: &Specification, data_set: &DataSet) {
+        for field in spec.fields_in_data_set(data_set) {
+            let value = match field.raw_value_f64() {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let key = (field.packet_id(), field.field_index());
+            let position = match self.positions.get(&key) {
+                Some(&position) => position,
+                None => {
+                    let field_spec = field.field_spec();
+                    self.positions.insert(key, self.series.len());
+                    self.series.push(Series {
+                        packet_id: key.0,
+                        field_id: field_spec.field_id.clone(),
+                        interpolate: field_spec.typ == Type::Number && field_spec.enum_type.is_none(),
+                        samples: Vec::new(),
+                    });
+                    self.series.len() - 1
+                }
+            };
+
+            self.series [position].add_sample(Sample {
+                timestamp: field.data().as_header().timestamp,
+                value,
+                ttl: self.policy.ttl(field.data()),
+            });
+        }
+    }
+
+    /// Remove all recorded values older than `min_timestamp`, keeping the most recent one of
+    /// each field so that it can still be held or interpolated from.
+    pub fn remove_samples_older_than(&mut self, min_timestamp: DateTime<UTC>) {
+        for series in self.series.iter_mut() {
+            let index = series.samples.partition_point(|sample| sample.timestamp < min_timestamp);
+            if index > 1 {
+                series.samples.drain(0..index - 1);
+            }
+        }
+    }
+
+    /// Return the `PacketFieldId`s of the columns in the order of the `ResampledRow` values.
+    pub fn columns(&self) -> Vec<PacketFieldId<'_>> {
+        self.series.iter().map(|series| PacketFieldId(series.packet_id, &series.field_id)).collect()
+    }
+
+    /// Get the value of a column at the given point in time.
+    pub fn value_at(&self, column: usize, timestamp: DateTime<UTC>) -> Option<f64> {
+        self.series.get(column)?.value_at(self.strategy, timestamp)
+    }
+
+    /// Get the values of all columns at the given point in time.
+    pub fn row_at(&self, timestamp: DateTime<UTC>) -> ResampledRow {
+        ResampledRow {
+            timestamp,
+            values: self.series.iter().map(|series| series.value_at(self.strategy, timestamp)).collect(),
+        }
+    }
+
+    /// Get a `ResampledRow` for each point of the time grid from `start` to `end` (inclusive).
+    ///
+    /// # Panics
+    ///
+    /// The `interval` must be positive.
+    pub fn resample(&self, start: DateTime<UTC>, end: DateTime<UTC>, interval: Duration) -> Vec<ResampledRow> {
+        assert!(interval > Duration::zero(), "Interval must be positive");
+
+        let mut rows = Vec::new();
+        let mut timestamp = start;
+        while timestamp <= end {
+            rows.push(self.row_at(timestamp));
+            timestamp = timestamp + interval;
+        }
+        rows
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, UTC};
+
+    use specification_file::{Language, SpecificationFile};
+    use staleness::DataKind;
+
+    use super::*;
+
+    use test_data::data_set_with_temperature;
+
+    fn resampler_with_samples(strategy: Strategy, spec: &Specification, t0: DateTime<UTC>) -> Resampler {
+        let mut resampler = Resampler::new(strategy);
+        resampler.add_data_set(spec, &data_set_with_temperature(spec, 0x11, t0 + Duration::seconds(5), Some(200)));
+        resampler.add_data_set(spec, &data_set_with_temperature(spec, 0x12, t0 + Duration::seconds(8), Some(300)));
+        resampler.add_data_set(spec, &data_set_with_temperature(spec, 0x11, t0 + Duration::seconds(25), Some(220)));
+        resampler
+    }
+
+    fn temperatures(resampler: &Resampler, column: usize, t0: DateTime<UTC>) -> Vec<Option<f64>> {
+        resampler.resample(t0, t0 + Duration::seconds(30), Duration::seconds(10)).iter().map(|row| {
+            row.values [column].map(|value| (value * 100.0).round() / 100.0)
+        }).collect()
+    }
+
+    #[test]
+    fn test_strategies() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let t0 = UTC.timestamp(1485688930, 0);
+
+        let resampler = resampler_with_samples(Strategy::SampleAndHold, &spec, t0);
+        let columns = resampler.columns();
+        assert_eq!("11_0010_7E11_10_0100_000_2_0", columns [0].packet_field_id_string());
+        assert_eq!("12_0010_7E11_10_0100_000_2_0", columns [columns.len() / 2].packet_field_id_string());
+
+        let other_column = columns.len() / 2;
+        assert_eq!(vec![ None, Some(20.0), Some(20.0), Some(22.0) ], temperatures(&resampler, 0, t0));
+        assert_eq!(vec![ None, Some(30.0), Some(30.0), Some(30.0) ], temperatures(&resampler, other_column, t0));
+
+        let resampler = resampler_with_samples(Strategy::Linear, &spec, t0);
+        assert_eq!(vec![ None, Some(20.5), Some(21.5), None ], temperatures(&resampler, 0, t0));
+        assert_eq!(vec![ None, None, None, None ], temperatures(&resampler, other_column, t0));
+        assert_eq!(Some(20.0), resampler.value_at(0, t0 + Duration::seconds(5)));
+
+        let resampler = resampler_with_samples(Strategy::Nearest, &spec, t0);
+        assert_eq!(vec![ Some(20.0), Some(20.0), Some(22.0), Some(22.0) ], temperatures(&resampler, 0, t0));
+        assert_eq!(vec![ Some(30.0), Some(30.0), Some(30.0), Some(30.0) ], temperatures(&resampler, other_column, t0));
+    }
+
+    #[test]
+    fn test_staleness() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let t0 = UTC.timestamp(1485688930, 0);
+
+        let mut policy = StalenessPolicy::new();
+        policy.set_ttl(DataKind::Packet, Duration::seconds(10));
+        policy.set_packet_ttl(PacketId(0x12, 0x0010, 0x7E11, 0x0100), Duration::seconds(2));
+
+        let mut resampler = Resampler::new(Strategy::SampleAndHold).with_staleness(policy.clone());
+        resampler.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(25), Some(220)));
+        resampler.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(5), Some(200)));
+        resampler.add_data_set(&spec, &data_set_with_temperature(&spec, 0x12, t0 + Duration::seconds(8), Some(300)));
+        let other_column = resampler.columns().len() / 2;
+        assert_eq!(vec![ None, Some(20.0), None, Some(22.0) ], temperatures(&resampler, 0, t0));
+        assert_eq!(vec![ None, Some(30.0), None, None ], temperatures(&resampler, other_column, t0));
+
+        let mut resampler = Resampler::new(Strategy::Linear).with_staleness(policy);
+        resampler.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(5), Some(200)));
+        resampler.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(15), Some(210)));
+        resampler.add_data_set(&spec, &data_set_with_temperature(&spec, 0x11, t0 + Duration::seconds(29), Some(240)));
+        assert_eq!(vec![ None, Some(20.5), None, None ], temperatures(&resampler, 0, t0));
+
+        resampler.remove_samples_older_than(t0 + Duration::seconds(20));
+        assert_eq!(vec![ None, None, None, None ], temperatures(&resampler, 0, t0));
+        assert_eq!(Some(21.0), resampler.value_at(0, t0 + Duration::seconds(15)));
+    }
+}
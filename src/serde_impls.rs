@@ -0,0 +1,302 @@
+//! `Serialize` and `Deserialize` implementations for the VBus data types.
+//!
+//! This module is only available if the `serde` feature is enabled.
+//!
+//! Timestamps are represented as RFC 3339 strings, payloads as lowercase hex strings containing
+//! only the valid frame data and `PacketId` values as their ID string.
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{self, Visitor};
+use serde::ser::SerializeStruct;
+
+use chrono::{DateTime, UTC};
+
+use data::Data;
+use data_set::DataSet;
+use header::Header;
+use packet::{Packet, PacketId, ToPacketId};
+use telegram::Telegram;
+
+
+pub mod rfc3339 {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    use chrono::{DateTime, UTC};
+
+    pub fn serialize<S: Serializer>(value: &DateTime<UTC>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<UTC>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        match DateTime::parse_from_rfc3339(&s) {
+            Ok(timestamp) => Ok(timestamp.with_timezone(&UTC)),
+            Err(_) => Err(D::Error::custom(format!("Invalid RFC 3339 timestamp {:?}", s))),
+        }
+    }
+}
+
+
+fn to_hex(buf: &[u8]) -> String {
+    buf.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+
+fn from_hex(s: &str, buf: &mut [u8]) -> Result<usize, String> {
+    if !s.len().is_multiple_of(2) {
+        return Err(format!("Odd length of hex string {:?}", s));
+    }
+
+    let len = s.len() / 2;
+    if len > buf.len() {
+        return Err(format!("Hex string {:?} exceeds {} bytes", s, buf.len()));
+    }
+
+    for (index, b) in buf.iter_mut().take(len).enumerate() {
+        let digits = s.get(index * 2..index * 2 + 2).ok_or_else(|| format!("Invalid characters in hex string {:?}", s))?;
+        *b = u8::from_str_radix(digits, 16).map_err(|_| format!("Invalid characters in hex string {:?}", s))?;
+    }
+
+    Ok(len)
+}
+
+
+impl Serialize for PacketId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.packet_id_string())
+    }
+}
+
+
+struct PacketIdVisitor;
+
+
+impl<'de> Visitor<'de> for PacketIdVisitor {
+    type Value = PacketId;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a packet ID string")
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<PacketId, E> {
+        s.to_packet_id().map_err(E::custom)
+    }
+}
+
+
+impl<'de> Deserialize<'de> for PacketId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<PacketId, D::Error> {
+        deserializer.deserialize_str(PacketIdVisitor)
+    }
+}
+
+
+#[derive(Deserialize)]
+struct PacketRepr {
+    header: Header,
+    command: u16,
+    frame_data: String,
+}
+
+
+impl Serialize for Packet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Packet", 3)?;
+        state.serialize_field("header", &self.header)?;
+        state.serialize_field("command", &self.command)?;
+        state.serialize_field("frame_data", &to_hex(self.valid_frame_data()))?;
+        state.end()
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Packet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Packet, D::Error> {
+        let repr = PacketRepr::deserialize(deserializer)?;
+
+        let mut frame_data = [0u8; 508];
+        let len = from_hex(&repr.frame_data, &mut frame_data).map_err(de::Error::custom)?;
+        if !len.is_multiple_of(4) {
+            return Err(de::Error::custom(format!("Invalid length of packet frame data {}", len)));
+        }
+
+        Ok(Packet {
+            header: repr.header,
+            command: repr.command,
+            frame_count: (len / 4) as u8,
+            frame_data,
+        })
+    }
+}
+
+
+#[derive(Deserialize)]
+struct TelegramRepr {
+    header: Header,
+    command: u8,
+    frame_data: String,
+}
+
+
+impl Serialize for Telegram {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Telegram", 3)?;
+        state.serialize_field("header", &self.header)?;
+        state.serialize_field("command", &self.command)?;
+        state.serialize_field("frame_data", &to_hex(self.valid_frame_data()))?;
+        state.end()
+    }
+}
+
+
+impl<'de> Deserialize<'de> for Telegram {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Telegram, D::Error> {
+        let repr = TelegramRepr::deserialize(deserializer)?;
+
+        let mut frame_data = [0u8; 21];
+        let len = from_hex(&repr.frame_data, &mut frame_data).map_err(de::Error::custom)?;
+        if len != Telegram::frame_count_from_command(repr.command) as usize * 7 {
+            return Err(de::Error::custom(format!("Invalid length of telegram frame data {}", len)));
+        }
+
+        Ok(Telegram {
+            header: repr.header,
+            command: repr.command,
+            frame_data,
+        })
+    }
+}
+
+
+#[derive(Deserialize)]
+struct DataSetRepr {
+    #[serde(with = "rfc3339")]
+    timestamp: DateTime<UTC>,
+    data: Vec<Data>,
+}
+
+
+impl Serialize for DataSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("DataSet", 2)?;
+        state.serialize_field("timestamp", &self.timestamp.to_rfc3339())?;
+        state.serialize_field("data", self.as_data_slice())?;
+        state.end()
+    }
+}
+
+
+impl<'de> Deserialize<'de> for DataSet {
+    /// Deserialize a `DataSet`, rebuilding its index from the contained `Data` values.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<DataSet, D::Error> {
+        let repr = DataSetRepr::deserialize(deserializer)?;
+        Ok(DataSet::from_data(repr.timestamp, repr.data))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use chrono::{TimeZone, UTC};
+
+    use live_data_decoder::data_from_checked_bytes;
+    use specification::{PacketFieldSnapshot, Specification};
+    use specification_file::{Language, SpecificationFile};
+
+    use super::*;
+
+    use test_data::{LIVE_DATA_1, LIVE_TELEGRAM_1};
+
+    #[test]
+    fn test_data_set() {
+        let timestamp = UTC.timestamp(1485688933, 0);
+        let channel = 0x11;
+
+        let data_set = DataSet::from_data(timestamp, vec![
+            data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [0..]),
+            data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [352..]),
+            data_from_checked_bytes(timestamp, channel, &LIVE_TELEGRAM_1 [0..]),
+        ]);
+
+        let json = serde_json::to_string(&data_set).unwrap();
+        assert!(json.starts_with("{\"timestamp\":\"2017-01-29T11:22:13+00:00\",\"data\":[{\"type\":\"Packet\",\"header\":{\"timestamp\":\"2017-01-29T11:22:13+00:00\",\"channel\":17,\"destination_address\":16,\"source_address\":32273,\"protocol_version\":16},\"command\":256,\"frame_data\":\""));
+
+        let mut result: DataSet = serde_json::from_str(&json).unwrap();
+        assert_eq!(timestamp, result.timestamp);
+        assert_eq!(3, result.len());
+        for (left, right) in data_set.iter().zip(result.iter()) {
+            assert_eq!(left.id_string(), right.id_string());
+            assert_eq!(left.as_header().timestamp, right.as_header().timestamp);
+        }
+
+        let left = data_set.as_data_slice() [0].as_packet();
+        let right = result.as_data_slice() [0].as_packet();
+        assert_eq!(left.frame_count, right.frame_count);
+        assert_eq!(left.valid_frame_data(), right.valid_frame_data());
+
+        let left = data_set.as_data_slice() [1].as_datagram();
+        let right = result.as_data_slice() [1].as_datagram();
+        assert_eq!(left.param16, right.param16);
+        assert_eq!(left.param32, right.param32);
+
+        let left = data_set.as_data_slice() [2].as_telegram();
+        let right = result.as_data_slice() [2].as_telegram();
+        assert_eq!(left.valid_frame_data(), right.valid_frame_data());
+
+        // the index must have been rebuilt
+        result.add_data(data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [0..]));
+        assert_eq!(3, result.len());
+    }
+
+    #[test]
+    fn test_packet_id() {
+        let packet_id = PacketId(0x11, 0x0010, 0x7E11, 0x0100);
+
+        let json = serde_json::to_string(&packet_id).unwrap();
+        assert_eq!("\"11_0010_7E11_10_0100\"", json);
+        assert_eq!(packet_id, serde_json::from_str::<PacketId>(&json).unwrap());
+
+        assert!(serde_json::from_str::<PacketId>("\"11_0010_7E11\"").is_err());
+    }
+
+    #[test]
+    fn test_packet_field_snapshot() {
+        let timestamp = UTC.timestamp(1485688933, 0);
+        let channel = 0x11;
+
+        let data_set = DataSet::from_data(timestamp, vec![
+            data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [0..]),
+        ]);
+
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let snapshot = spec.fields_in_data_set(&data_set).next().unwrap().to_snapshot();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        assert!(json.starts_with("{\"timestamp\":\"2017-01-29T11:22:13+00:00\",\"packet_id\":\"11_0010_7E11_10_0100\",\"field_id\":\"000_2_0\",\"packet_field_id\":\"11_0010_7E11_10_0100_000_2_0\","));
+        assert!(json.contains("\"unit_family\":\"Temperature\",\"precision\":1,\"type\":\"Number\","));
+
+        assert_eq!(snapshot, serde_json::from_str::<PacketFieldSnapshot>(&json).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_frame_data() {
+        let header = "{\"timestamp\":\"2017-01-29T11:22:13+00:00\",\"channel\":0,\"destination_address\":16,\"source_address\":32273,\"protocol_version\":16}";
+
+        let json = format!("{{\"header\":{},\"command\":256,\"frame_data\":\"0102030405\"}}", header);
+        assert!(serde_json::from_str::<Packet>(&json).is_err());
+
+        let json = format!("{{\"header\":{},\"command\":256,\"frame_data\":\"0102030g\"}}", header);
+        assert!(serde_json::from_str::<Packet>(&json).is_err());
+
+        let json = format!("{{\"header\":{},\"command\":63,\"frame_data\":\"01020304050607\"}}", header);
+        assert_eq!(&[1, 2, 3, 4, 5, 6, 7], serde_json::from_str::<Telegram>(&json).unwrap().valid_frame_data());
+
+        let json = format!("{{\"header\":{},\"command\":63,\"frame_data\":\"010203040506\"}}", header);
+        assert!(serde_json::from_str::<Telegram>(&json).is_err());
+    }
+}
@@ -1,15 +1,23 @@
 //! This module provides the `Specification` and its associated types to allow interpretation
 //! of the fields contained within the `frame_data` payload of `Packet` values.
-use std::cell::RefCell;
 use std::clone::Clone;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::rc::Rc;
+use std::sync::{Arc, RwLock};
 
 use chrono::{DateTime, TimeZone, UTC};
 
 use packet::{PacketId, PacketFieldId};
 use data::Data;
-use specification_file::{SpecificationFile, Language, UnitFamily, UnitId, Type, PacketTemplateFieldPart};
+use datagram::Datagram;
+use field_value::FieldValue;
+use format_options::{self, FormatOptions};
+use parameter_catalogue::{self, Parameter, ParameterCatalogue};
+use specification_extension::{EnumKind, EnumType, SpecificationExtension};
+use staleness::StalenessPolicy;
+use specification_file::{SpecificationFile, TemplateIndex, Language, UnitFamily, UnitId, Type, PacketTemplateField, PacketTemplateFieldPart};
+use translation_table::TranslationTable;
+use unit_conversion::{self, UnitSystem};
 
 
 /// Contains information about a VBus device.
@@ -84,10 +92,10 @@ pub struct PacketSpec {
     pub command: u16,
 
     /// The `DeviceSpec` containing information about the destination VBus device.
-    pub destination_device: Rc<DeviceSpec>,
+    pub destination_device: Arc<DeviceSpec>,
 
     /// The `DeviceSpec` containing information about the source VBus device.
-    pub source_device: Rc<DeviceSpec>,
+    pub source_device: Arc<DeviceSpec>,
 
     /// The name of the packet, containing channel, source and optionally destination names.
     pub name: String,
@@ -154,6 +162,66 @@ pub struct PacketFieldSpec {
 
     /// The language used for the specification.
     pub language: Language,
+
+    /// The `EnumType` assigned to the field by a `SpecificationExtension`, if any.
+    pub enum_type: Option<Arc<EnumType>>,
+}
+
+
+/// A filter for `PacketFieldSpec` values used by `Specification::find_packet_fields`.
+///
+/// Every criterion that is `None` matches all fields.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::specification::PacketFieldQuery;
+/// use resol_vbus::specification_file::UnitFamily;
+///
+/// let query = PacketFieldQuery {
+///     name: Some("sensor".to_owned()),
+///     unit_family: Some(UnitFamily::Temperature),
+///     ..PacketFieldQuery::default()
+/// };
+/// # drop(query);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PacketFieldQuery {
+    /// A case-insensitive part of the localized field name.
+    pub name: Option<String>,
+
+    /// The `UnitFamily` of the field.
+    pub unit_family: Option<UnitFamily>,
+
+    /// The `Type` of the field.
+    pub typ: Option<Type>,
+}
+
+
+/// A reference to a `PacketFieldSpec` within its `PacketSpec`.
+///
+/// The function `Specification::find_packet_fields` returns a list of these.
+#[derive(Debug, Clone)]
+pub struct PacketSpecField {
+    /// The `PacketSpec` containing the field.
+    pub packet_spec: Arc<PacketSpec>,
+
+    /// The index of the `PacketFieldSpec` in the `PacketSpec`'s fields.
+    pub field_index: usize,
+}
+
+
+/// A list of errors that can occur if a raw value cannot be written into a slice of bytes.
+#[derive(Debug, PartialEq)]
+pub enum EncodeError {
+    /// The raw value is outside of the range supported by the field's parts.
+    OutOfRange,
+
+    /// The raw value is inside of the range but cannot be composed from the field's parts.
+    NotRepresentable,
+
+    /// The slice of bytes is too small to contain all of the field's parts.
+    BufferTooSmall,
 }
 
 
@@ -165,6 +233,8 @@ pub struct RawValueFormatter<'a> {
     precision: i32,
     raw_value: i64,
     unit_text: &'a str,
+    enum_type: Option<&'a EnumType>,
+    options: Option<&'a FormatOptions>,
 }
 
 
@@ -176,6 +246,22 @@ pub struct PacketFieldFormatter<'a> {
     precision: i32,
     raw_value: Option<i64>,
     unit_text: &'a str,
+    enum_type: Option<&'a EnumType>,
+    options: Option<&'a FormatOptions>,
+}
+
+
+/// A helper type for formatting `Datagram`s that get or set a controller value.
+#[derive(Debug)]
+pub struct DatagramFormatter<'a> {
+    language: Language,
+    command: u16,
+    param16: i16,
+    param32: i32,
+    is_value_command: bool,
+    parameter: Option<&'a Parameter>,
+    unit_text: &'a str,
+    options: Option<&'a FormatOptions>,
 }
 
 
@@ -195,15 +281,59 @@ pub struct PacketFieldFormatter<'a> {
 /// assert_eq!(None, device_spec.peer_address);
 /// assert_eq!("DeltaSol MX [Regler]", device_spec.name);
 /// ```
+///
+/// A `Specification` can also be constructed from several `SpecificationFile` layers using
+/// `Specification::from_files`. Later layers take precedence over earlier ones:
+///
+/// - the first matching device template is searched starting from the last layer
+/// - the fields of all matching packet templates are merged, starting from the first layer.
+///   A field of a later layer replaces a field with the same ID of an earlier layer, fields
+///   with new IDs are appended
+///
+/// The `Specification` is `Send` and `Sync`, so it can be shared between threads using an `Arc`.
+/// The `DeviceSpec` and `PacketSpec` values are created lazily on first lookup and are cached
+/// behind a `RwLock`, so that concurrent lookups of already known specs only need a read lock.
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// use resol_vbus::{SpecificationFile, Specification, Language};
+///
+/// let spec = Arc::new(Specification::from_file(SpecificationFile::new_default(), Language::De));
+///
+/// let threads = (0..4).map(|_| {
+///     let spec = spec.clone();
+///     thread::spawn(move || {
+///         spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100).name.clone()
+///     })
+/// }).collect::<Vec<_>>();
+///
+/// for thread in threads {
+///     assert_eq!("DeltaSol MX [Regler]", thread.join().unwrap());
+/// }
+/// ```
 #[derive(Debug)]
 pub struct Specification {
-    file: SpecificationFile,
+    files: Vec<SpecificationFile>,
+    indices: Vec<TemplateIndex>,
+    translations: Vec<TranslationTable>,
+    extensions: Vec<SpecificationExtension>,
+    parameter_catalogues: Vec<ParameterCatalogue>,
     language: Language,
-    devices: RefCell<Vec<Rc<DeviceSpec>>>,
-    packets: RefCell<Vec<Rc<PacketSpec>>>,
+    devices: RwLock<DeviceSpecCache>,
+    packets: RwLock<PacketSpecCache>,
 }
 
 
+/// Cached `DeviceSpec` values, keyed by channel and self address.
+type DeviceSpecCache = HashMap<(u8, u16), Vec<Arc<DeviceSpec>>>;
+
+
+/// Cached `PacketSpec` values, keyed by channel, destination address, source address and command.
+type PacketSpecCache = HashMap<(u8, u16, u16, u16), Arc<PacketSpec>>;
+
+
 /// An iterator over the fields of the `Packet` instances in a `DataSet`.
 ///
 /// The function `Specification::fields_in_data_set` returns this iterator.
@@ -232,6 +362,7 @@ pub struct DataSetPacketFieldIterator<'a, T: AsRef<[Data]> + 'a> {
     data_set: &'a T,
     data_index: usize,
     field_index: usize,
+    staleness: Option<(&'a StalenessPolicy, DateTime<UTC>)>,
 }
 
 
@@ -240,62 +371,137 @@ pub struct DataSetPacketFieldIterator<'a, T: AsRef<[Data]> + 'a> {
 pub struct DataSetPacketField<'a, T: AsRef<[Data]> + 'a> {
     data_set: &'a T,
     data_index: usize,
-    packet_spec: Rc<PacketSpec>,
+    packet_spec: Arc<PacketSpec>,
     field_index: usize,
     raw_value: Option<i64>,
+    stale: bool,
 }
 
 
-fn get_cached_device_spec(devices: &[Rc<DeviceSpec>], channel: u8, self_address: u16, peer_address: u16) -> Option<Rc<DeviceSpec>> {
-    let result = devices.iter().find(|&device| {
-        if device.channel != channel {
-            false
-        } else if device.self_address != self_address {
-            false
-        } else if device.peer_address.is_some() && device.peer_address.unwrap() != peer_address {
-            false
-        } else {
-            true
-        }
+/// An owned snapshot of a `DataSetPacketField`, including the metadata of its `PacketFieldSpec`.
+///
+/// If the `serde` feature is enabled, the snapshot can be serialized, e.g. to publish field
+/// values as JSON.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::{DataSet, Language, Specification, SpecificationFile};
+///
+/// # #[allow(dead_code)]
+/// fn print_snapshots(spec: &Specification, data_set: &DataSet) {
+///     for field in spec.fields_in_data_set(data_set) {
+///         let snapshot = field.to_snapshot();
+///         println!("{} = {}{}", snapshot.packet_field_id, snapshot.text, snapshot.unit_text);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PacketFieldSnapshot {
+    /// The timestamp of the `Data` value containing the field.
+    #[cfg_attr(feature = "serde", serde(with = "::serde_impls::rfc3339"))]
+    pub timestamp: DateTime<UTC>,
+
+    /// The `PacketId` of the `Packet` containing the field.
+    pub packet_id: PacketId,
+
+    /// The field ID.
+    pub field_id: String,
+
+    /// The packet field ID string.
+    pub packet_field_id: String,
+
+    /// The name of the packet.
+    pub packet_name: String,
+
+    /// The name of the field.
+    pub field_name: String,
+
+    /// The unit code of the field.
+    pub unit_code: String,
+
+    /// The unit text of the field.
+    pub unit_text: String,
+
+    /// The `UnitFamily` of the field.
+    pub unit_family: UnitFamily,
+
+    /// The number of fractional digits of the field.
+    pub precision: i32,
+
+    /// The `Type` of the field.
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub typ: Type,
+
+    /// The raw value of the field.
+    pub raw_value: Option<i64>,
+
+    /// The raw value of the field as a `f64`, scaled according to its precision.
+    pub value: Option<f64>,
+
+    /// The formatted value of the field without its unit.
+    pub text: String,
+
+    /// Whether the `Data` value containing the field is stale.
+    pub stale: bool,
+}
+
+
+fn get_cached_device_spec(devices: &DeviceSpecCache, channel: u8, self_address: u16, peer_address: u16) -> Option<Arc<DeviceSpec>> {
+    let result = devices.get(&(channel, self_address)).and_then(|devices| {
+        devices.iter().find(|&device| {
+            match device.peer_address {
+                Some(device_peer_address) => device_peer_address == peer_address,
+                None => true,
+            }
+        })
     });
 
-    match result {
-        Some(device) => Some(device.clone()),
-        None => None,
-    }
+    result.cloned()
 }
 
 
-fn get_or_create_cached_device_spec(devices: &mut Vec<Rc<DeviceSpec>>, channel: u8, self_address: u16, peer_address: u16, file: &SpecificationFile, language: Language) -> Rc<DeviceSpec> {
+fn get_or_create_cached_device_spec(devices: &mut DeviceSpecCache, channel: u8, self_address: u16, peer_address: u16, spec: &Specification) -> Arc<DeviceSpec> {
     if let Some(device) = get_cached_device_spec(devices, channel, self_address, peer_address) {
         return device;
     }
 
-    let device_template = file.find_device_template(self_address, peer_address);
+    let language = spec.language;
+
+    let device_template = spec.files.iter().zip(&spec.indices).rev().filter_map(|(file, index)| {
+        index.find_device_template(file, self_address, peer_address).map(|device_template| (file, device_template))
+    }).next();
 
     let peer_address_option = match device_template {
         None => None,
-        Some(device_template) => if device_template.peer_mask == 0 {
+        Some((_, device_template)) => if device_template.peer_mask == 0 {
             None
         } else {
             Some(peer_address)
         }
     };
 
-    let device_id = match peer_address_option {
-        None => format!("{:02X}_{:04X}", channel, self_address),
-        Some(peer_address) => format!("{:02X}_{:04X}_{:04X}", channel, self_address, peer_address),
+    let device_key = match peer_address_option {
+        None => format!("{:04X}", self_address),
+        Some(peer_address) => format!("{:04X}_{:04X}", self_address, peer_address),
     };
 
-    let name = match device_template {
-        None => {
+    let device_id = format!("{:02X}_{}", channel, device_key);
+
+    let name = match (device_template, spec.translate(&device_key)) {
+        (Some(_), Some(name)) => name.to_owned(),
+        (None, _) => {
             match language {
                 Language::En => format!("Unknown device 0x{:04X}", self_address),
                 Language::De => format!("Unbekanntes Gerät 0x{:04X}", self_address),
-                Language::Fr => format!("Unknown device 0x{:04X}", self_address),  // FIXME(daniel): missing translation
+                Language::Fr => format!("Appareil inconnu 0x{:04X}", self_address),
+                Language::It => format!("Dispositivo sconosciuto 0x{:04X}", self_address),
+                Language::Es => format!("Dispositivo desconocido 0x{:04X}", self_address),
+                Language::Nl => format!("Onbekend apparaat 0x{:04X}", self_address),
             }
         },
-        Some(device_template) => {
+        (Some((file, device_template)), None) => {
             file.localized_text_by_index(&device_template.name_localized_text_index, language).to_owned()
         }
     };
@@ -313,41 +519,63 @@ fn get_or_create_cached_device_spec(devices: &mut Vec<Rc<DeviceSpec>>, channel:
         name: name,
     };
 
-    devices.push(Rc::new(device));
+    let device = Arc::new(device);
 
-    get_cached_device_spec(devices, channel, self_address, peer_address).unwrap()
+    devices.entry((channel, self_address)).or_default().push(device.clone());
+
+    device
 }
 
 
-fn get_cached_packet_spec(packets: &[Rc<PacketSpec>], channel: u8, destination_address: u16, source_address: u16, command: u16) -> Option<Rc<PacketSpec>> {
-    let result = packets.iter().find(|&packet| {
-        if packet.channel != channel {
-            false
-        } else if packet.destination_address != destination_address {
-            false
-        } else if packet.source_address != source_address {
-            false
-        } else if packet.command != command {
-            false
-        } else {
-            true
-        }
-    });
+fn create_packet_field_spec(packet_id: &str, field: &PacketTemplateField, file: &SpecificationFile, spec: &Specification) -> PacketFieldSpec {
+    let language = spec.language;
 
-    match result {
-        Some(packet) => Some(packet.clone()),
-        None => None,
+    let field_id = file.text_by_index(&field.id_text_index).to_string();
+
+    let packet_field_id = format!("{}_{}", packet_id, field_id);
+
+    // translations and extensions are keyed by the packet field ID without the channel prefix
+    let field_key = &packet_field_id [3..];
+
+    let field_name = match spec.translate(field_key) {
+        Some(field_name) => field_name.to_string(),
+        None => file.localized_text_by_index(&field.name_localized_text_index, language).to_string(),
+    };
+
+    let unit = file.unit_by_id(&field.unit_id);
+
+    let unit_family = file.unit_family_by_id(&unit.unit_family_id);
+    let unit_code = file.text_by_index(&unit.unit_code_text_index).to_string();
+    let unit_text = file.text_by_index(&unit.unit_text_text_index).to_string();
+
+    let enum_type = spec.extensions.iter().rev().filter_map(|ext| ext.enum_type_for_field(field_key)).next().cloned();
+
+    let typ = file.type_by_id(&field.type_id);
+
+    PacketFieldSpec {
+        field_id: field_id,
+        packet_field_id: packet_field_id,
+        name: field_name,
+        unit_id: field.unit_id,
+        unit_family: unit_family,
+        unit_code: unit_code,
+        unit_text: unit_text,
+        precision: field.precision,
+        typ: typ,
+        parts: field.parts.clone(),
+        language: language,
+        enum_type,
     }
 }
 
 
-fn get_or_create_cached_packet_spec(packets: &mut Vec<Rc<PacketSpec>>, channel: u8, destination_address: u16, source_address: u16, command: u16, devices: &mut Vec<Rc<DeviceSpec>>, file: &SpecificationFile, language: Language) -> Rc<PacketSpec> {
-    if let Some(packet) = get_cached_packet_spec(packets, channel, destination_address, source_address, command) {
-        return packet;
+fn get_or_create_cached_packet_spec(packets: &mut PacketSpecCache, channel: u8, destination_address: u16, source_address: u16, command: u16, devices: &mut DeviceSpecCache, spec: &Specification) -> Arc<PacketSpec> {
+    if let Some(packet) = packets.get(&(channel, destination_address, source_address, command)) {
+        return packet.clone();
     }
 
-    let destination_device = get_or_create_cached_device_spec(devices, channel, destination_address, source_address, file, language);
-    let source_device = get_or_create_cached_device_spec(devices, channel, source_address, destination_address, file, language);
+    let destination_device = get_or_create_cached_device_spec(devices, channel, destination_address, source_address, spec);
+    let source_device = get_or_create_cached_device_spec(devices, channel, source_address, destination_address, spec);
 
     let packet_id = format!("{:02X}_{:04X}_{:04X}_10_{:04X}", channel, destination_address, source_address, command);
 
@@ -356,40 +584,19 @@ fn get_or_create_cached_packet_spec(packets: &mut Vec<Rc<PacketSpec>>, channel:
         _ => format!("{} => {}", source_device.name, destination_device.name),
     };
 
-    let fields = match file.find_packet_template(destination_address, source_address, command) {
-        None => Vec::new(),
-        Some(packet_template) => {
-            packet_template.fields.iter().map(|field| {
-                let field_id = file.text_by_index(&field.id_text_index).to_string();
-
-                let packet_field_id = format!("{}_{}", packet_id, field_id);
-
-                let field_name = file.localized_text_by_index(&field.name_localized_text_index, language).to_string();
-
-                let unit = file.unit_by_id(&field.unit_id);
+    let mut fields: Vec<PacketFieldSpec> = Vec::new();
+    for (file, index) in spec.files.iter().zip(&spec.indices) {
+        if let Some(packet_template) = index.find_packet_template(file, destination_address, source_address, command) {
+            for field in &packet_template.fields {
+                let field_spec = create_packet_field_spec(&packet_id, field, file, spec);
 
-                let unit_family = file.unit_family_by_id(&unit.unit_family_id);
-                let unit_code = file.text_by_index(&unit.unit_code_text_index).to_string();
-                let unit_text = file.text_by_index(&unit.unit_text_text_index).to_string();
-
-                let typ = file.type_by_id(&field.type_id);
-
-                PacketFieldSpec {
-                    field_id: field_id,
-                    packet_field_id: packet_field_id,
-                    name: field_name,
-                    unit_id: field.unit_id,
-                    unit_family: unit_family,
-                    unit_code: unit_code,
-                    unit_text: unit_text,
-                    precision: field.precision,
-                    typ: typ,
-                    parts: field.parts.clone(),
-                    language: language,
+                match fields.iter().position(|other| other.field_id == field_spec.field_id) {
+                    Some(index) => fields [index] = field_spec,
+                    None => fields.push(field_spec),
                 }
-            }).collect()
-        },
-    };
+            }
+        }
+    }
 
     let packet = PacketSpec {
         packet_id: packet_id,
@@ -403,9 +610,85 @@ fn get_or_create_cached_packet_spec(packets: &mut Vec<Rc<PacketSpec>>, channel:
         fields: fields,
     };
 
-    packets.push(Rc::new(packet));
+    let packet = Arc::new(packet);
+
+    packets.insert((channel, destination_address, source_address, command), packet.clone());
+
+    packet
+}
+
+
+/// A group of parts that form a little-endian integer value, multiplied by the factor of its first part.
+struct PartGroup<'a> {
+    factor: i64,
+    parts: Vec<&'a PacketTemplateFieldPart>,
+}
+
+
+impl<'a> PartGroup<'a> {
+
+    fn is_full_byte(part: &PacketTemplateFieldPart) -> bool {
+        part.mask == 0xFF && part.bit_pos == 0
+    }
+
+    fn from_parts(parts: &'a [PacketTemplateFieldPart]) -> Vec<PartGroup<'a>> {
+        let mut sorted_parts = parts.iter().collect::<Vec<_>>();
+        sorted_parts.sort_by_key(|part| part.factor);
+
+        let mut groups: Vec<PartGroup<'a>> = Vec::new();
+        for part in sorted_parts {
+            let group_index = if PartGroup::is_full_byte(part) {
+                groups.iter().position(|group| {
+                    let last_part = group.parts [group.parts.len() - 1];
+                    PartGroup::is_full_byte(last_part) && !last_part.is_signed && last_part.factor.checked_mul(256) == Some(part.factor)
+                })
+            } else {
+                None
+            };
+
+            match group_index {
+                Some(index) => groups [index].parts.push(part),
+                None => groups.push(PartGroup {
+                    factor: part.factor,
+                    parts: vec![ part ],
+                }),
+            }
+        }
+
+        groups
+    }
+
+    fn is_signed(&self) -> bool {
+        self.parts [self.parts.len() - 1].is_signed
+    }
+
+    fn range(&self) -> (i64, i64) {
+        let first_part = self.parts [0];
+        if !PartGroup::is_full_byte(first_part) {
+            (0, (first_part.mask >> first_part.bit_pos) as i64)
+        } else {
+            let bits = self.parts.len() as u32 * 8;
+            if bits >= 64 {
+                (i64::MIN, i64::MAX)
+            } else if self.is_signed() {
+                (-(1 << (bits - 1)), (1 << (bits - 1)) - 1)
+            } else {
+                (0, (1 << bits) - 1)
+            }
+        }
+    }
+
+    fn write(&self, buf: &mut [u8], value: i64) {
+        for (index, part) in self.parts.iter().enumerate() {
+            let offset = part.offset as usize;
+            if PartGroup::is_full_byte(part) {
+                buf [offset] = (value >> (index * 8)) as u8;
+            } else {
+                buf [offset] = (buf [offset] & !part.mask) | (((value as u8) << part.bit_pos) & part.mask);
+            }
+        }
+    }
 
-    get_cached_packet_spec(packets, channel, destination_address, source_address, command).unwrap()
 }
 
 
@@ -469,17 +752,234 @@ impl Specification {
     /// # drop(spec);
     /// ```
     pub fn from_file(file: SpecificationFile, language: Language) -> Specification {
-        let devices = RefCell::new(Vec::new());
-        let packets = RefCell::new(Vec::new());
+        Self::from_files(vec![ file ], language)
+    }
+
+    /// Construct a `Specification` from a list of `SpecificationFile` layers and a `Language`.
+    ///
+    /// Later layers take precedence over earlier ones. Device templates of later layers hide
+    /// matching device templates of earlier layers. The fields of all matching packet templates
+    /// are merged, fields of later layers replace fields with the same ID of earlier layers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    ///
+    /// # let overrides_file = SpecificationFile::new_default();
+    /// let spec = Specification::from_files(vec![
+    ///     SpecificationFile::new_default(),
+    ///     overrides_file,
+    /// ], Language::De);
+    ///
+    /// // work with the spec...
+    /// # drop(spec);
+    /// ```
+    pub fn from_files(files: Vec<SpecificationFile>, language: Language) -> Specification {
+        let indices = files.iter().map(TemplateIndex::new).collect();
+        let devices = RwLock::new(HashMap::new());
+        let packets = RwLock::new(HashMap::new());
 
         Specification {
-            file: file,
-            language: language,
-            devices: devices,
-            packets: packets,
+            files,
+            indices,
+            translations: Vec::new(),
+            extensions: Vec::new(),
+            parameter_catalogues: Vec::new(),
+            language,
+            devices,
+            packets,
         }
     }
 
+    /// Add a `SpecificationFile` layer that takes precedence over all existing layers.
+    ///
+    /// All previously cached `DeviceSpec` and `PacketSpec` values are discarded.
+    pub fn push_file(&mut self, file: SpecificationFile) {
+        self.indices.push(TemplateIndex::new(&file));
+        self.files.push(file);
+        self.devices.get_mut().unwrap().clear();
+        self.packets.get_mut().unwrap().clear();
+    }
+
+    /// Get the list of `SpecificationFile` layers, ordered by ascending precedence.
+    pub fn files(&self) -> &[SpecificationFile] {
+        &self.files
+    }
+
+    /// Add a `TranslationTable` that takes precedence over all existing translations.
+    ///
+    /// Only tables matching the `Language` of this `Specification` are used to look up device
+    /// and field names, all other names fall back to the localized texts of the
+    /// `SpecificationFile` layers. All previously cached `DeviceSpec` and `PacketSpec` values
+    /// are discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language, TranslationTable};
+    ///
+    /// let mut table = TranslationTable::new(Language::Es);
+    /// table.insert("7E11", "DeltaSol MX [Regulador]");
+    ///
+    /// let mut spec = Specification::from_file(SpecificationFile::new_default(), Language::Es);
+    /// spec.push_translations(table);
+    ///
+    /// let device_spec = spec.get_device_spec(0x00, 0x7E11, 0x0010);
+    /// assert_eq!("DeltaSol MX [Regulador]", device_spec.name);
+    /// ```
+    pub fn push_translations(&mut self, table: TranslationTable) {
+        self.translations.push(table);
+        self.devices.get_mut().unwrap().clear();
+        self.packets.get_mut().unwrap().clear();
+    }
+
+    /// Get the list of `TranslationTable` values, ordered by ascending precedence.
+    pub fn translations(&self) -> &[TranslationTable] {
+        &self.translations
+    }
+
+    /// Add a `SpecificationExtension` that takes precedence over all existing extensions.
+    ///
+    /// All previously cached `DeviceSpec` and `PacketSpec` values are discarded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, SpecificationExtension, Language};
+    ///
+    /// let ext = SpecificationExtension::parse("
+    ///     [flags ErrorMask]
+    ///     0 = Sensor line broken
+    ///     1 = Sensor line short-circuited
+    ///
+    ///     [fields]
+    ///     0010_7E11_10_0100_096_4_0 = ErrorMask
+    /// ").unwrap();
+    ///
+    /// let mut spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+    /// spec.push_extension(ext);
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("096_4_0").unwrap();
+    ///
+    /// assert_eq!("Sensor line broken, Sensor line short-circuited", format!("{}", field_spec.fmt_raw_value(Some(3), true)));
+    /// ```
+    pub fn push_extension(&mut self, ext: SpecificationExtension) {
+        self.extensions.push(ext);
+        self.devices.get_mut().unwrap().clear();
+        self.packets.get_mut().unwrap().clear();
+    }
+
+    /// Get the list of `SpecificationExtension` values, ordered by ascending precedence.
+    pub fn extensions(&self) -> &[SpecificationExtension] {
+        &self.extensions
+    }
+
+    /// Add a `ParameterCatalogue` that takes precedence over all existing catalogues.
+    pub fn push_parameter_catalogue(&mut self, catalogue: ParameterCatalogue) {
+        self.parameter_catalogues.push(catalogue);
+    }
+
+    /// Get the list of `ParameterCatalogue` values, ordered by ascending precedence.
+    pub fn parameter_catalogues(&self) -> &[ParameterCatalogue] {
+        &self.parameter_catalogues
+    }
+
+    /// Get the `Parameter` with the given index of a device address.
+    pub fn get_parameter(&self, address: u16, index: u16) -> Option<&Parameter> {
+        self.parameter_catalogues.iter().rev()
+            .filter_map(|catalogue| catalogue.parameter(address, index))
+            .next()
+    }
+
+    /// Get the `Parameter` a `Datagram` refers to.
+    ///
+    /// See `ParameterCatalogue::parameter_for_datagram` for details.
+    pub fn get_parameter_for_datagram(&self, dgram: &Datagram) -> Option<&Parameter> {
+        self.parameter_catalogues.iter().rev()
+            .filter_map(|catalogue| catalogue.parameter_for_datagram(dgram))
+            .next()
+    }
+
+    fn unit_text_by_code(&self, unit_code: &str) -> &str {
+        self.files.iter().rev()
+            .flat_map(|file| file.units.iter().map(move |unit| (file, unit)))
+            .find(|(file, unit)| file.text_by_index(&unit.unit_code_text_index) == unit_code)
+            .map_or("", |(file, unit)| file.text_by_index(&unit.unit_text_text_index))
+    }
+
+    /// Format a `Datagram` that gets or sets a controller value using the `ParameterCatalogue`s.
+    ///
+    /// Read requests are formatted as the name of the parameter, answers and write requests
+    /// additionally contain the formatted value. Values of unknown parameters are formatted as
+    /// their index and raw value. Datagrams with other commands are formatted as their command
+    /// and parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{Specification, SpecificationFile, Language, ParameterCatalogue};
+    /// use resol_vbus::{Header, Datagram};
+    /// use resol_vbus::utils::utc_timestamp;
+    ///
+    /// let catalogue = ParameterCatalogue::parse("
+    ///     [device 0x7E11]
+    ///     0x0123 = Max tank temperature; DegreesCelsius; 1; 200; 950; rw
+    ///     0x0123.de = Maximale Speichertemperatur
+    /// ").unwrap();
+    ///
+    /// let mut spec = Specification::from_file(SpecificationFile::new_default(), Language::De);
+    /// spec.push_parameter_catalogue(catalogue);
+    ///
+    /// let fmt_dgram = |command, param16, param32| {
+    ///     let dgram = Datagram {
+    ///         header: Header {
+    ///             timestamp: utc_timestamp(1485688933),
+    ///             channel: 0x00,
+    ///             destination_address: 0x7E11,
+    ///             source_address: 0x0020,
+    ///             protocol_version: 0x20,
+    ///         },
+    ///         command,
+    ///         param16,
+    ///         param32,
+    ///     };
+    ///     format!("{}", spec.fmt_datagram(&dgram))
+    /// };
+    ///
+    /// assert_eq!("Maximale Speichertemperatur = 65,5 °C", fmt_dgram(0x0200, 0x0123, 655));
+    /// assert_eq!("Maximale Speichertemperatur", fmt_dgram(0x0300, 0x0123, 0));
+    /// assert_eq!("0x0124 = 655", fmt_dgram(0x0200, 0x0124, 655));
+    /// assert_eq!("0x0500: 0x0000, 0", fmt_dgram(0x0500, 0, 0));
+    /// ```
+    pub fn fmt_datagram(&self, dgram: &Datagram) -> DatagramFormatter<'_> {
+        let parameter = self.get_parameter_for_datagram(dgram);
+
+        let unit_text = match parameter {
+            Some(parameter) if !parameter.unit_code.is_empty() => self.unit_text_by_code(&parameter.unit_code),
+            _ => "",
+        };
+
+        DatagramFormatter {
+            language: self.language,
+            command: dgram.command,
+            param16: dgram.param16,
+            param32: dgram.param32,
+            is_value_command: parameter_catalogue::datagram_parameter_address(dgram).is_some(),
+            parameter,
+            unit_text,
+            options: None,
+        }
+    }
+
+    fn translate(&self, key: &str) -> Option<&str> {
+        self.translations.iter().rev()
+            .filter(|table| table.language() == self.language)
+            .filter_map(|table| table.get(key))
+            .next()
+    }
+
     /// Get a `DeviceSpec`.
     ///
     /// # Examples
@@ -496,9 +996,13 @@ impl Specification {
     /// assert_eq!(None, device_spec.peer_address);
     /// assert_eq!("DeltaSol MX [Regler]", device_spec.name);
     /// ```
-    pub fn get_device_spec(&self, channel: u8, self_address: u16, peer_address: u16) -> Rc<DeviceSpec> {
-        let mut devices = self.devices.borrow_mut();
-        get_or_create_cached_device_spec(&mut devices, channel, self_address, peer_address, &self.file, self.language)
+    pub fn get_device_spec(&self, channel: u8, self_address: u16, peer_address: u16) -> Arc<DeviceSpec> {
+        if let Some(device) = get_cached_device_spec(&self.devices.read().unwrap(), channel, self_address, peer_address) {
+            return device;
+        }
+
+        let mut devices = self.devices.write().unwrap();
+        get_or_create_cached_device_spec(&mut devices, channel, self_address, peer_address, self)
     }
 
     /// Get a `PacketSpec`.
@@ -520,10 +1024,15 @@ impl Specification {
     /// assert_eq!("DeltaSol MX [Regler]", packet_spec.source_device.name);
     /// assert_eq!("DeltaSol MX [Regler]", packet_spec.name);
     /// ```
-    pub fn get_packet_spec(&self, channel: u8, destination_address: u16, source_address: u16, command: u16) -> Rc<PacketSpec> {
-        let mut devices = self.devices.borrow_mut();
-        let mut packets = self.packets.borrow_mut();
-        get_or_create_cached_packet_spec(&mut packets, channel, destination_address, source_address, command, &mut devices, &self.file, self.language)
+    pub fn get_packet_spec(&self, channel: u8, destination_address: u16, source_address: u16, command: u16) -> Arc<PacketSpec> {
+        if let Some(packet) = self.packets.read().unwrap().get(&(channel, destination_address, source_address, command)) {
+            return packet.clone();
+        }
+
+        // always lock `devices` before `packets` to prevent deadlocks
+        let mut devices = self.devices.write().unwrap();
+        let mut packets = self.packets.write().unwrap();
+        get_or_create_cached_packet_spec(&mut packets, channel, destination_address, source_address, command, &mut devices, self)
     }
 
     /// Get a `PacketSpec`.
@@ -545,7 +1054,7 @@ impl Specification {
     /// assert_eq!("DeltaSol MX [Regler]", packet_spec.source_device.name);
     /// assert_eq!("DeltaSol MX [Regler]", packet_spec.name);
     /// ```
-    pub fn get_packet_spec_by_id(&self, packet_id: PacketId) -> Rc<PacketSpec> {
+    pub fn get_packet_spec_by_id(&self, packet_id: PacketId) -> Arc<PacketSpec> {
         self.get_packet_spec(packet_id.0, packet_id.1, packet_id.2, packet_id.3)
     }
 
@@ -576,11 +1085,135 @@ impl Specification {
             data_set: data_set,
             data_index: 0,
             field_index: 0,
+            staleness: None,
+        }
+    }
+
+    /// Get a `DeviceSpec` for every device template of all `SpecificationFile` layers.
+    ///
+    /// Device templates that match a range of addresses are represented by their template address.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+    ///
+    /// let device_specs = spec.device_specs(0x00);
+    /// assert!(device_specs.iter().any(|device_spec| device_spec.name == "DeltaSol MX [Regler]"));
+    /// ```
+    pub fn device_specs(&self, channel: u8) -> Vec<Arc<DeviceSpec>> {
+        let mut device_ids = HashSet::new();
+        let mut device_specs = Vec::new();
+        for file in &self.files {
+            for device_template in &file.device_templates {
+                let device_spec = self.get_device_spec(channel, device_template.self_address, device_template.peer_address);
+                if device_ids.insert(device_spec.device_id.clone()) {
+                    device_specs.push(device_spec);
+                }
+            }
+        }
+        device_specs
+    }
+
+    /// Get a `PacketSpec` for every packet template of all `SpecificationFile` layers.
+    ///
+    /// Packet templates that match a range of addresses are represented by their template
+    /// addresses. The fields of each `PacketSpec` can be inspected without a `DataSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+    ///
+    /// for packet_spec in spec.packet_specs(0x00) {
+    ///     for field_spec in &packet_spec.fields {
+    ///         println!("{}: {} ({})", field_spec.packet_field_id, field_spec.name, field_spec.unit_code);
+    ///     }
+    /// }
+    /// ```
+    pub fn packet_specs(&self, channel: u8) -> Vec<Arc<PacketSpec>> {
+        let mut packet_keys = HashSet::new();
+        let mut packet_specs = Vec::new();
+        for file in &self.files {
+            for packet_template in &file.packet_templates {
+                let key = (packet_template.destination_address, packet_template.source_address, packet_template.command);
+                if packet_keys.insert(key) {
+                    packet_specs.push(self.get_packet_spec(channel, key.0, key.1, key.2));
+                }
+            }
+        }
+        packet_specs
+    }
+
+    /// Find all `PacketSpec` values whose localized name contains the given text, ignoring case.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::De);
+    ///
+    /// let packet_specs = spec.find_packet_specs(0x00, "deltasol mx [regler]");
+    /// assert_eq!("00_0010_7E11_10_0100", packet_specs [0].packet_id);
+    /// ```
+    pub fn find_packet_specs(&self, channel: u8, name: &str) -> Vec<Arc<PacketSpec>> {
+        let name = name.to_lowercase();
+        self.packet_specs(channel).into_iter().filter(|packet_spec| {
+            packet_spec.name.to_lowercase().contains(&name)
+        }).collect()
+    }
+
+    /// Find all packet fields that match a `PacketFieldQuery`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    /// use resol_vbus::specification::PacketFieldQuery;
+    /// use resol_vbus::specification_file::UnitFamily;
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+    ///
+    /// let fields = spec.find_packet_fields(0x00, &PacketFieldQuery {
+    ///     name: Some("sensor 1".to_owned()),
+    ///     unit_family: Some(UnitFamily::Temperature),
+    ///     ..PacketFieldQuery::default()
+    /// });
+    ///
+    /// let field = fields.iter().find(|field| field.packet_spec.packet_id == "00_0010_7E11_10_0100").unwrap();
+    /// assert_eq!("Temperature sensor 1", field.field_spec().name);
+    /// ```
+    pub fn find_packet_fields(&self, channel: u8, query: &PacketFieldQuery) -> Vec<PacketSpecField> {
+        let name = query.name.as_ref().map(|name| name.to_lowercase());
+
+        let mut fields = Vec::new();
+        for packet_spec in self.packet_specs(channel) {
+            for (field_index, field_spec) in packet_spec.fields.iter().enumerate() {
+                let is_match = name.as_ref().is_none_or(|name| field_spec.name.to_lowercase().contains(name))
+                    && query.unit_family.is_none_or(|unit_family| field_spec.unit_family == unit_family)
+                    && query.typ.is_none_or(|typ| field_spec.typ == typ);
+
+                if is_match {
+                    fields.push(PacketSpecField {
+                        packet_spec: packet_spec.clone(),
+                        field_index,
+                    });
+                }
+            }
         }
+        fields
     }
 
     /// Format a timestamp.
     ///
+    /// The timestamp is formatted in UTC using the date format of the `Language`. Use
+    /// `RawValueFormatter::with_options` to select a different format or timezone.
+    ///
     /// # Examples
     ///
     /// ```rust
@@ -596,6 +1229,9 @@ impl Specification {
     /// assert_eq!("29/01/2017 11:22:13", fmt_localized_timestamp(Language::En));
     /// assert_eq!("29.01.2017 11:22:13", fmt_localized_timestamp(Language::De));
     /// assert_eq!("29/01/2017 11:22:13", fmt_localized_timestamp(Language::Fr));
+    /// assert_eq!("29/01/2017 11:22:13", fmt_localized_timestamp(Language::It));
+    /// assert_eq!("29/01/2017 11:22:13", fmt_localized_timestamp(Language::Es));
+    /// assert_eq!("29-01-2017 11:22:13", fmt_localized_timestamp(Language::Nl));
     /// ```
     pub fn fmt_timestamp<Tz: TimeZone>(&self, timestamp: DateTime<Tz>) -> RawValueFormatter {
         RawValueFormatter {
@@ -604,6 +1240,8 @@ impl Specification {
             precision: 0,
             raw_value: timestamp.timestamp() - 978307200,
             unit_text: "",
+            enum_type: None,
+            options: None,
         }
     }
 
@@ -630,6 +1268,16 @@ impl PacketSpec {
 }
 
 
+impl PacketSpecField {
+
+    /// Get the referenced `PacketFieldSpec`.
+    pub fn field_spec(&self) -> &PacketFieldSpec {
+        &self.packet_spec.fields [self.field_index]
+    }
+
+}
+
+
 impl PacketFieldSpec {
 
     /// Construct an `i64` raw value from a slice of bytes.
@@ -674,8 +1322,80 @@ impl PacketFieldSpec {
         }
     }
 
+    /// Interpret a raw value according to the `EnumType` or, if there is none, the `Type` of the field.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language, FieldValue};
+    /// use resol_vbus::chrono::Duration;
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+    ///
+    /// let value = field_spec.field_value(1234);
+    /// assert_eq!(Some(123.4), value.as_f64());
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x1040, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("004_2_0").unwrap();
+    ///
+    /// assert_eq!(FieldValue::Time(Duration::minutes(721)), field_spec.field_value(721));
+    /// ```
+    pub fn field_value(&self, raw_value: i64) -> FieldValue<'_> {
+        if let Some(ref enum_type) = self.enum_type {
+            return match enum_type.kind {
+                EnumKind::Enum => FieldValue::Enum(raw_value, enum_type),
+                EnumKind::BitFlags => FieldValue::BitFlags(self.mask_raw_value(raw_value), enum_type),
+            };
+        }
+
+        match self.typ {
+            Type::Number => FieldValue::Number {
+                value: raw_value as f64 * power_of_ten_f64(- self.precision),
+                precision: self.precision,
+                unit_family: self.unit_family,
+                unit_code: &self.unit_code,
+                unit_text: &self.unit_text,
+            },
+            Type::Time => FieldValue::from_time_raw_value(raw_value),
+            Type::WeekTime => FieldValue::from_week_time_raw_value(raw_value),
+            Type::DateTime => FieldValue::from_date_time_raw_value(raw_value),
+        }
+    }
+
+    /// Construct a `f64` value from a slice of bytes, converted into another unit of the same `UnitFamily`.
+    ///
+    /// Returns `None` if the slice does not contain the field or if the field's unit cannot be
+    /// converted into the requested unit.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+    ///
+    /// let buf = &[ 0xE8, 0x03 ];
+    /// assert_eq!(Some(100.0), field_spec.raw_value_f64(buf));
+    /// assert_eq!("212.0", format!("{:.1}", field_spec.raw_value_f64_in_unit(buf, "DegreesFahrenheit").unwrap()));
+    /// assert_eq!(None, field_spec.raw_value_f64_in_unit(buf, "Liters"));
+    /// ```
+    pub fn raw_value_f64_in_unit(&self, buf: &[u8], unit_code: &str) -> Option<f64> {
+        let value = self.raw_value_f64(buf)?;
+        unit_conversion::convert(value, self.unit_family, &self.unit_code, unit_code)
+    }
+
     /// Format a raw value into its textual representation.
     pub fn fmt_raw_value(&self, raw_value: Option<i64>, append_unit: bool) -> PacketFieldFormatter {
+        let raw_value = match self.enum_type {
+            Some(ref enum_type) if enum_type.kind == EnumKind::BitFlags => raw_value.map(|raw_value| self.mask_raw_value(raw_value)),
+            _ => raw_value,
+        };
         let unit_text = if append_unit {
             &self.unit_text
         } else {
@@ -687,6 +1407,194 @@ impl PacketFieldSpec {
             precision: self.precision,
             raw_value: raw_value,
             unit_text: unit_text,
+            enum_type: self.enum_type.as_deref(),
+            options: None,
+        }
+    }
+
+    /// Format a raw value into its textual representation, converted into the preferred unit of a `UnitSystem`.
+    ///
+    /// The precision is adjusted to the magnitude of the target unit. Fields with a unit that
+    /// is unknown to the `unit_conversion` module, as well as fields that are not of type
+    /// `Type::Number` or that have an `EnumType`, are formatted unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    /// use resol_vbus::unit_conversion::UnitSystem;
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+    ///
+    /// let raw_value = field_spec.raw_value_i64(&[ 0xE8, 0x03 ]);
+    /// assert_eq!("100.0 °C", format!("{}", field_spec.fmt_raw_value_in_unit_system(raw_value, true, UnitSystem::Metric)));
+    /// assert_eq!("212.0 °F", format!("{}", field_spec.fmt_raw_value_in_unit_system(raw_value, true, UnitSystem::UsCustomary)));
+    /// assert_eq!("373.2 K", format!("{}", field_spec.fmt_raw_value_in_unit_system(raw_value, true, UnitSystem::Si)));
+    /// ```
+    pub fn fmt_raw_value_in_unit_system(&self, raw_value: Option<i64>, append_unit: bool, unit_system: UnitSystem) -> PacketFieldFormatter<'_> {
+        let from_unit = unit_conversion::find_unit_definition(self.unit_family, &self.unit_code);
+        let to_unit = unit_system.target_unit(self.unit_family, &self.unit_code);
+
+        match (self.typ, from_unit, to_unit) {
+            (Type::Number, Some(from_unit), Some(to_unit)) if from_unit != to_unit && self.enum_type.is_none() => {
+                // drop one fractional digit for each order of magnitude the target unit is smaller
+                let magnitude = (from_unit.factor / to_unit.factor).log10().round() as i32;
+                let precision = (self.precision - magnitude).max(0);
+
+                let raw_value = raw_value.and_then(|raw_value| {
+                    let value = raw_value as f64 * power_of_ten_f64(- self.precision);
+                    unit_conversion::convert_between(value, from_unit, to_unit)
+                }).map(|value| (value * power_of_ten_f64(precision)).round() as i64);
+
+                PacketFieldFormatter {
+                    language: self.language,
+                    typ: self.typ,
+                    precision,
+                    raw_value,
+                    unit_text: if append_unit { to_unit.unit_text } else { "" },
+                    enum_type: None,
+                    options: None,
+                }
+            }
+            _ => self.fmt_raw_value(raw_value, append_unit),
+        }
+    }
+
+    /// Get the minimum and maximum raw value that can be stored using the field's parts.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::De);
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+    ///
+    /// assert_eq!((-32768, 32767), field_spec.raw_value_range());
+    /// ```
+    pub fn raw_value_range(&self) -> (i64, i64) {
+        PartGroup::from_parts(&self.parts).iter().fold((0i64, 0i64), |(min, max), group| {
+            let (group_min, group_max) = group.range();
+            let min = min.saturating_add(group_min.saturating_mul(group.factor));
+            let max = max.saturating_add(group_max.saturating_mul(group.factor));
+            (min, max)
+        })
+    }
+
+    /// Mask a sign-extended raw value to the number of bits covered by the field's parts.
+    fn mask_raw_value(&self, raw_value: i64) -> i64 {
+        let (min, max) = self.raw_value_range();
+        let bit_count = 64 - (max.wrapping_sub(min) as u64).leading_zeros();
+        if bit_count < 64 {
+            raw_value & ((1i64 << bit_count) - 1)
+        } else {
+            raw_value
+        }
+    }
+
+    /// Write an `i64` raw value into a slice of bytes.
+    ///
+    /// Bits of the slice that are not covered by the field's parts are left untouched, so that
+    /// several bit-packed fields sharing the same byte can be written one after another.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    /// use resol_vbus::specification::EncodeError;
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::De);
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+    ///
+    /// let mut buf = [0u8; 4];
+    /// field_spec.set_raw_value_i64(&mut buf, -1234).unwrap();
+    /// assert_eq!([0x2E, 0xFB, 0x00, 0x00], buf);
+    /// assert_eq!(Some(-1234), field_spec.raw_value_i64(&buf));
+    ///
+    /// assert_eq!(Err(EncodeError::OutOfRange), field_spec.set_raw_value_i64(&mut buf, 32768));
+    /// assert_eq!(Err(EncodeError::BufferTooSmall), field_spec.set_raw_value_i64(&mut buf [0..1], 0));
+    /// ```
+    pub fn set_raw_value_i64(&self, buf: &mut [u8], raw_value: i64) -> Result<(), EncodeError> {
+        let (min, max) = self.raw_value_range();
+        if raw_value < min || raw_value > max {
+            return Err(EncodeError::OutOfRange);
+        }
+
+        if self.parts.iter().any(|part| part.offset < 0 || part.offset as usize >= buf.len()) {
+            return Err(EncodeError::BufferTooSmall);
+        }
+
+        let groups = PartGroup::from_parts(&self.parts);
+
+        // distribute the raw value onto the groups, starting with the largest factor
+        let mut remainder = raw_value;
+        let mut values = vec![ 0; groups.len() ];
+        for (index, group) in groups.iter().enumerate().rev() {
+            // groups without a factor do not contribute to the raw value and are left untouched
+            if group.factor == 0 {
+                continue;
+            }
+
+            let (group_min, group_max) = group.range();
+
+            // if the lower groups cannot absorb a negative remainder, round towards negative
+            // infinity so that the remainder is always non-negative
+            let lower_groups_non_negative = groups [..index].iter().all(|group| group.factor == 0 || group.range().0 >= 0);
+            let value = if lower_groups_non_negative {
+                remainder.div_euclid(group.factor)
+            } else {
+                remainder / group.factor
+            };
+            let value = value.max(group_min).min(group_max);
+
+            remainder -= value * group.factor;
+            values [index] = value;
+        }
+
+        if remainder != 0 {
+            return Err(EncodeError::NotRepresentable);
+        }
+
+        for (group, value) in groups.iter().zip(values) {
+            if group.factor != 0 {
+                group.write(buf, value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write a `f64` value into a slice of bytes, after converting it to a raw value using the field's precision.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language};
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::De);
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+    ///
+    /// let mut buf = [0u8; 4];
+    /// field_spec.set_raw_value_f64(&mut buf, 21.5).unwrap();
+    /// assert_eq!(Some(21.5), field_spec.raw_value_f64(&buf));
+    /// ```
+    pub fn set_raw_value_f64(&self, buf: &mut [u8], value: f64) -> Result<(), EncodeError> {
+        let raw_value = (value * power_of_ten_f64(self.precision)).round();
+        if raw_value.is_nan() {
+            Err(EncodeError::NotRepresentable)
+        } else if raw_value < i64::MIN as f64 || raw_value >= i64::MAX as f64 {
+            Err(EncodeError::OutOfRange)
+        } else {
+            self.set_raw_value_i64(buf, raw_value as i64)
         }
     }
 
@@ -726,6 +1634,39 @@ const WEEKDAYS_FR: [&'static str; 7] = [
 ];
 
 
+const WEEKDAYS_IT: [&str; 7] = [
+    "Lu",
+    "Ma",
+    "Me",
+    "Gi",
+    "Ve",
+    "Sa",
+    "Do",
+];
+
+
+const WEEKDAYS_ES: [&str; 7] = [
+    "Lu",
+    "Ma",
+    "Mi",
+    "Ju",
+    "Vi",
+    "Sá",
+    "Do",
+];
+
+
+const WEEKDAYS_NL: [&str; 7] = [
+    "Ma",
+    "Di",
+    "Wo",
+    "Do",
+    "Vr",
+    "Za",
+    "Zo",
+];
+
+
 impl<'a> RawValueFormatter<'a> {
 
     /// Construct a `RawValueFormatter` to help format a raw value into its textual representation.
@@ -736,17 +1677,130 @@ impl<'a> RawValueFormatter<'a> {
             precision: precision,
             raw_value: raw_value,
             unit_text: unit_text,
+            enum_type: None,
+            options: None,
+        }
+    }
+
+    /// Construct a `RawValueFormatter` to help format a raw value of an `EnumType` into its localized label(s).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::Language;
+    /// use resol_vbus::specification::RawValueFormatter;
+    /// use resol_vbus::specification_extension::{EnumKind, EnumType};
+    ///
+    /// let mut enum_type = EnumType::new("Errors", EnumKind::BitFlags);
+    /// enum_type.insert_label(0, Language::En, "Sensor 1 defective");
+    /// enum_type.insert_label(0, Language::De, "Sensor 1 defekt");
+    ///
+    /// let fmt_flags = |language, raw_value| {
+    ///     format!("{}", RawValueFormatter::new_enum(language, &enum_type, raw_value))
+    /// };
+    ///
+    /// assert_eq!("Sensor 1 defekt", fmt_flags(Language::De, 1));
+    /// assert_eq!("Sensor 1 defective, Bit 2", fmt_flags(Language::En, 5));
+    /// assert_eq!("", fmt_flags(Language::En, 0));
+    /// ```
+    pub fn new_enum(language: Language, enum_type: &'a EnumType, raw_value: i64) -> RawValueFormatter<'a> {
+        RawValueFormatter {
+            language,
+            typ: Type::Number,
+            precision: 0,
+            raw_value,
+            unit_text: "",
+            enum_type: Some(enum_type),
+            options: None,
+        }
+    }
+
+    /// Use the given `FormatOptions` instead of the defaults of the `Language`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{Language, FormatOptions};
+    /// use resol_vbus::specification::RawValueFormatter;
+    /// use resol_vbus::specification_file::Type;
+    ///
+    /// let options = FormatOptions {
+    ///     grouping_separator: Some(",".to_owned()),
+    ///     ..FormatOptions::iso()
+    /// };
+    ///
+    /// let formatter = RawValueFormatter::new(Language::De, Type::Number, 2, -123456789, " Wh");
+    /// assert_eq!("-1234567,89 Wh", format!("{}", formatter));
+    /// assert_eq!("-1,234,567.89 Wh", format!("{}", formatter.with_options(&options)));
+    /// ```
+    pub fn with_options(self, options: &'a FormatOptions) -> RawValueFormatter<'a> {
+        RawValueFormatter {
+            options: Some(options),
+            ..self
+        }
+    }
+
+}
+
+
+fn write_grouped(f: &mut fmt::Formatter, value: i64, grouping_separator: Option<&str>) -> fmt::Result {
+    match grouping_separator {
+        Some(grouping_separator) => {
+            let digits = format!("{}", value);
+            let digits = if let Some(digits) = digits.strip_prefix('-') {
+                write!(f, "-")?;
+                digits
+            } else {
+                &digits
+            };
+            for (index, digit) in digits.chars().enumerate() {
+                if index > 0 && (digits.len() - index) % 3 == 0 {
+                    write!(f, "{}", grouping_separator)?;
+                }
+                write!(f, "{}", digit)?;
+            }
+            Ok(())
         }
+        None => write!(f, "{}", value),
     }
+}
 
+
+fn fmt_enum_value(f: &mut fmt::Formatter, language: Language, enum_type: &EnumType, raw_value: i64) -> fmt::Result {
+    match enum_type.kind {
+        EnumKind::Enum => {
+            match enum_type.variant(raw_value) {
+                Some(variant) => write!(f, "{}", variant.label(language)),
+                None => write!(f, "{}", raw_value),
+            }
+        }
+        EnumKind::BitFlags => {
+            let mut separator = "";
+            for bit in 0..64 {
+                if (raw_value >> bit) & 1 != 0 {
+                    match enum_type.variant(bit) {
+                        Some(variant) => write!(f, "{}{}", separator, variant.label(language))?,
+                        None => write!(f, "{}Bit {}", separator, bit)?,
+                    }
+                    separator = ", ";
+                }
+            }
+            Ok(())
+        }
+    }
 }
 
 
 impl<'a> fmt::Display for RawValueFormatter<'a> {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(enum_type) = self.enum_type {
+            return fmt_enum_value(f, self.language, enum_type, self.raw_value);
+        }
+
         match self.typ {
             Type::Number => {
+                let grouping_separator = self.options.and_then(|options| options.grouping_separator.as_deref());
                 if self.precision > 0 {
                     let sign = if self.raw_value < 0 {
                         "-"
@@ -757,12 +1811,14 @@ impl<'a> fmt::Display for RawValueFormatter<'a> {
                     let factor = power_of_ten_i64(self.precision as u32);
                     let left_part = raw_value / factor;
                     let right_part = raw_value % factor;
-                    let separator = match self.language {
-                        Language::En => ".",
-                        Language::De | Language::Fr => ",",
+                    let separator = match self.options {
+                        Some(options) => &options.decimal_separator,
+                        None => format_options::decimal_separator(self.language),
                     };
 
-                    write!(f, "{}{}{}", sign, left_part, separator)?;
+                    write!(f, "{}", sign)?;
+                    write_grouped(f, left_part, grouping_separator)?;
+                    write!(f, "{}", separator)?;
                     match self.precision {
                         1 => write!(f, "{:01}", right_part)?,
                         2 => write!(f, "{:02}", right_part)?,
@@ -780,32 +1836,41 @@ impl<'a> fmt::Display for RawValueFormatter<'a> {
                     };
                     write!(f, "{}", self.unit_text)
                 } else {
-                    write!(f, "{}{}", self.raw_value, self.unit_text)
+                    write_grouped(f, self.raw_value, grouping_separator)?;
+                    write!(f, "{}", self.unit_text)
                 }
             }
             Type::Time => {
                 let hours = self.raw_value / 60;
                 let minutes = self.raw_value % 60;
-                write!(f, "{:02}:{:02}", hours, minutes)
+                match self.options {
+                    Some(options) => write!(f, "{}", options.fmt_time(hours, minutes)),
+                    None => write!(f, "{:02}:{:02}", hours, minutes),
+                }
             }
             Type::WeekTime => {
                 let weekday_idx = ((self.raw_value / 1440) % 7) as usize;
                 let hours = (self.raw_value / 60) % 24;
                 let minutes = self.raw_value % 60;
-                match self.language {
-                    Language::En => write!(f, "{},{:02}:{:02}", WEEKDAYS_EN [weekday_idx], hours, minutes),
-                    Language::De => write!(f, "{},{:02}:{:02}", WEEKDAYS_DE [weekday_idx], hours, minutes),
-                    Language::Fr => write!(f, "{},{:02}:{:02}", WEEKDAYS_FR [weekday_idx], hours, minutes),
+                let weekdays = match self.language {
+                    Language::En => WEEKDAYS_EN,
+                    Language::De => WEEKDAYS_DE,
+                    Language::Fr => WEEKDAYS_FR,
+                    Language::It => WEEKDAYS_IT,
+                    Language::Es => WEEKDAYS_ES,
+                    Language::Nl => WEEKDAYS_NL,
+                };
+                match self.options {
+                    Some(options) => write!(f, "{},{}", weekdays [weekday_idx], options.fmt_time(hours, minutes)),
+                    None => write!(f, "{},{:02}:{:02}", weekdays [weekday_idx], hours, minutes),
                 }
             }
             Type::DateTime => {
-                let timestamp = UTC.timestamp(self.raw_value + 978307200, 0);
-                match self.language {
-                    Language::En | Language::Fr => {
-                        write!(f, "{}", timestamp.format("%d/%m/%Y %H:%M:%S"))
-                    }
-                    Language::De => {
-                        write!(f, "{}", timestamp.format("%d.%m.%Y %H:%M:%S"))
+                match self.options {
+                    Some(options) => write!(f, "{}", options.fmt_date_time(self.raw_value + 978307200)),
+                    None => {
+                        let timestamp = UTC.timestamp(self.raw_value + 978307200, 0);
+                        write!(f, "{}", timestamp.format(format_options::date_time_pattern(self.language)))
                     }
                 }
             }
@@ -815,11 +1880,49 @@ impl<'a> fmt::Display for RawValueFormatter<'a> {
 }
 
 
+impl<'a> PacketFieldFormatter<'a> {
+
+    /// Use the given `FormatOptions` instead of the defaults of the `Language`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{SpecificationFile, Specification, Language, FormatOptions};
+    ///
+    /// let spec = Specification::from_file(SpecificationFile::new_default(), Language::De);
+    ///
+    /// let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+    /// let field_spec = packet_spec.get_field_spec("000_2_0").unwrap();
+    ///
+    /// let options = FormatOptions::iso();
+    ///
+    /// assert_eq!("Temperatur Sensor 1", field_spec.name);
+    /// assert_eq!("12,3 °C", format!("{}", field_spec.fmt_raw_value(Some(123), true)));
+    /// assert_eq!("12.3 °C", format!("{}", field_spec.fmt_raw_value(Some(123), true).with_options(&options)));
+    /// ```
+    pub fn with_options(self, options: &'a FormatOptions) -> PacketFieldFormatter<'a> {
+        PacketFieldFormatter {
+            options: Some(options),
+            ..self
+        }
+    }
+
+}
+
+
 impl<'a> fmt::Display for PacketFieldFormatter<'a> {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if let Some(raw_value) = self.raw_value {
-            let formatter = RawValueFormatter::new(self.language, self.typ, self.precision, raw_value, self.unit_text);
+            let formatter = RawValueFormatter {
+                language: self.language,
+                typ: self.typ,
+                precision: self.precision,
+                raw_value,
+                unit_text: self.unit_text,
+                enum_type: self.enum_type,
+                options: self.options,
+            };
             formatter.fmt(f)
         } else {
             Ok(())
@@ -829,6 +1932,63 @@ impl<'a> fmt::Display for PacketFieldFormatter<'a> {
 }
 
 
+impl<'a> DatagramFormatter<'a> {
+
+    /// Use the given `FormatOptions` instead of the defaults of the `Language`.
+    pub fn with_options(self, options: &'a FormatOptions) -> DatagramFormatter<'a> {
+        DatagramFormatter {
+            options: Some(options),
+            ..self
+        }
+    }
+
+}
+
+
+impl<'a> fmt::Display for DatagramFormatter<'a> {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.is_value_command {
+            return write!(f, "0x{:04X}: 0x{:04X}, {}", self.command, self.param16 as u16, self.param32);
+        }
+
+        match self.parameter {
+            Some(parameter) => {
+                write!(f, "{}", parameter.name(self.language))?;
+                if self.command != parameter_catalogue::COMMAND_READ_VALUE {
+                    let formatter = RawValueFormatter::new(self.language, Type::Number, parameter.precision, self.param32 as i64, self.unit_text);
+                    match self.options {
+                        Some(options) => write!(f, " = {}", formatter.with_options(options))?,
+                        None => write!(f, " = {}", formatter)?,
+                    }
+                }
+                Ok(())
+            }
+            None => {
+                write!(f, "0x{:04X}", self.param16 as u16)?;
+                if self.command != parameter_catalogue::COMMAND_READ_VALUE {
+                    write!(f, " = {}", self.param32)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+}
+
+
+impl<'a, T: AsRef<[Data]> + 'a> DataSetPacketFieldIterator<'a, T> {
+
+    /// Mark the fields of `Data` values that are stale at the given point in time according to
+    /// the `StalenessPolicy`.
+    pub fn with_staleness(mut self, policy: &'a StalenessPolicy, now: DateTime<UTC>) -> DataSetPacketFieldIterator<'a, T> {
+        self.staleness = Some((policy, now));
+        self
+    }
+
+}
+
+
 impl<'a, T: AsRef<[Data]> + 'a> Iterator for DataSetPacketFieldIterator<'a, T> {
     type Item = DataSetPacketField<'a, T>;
 
@@ -849,12 +2009,18 @@ impl<'a, T: AsRef<[Data]> + 'a> Iterator for DataSetPacketFieldIterator<'a, T> {
                     let field_spec = &packet_spec.fields [field_index];
                     let raw_value = field_spec.raw_value_i64(frame_data);
 
+                    let stale = match self.staleness {
+                        Some((policy, now)) => policy.is_stale(data, now),
+                        None => false,
+                    };
+
                     return Some(DataSetPacketField {
                         data_set: self.data_set,
                         data_index: self.data_index,
                         packet_spec: packet_spec.clone(),
                         field_index: field_index,
                         raw_value: raw_value,
+                        stale,
                     });
                 }
             }
@@ -871,13 +2037,14 @@ impl<'a, T: AsRef<[Data]> + 'a> Iterator for DataSetPacketFieldIterator<'a, T> {
 impl<'a, T: AsRef<[Data]>> DataSetPacketField<'a, T> {
 
     /// Construct new `DataSetPacketField` value.
-    pub fn new(data_set: &'a T, data_index: usize, packet_spec: Rc<PacketSpec>, field_index: usize, raw_value: Option<i64>) -> DataSetPacketField<'a, T> {
+    pub fn new(data_set: &'a T, data_index: usize, packet_spec: Arc<PacketSpec>, field_index: usize, raw_value: Option<i64>) -> DataSetPacketField<'a, T> {
         DataSetPacketField {
             data_set: data_set,
             data_index: data_index,
             packet_spec: packet_spec,
             field_index: field_index,
             raw_value: raw_value,
+            stale: false,
         }
     }
 
@@ -926,6 +2093,13 @@ impl<'a, T: AsRef<[Data]>> DataSetPacketField<'a, T> {
         PacketFieldId(self.data().as_packet().packet_id(), &self.field_spec().field_id)
     }
 
+    /// Return whether the `Data` associated with this field is stale.
+    ///
+    /// Fields are only marked stale if the iterator was configured using `with_staleness`.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
     /// Return the raw value associated with this field.
     pub fn raw_value_i64(&self) -> &Option<i64> {
         &self.raw_value
@@ -939,9 +2113,48 @@ impl<'a, T: AsRef<[Data]>> DataSetPacketField<'a, T> {
         }
     }
 
-    /// Format the raw value associated with this field.
-    pub fn fmt_raw_value(&self, append_unit: bool) -> PacketFieldFormatter {
-        self.field_spec().fmt_raw_value(self.raw_value, append_unit)
+    /// Get the raw value associated with this field as a `f64`, converted into another unit of the same `UnitFamily`.
+    pub fn raw_value_f64_in_unit(&self, unit_code: &str) -> Option<f64> {
+        let field_spec = self.field_spec();
+        let value = self.raw_value_f64()?;
+        unit_conversion::convert(value, field_spec.unit_family, &field_spec.unit_code, unit_code)
+    }
+
+    /// Get the raw value associated with this field, interpreted according to the field's `Type`.
+    pub fn field_value(&self) -> Option<FieldValue<'_>> {
+        self.raw_value.map(|raw_value| self.field_spec().field_value(raw_value))
+    }
+
+    /// Format the raw value associated with this field.
+    pub fn fmt_raw_value(&self, append_unit: bool) -> PacketFieldFormatter {
+        self.field_spec().fmt_raw_value(self.raw_value, append_unit)
+    }
+
+    /// Format the raw value associated with this field, converted into the preferred unit of a `UnitSystem`.
+    pub fn fmt_raw_value_in_unit_system(&self, append_unit: bool, unit_system: UnitSystem) -> PacketFieldFormatter<'_> {
+        self.field_spec().fmt_raw_value_in_unit_system(self.raw_value, append_unit, unit_system)
+    }
+
+    /// Create an owned `PacketFieldSnapshot` of this field.
+    pub fn to_snapshot(&self) -> PacketFieldSnapshot {
+        let field_spec = self.field_spec();
+        PacketFieldSnapshot {
+            timestamp: self.data().as_header().timestamp,
+            packet_id: self.packet_id(),
+            field_id: field_spec.field_id.clone(),
+            packet_field_id: self.packet_field_id().packet_field_id_string(),
+            packet_name: self.packet_spec.name.clone(),
+            field_name: field_spec.name.clone(),
+            unit_code: field_spec.unit_code.clone(),
+            unit_text: field_spec.unit_text.clone(),
+            unit_family: field_spec.unit_family,
+            precision: field_spec.precision,
+            typ: field_spec.typ,
+            raw_value: self.raw_value,
+            value: self.raw_value_f64(),
+            text: format!("{}", self.fmt_raw_value(false)),
+            stale: self.stale,
+        }
     }
 
 }
@@ -949,6 +2162,8 @@ impl<'a, T: AsRef<[Data]>> DataSetPacketField<'a, T> {
 
 #[cfg(test)]
 mod tests {
+    use chrono::Duration;
+
     use recording_reader::RecordingReader;
 
     use super::*;
@@ -988,6 +2203,9 @@ mod tests {
         assert_eq!("1.2345678900", fmt_to_string(En, Number, 10, 12345678900, ""));
         assert_eq!("1,2345678900", fmt_to_string(De, Number, 10, 12345678900, ""));
         assert_eq!("1,2345678900", fmt_to_string(Fr, Number, 10, 12345678900, ""));
+        assert_eq!("1,2345678900", fmt_to_string(It, Number, 10, 12345678900, ""));
+        assert_eq!("1,2345678900", fmt_to_string(Es, Number, 10, 12345678900, ""));
+        assert_eq!("1,2345678900", fmt_to_string(Nl, Number, 10, 12345678900, ""));
 
         assert_eq!("12:01", fmt_to_string(En, Time, 10, 721, " ignore this unit"));
         assert_eq!("12:01", fmt_to_string(De, Time, 10, 721, " ignore this unit"));
@@ -996,10 +2214,62 @@ mod tests {
         assert_eq!("Th,12:01", fmt_to_string(En, WeekTime, 10, 3 * 1440 + 721, " ignore this unit"));
         assert_eq!("Do,12:01", fmt_to_string(De, WeekTime, 10, 3 * 1440 + 721, " ignore this unit"));
         assert_eq!("Je,12:01", fmt_to_string(Fr, WeekTime, 10, 3 * 1440 + 721, " ignore this unit"));
+        assert_eq!("Gi,12:01", fmt_to_string(It, WeekTime, 10, 3 * 1440 + 721, " ignore this unit"));
+        assert_eq!("Ju,12:01", fmt_to_string(Es, WeekTime, 10, 3 * 1440 + 721, " ignore this unit"));
+        assert_eq!("Do,12:01", fmt_to_string(Nl, WeekTime, 10, 3 * 1440 + 721, " ignore this unit"));
 
         assert_eq!("22/12/2013 15:17:42", fmt_to_string(En, DateTime, 10, 409418262, " ignore this unit"));
         assert_eq!("22.12.2013 15:17:42", fmt_to_string(De, DateTime, 10, 409418262, " ignore this unit"));
         assert_eq!("22/12/2013 15:17:42", fmt_to_string(Fr, DateTime, 10, 409418262, " ignore this unit"));
+        assert_eq!("22/12/2013 15:17:42", fmt_to_string(It, DateTime, 10, 409418262, " ignore this unit"));
+        assert_eq!("22/12/2013 15:17:42", fmt_to_string(Es, DateTime, 10, 409418262, " ignore this unit"));
+        assert_eq!("22-12-2013 15:17:42", fmt_to_string(Nl, DateTime, 10, 409418262, " ignore this unit"));
+
+        let enum_type = EnumType::new("State", EnumKind::Enum);
+        assert_eq!("42", format!("{}", RawValueFormatter::new_enum(En, &enum_type, 42)));
+
+        let enum_type = EnumType::new("Errors", EnumKind::BitFlags);
+        assert_eq!("Bit 0, Bit 3", format!("{}", RawValueFormatter::new_enum(En, &enum_type, 9)));
+        assert_eq!("", format!("{}", RawValueFormatter::new_enum(En, &enum_type, 0)));
+    }
+
+    #[test]
+    fn test_raw_value_formatter_with_options() {
+        use chrono::FixedOffset;
+        use format_options::Timezone;
+        use specification_file::Language::*;
+        use specification_file::Type::*;
+
+        let options = FormatOptions {
+            decimal_separator: "'".to_owned(),
+            grouping_separator: Some(" ".to_owned()),
+            time_pattern: "%Hh%M".to_owned(),
+            date_time_pattern: "%Y-%m-%d %H:%M:%S %z".to_owned(),
+            timezone: Timezone::Fixed(FixedOffset::west(2 * 3600)),
+        };
+
+        let fmt_to_string = |language, typ, prec, value, unit| {
+            let formatter = RawValueFormatter::new(language, typ, prec, value, unit).with_options(&options);
+            format!("{}", formatter)
+        };
+
+        assert_eq!("123", fmt_to_string(De, Number, 0, 123, ""));
+        assert_eq!("1 234", fmt_to_string(De, Number, 0, 1234, ""));
+        assert_eq!("-123 456 unit", fmt_to_string(De, Number, 0, -123456, " unit"));
+        assert_eq!("-9 223 372 036 854 775 808", fmt_to_string(De, Number, 0, i64::MIN, ""));
+        assert_eq!("12 345'7", fmt_to_string(En, Number, 1, 123457, ""));
+        assert_eq!("-123'4567", fmt_to_string(En, Number, 4, -1234567, ""));
+
+        assert_eq!("12h01", fmt_to_string(En, Time, 10, 721, " ignore this unit"));
+        assert_eq!("Do,12h01", fmt_to_string(De, WeekTime, 10, 3 * 1440 + 721, " ignore this unit"));
+
+        assert_eq!("2013-12-22 13:17:42 -0200", fmt_to_string(De, DateTime, 10, 409418262, " ignore this unit"));
+
+        let options = FormatOptions::for_language(Nl);
+        for &(typ, value) in &[ (Number, 1234567), (Time, 721), (WeekTime, 3 * 1440 + 721), (DateTime, 409418262) ] {
+            let formatter = RawValueFormatter::new(Nl, typ, 2, value, " unit");
+            assert_eq!(format!("{}", formatter), format!("{}", formatter.with_options(&options)));
+        }
     }
 
     #[test]
@@ -1008,8 +2278,8 @@ mod tests {
 
         let spec = Specification::from_file(spec_file, Language::En);
 
-        assert_eq!(0, spec.devices.borrow().len());
-        assert_eq!(0, spec.packets.borrow().len());
+        assert_eq!(0, spec.devices.read().unwrap().len());
+        assert_eq!(0, spec.packets.read().unwrap().len());
     }
 
     #[test]
@@ -1018,11 +2288,11 @@ mod tests {
 
         let spec = Specification::from_file(spec_file, Language::En);
 
-        assert_eq!(0, spec.devices.borrow().len());
+        assert_eq!(0, spec.devices.read().unwrap().len());
 
         let device_spec = spec.get_device_spec(0x01, 0x7E31, 0x0010);
 
-        assert_eq!(1, spec.devices.borrow().len());
+        assert_eq!(1, spec.devices.read().unwrap().len());
         assert_eq!("01_7E31", device_spec.device_id);
         assert_eq!(0x01, device_spec.channel);
         assert_eq!(0x7E31, device_spec.self_address);
@@ -1031,12 +2301,12 @@ mod tests {
 
         let device_spec = spec.get_device_spec(0x01, 0x7E31, 0x0010);
 
-        assert_eq!(1, spec.devices.borrow().len());
+        assert_eq!(1, spec.devices.read().unwrap().len());
         assert_eq!("01_7E31", device_spec.device_id);
 
         let device_spec = spec.get_device_spec(0x00, 0x7E31, 0x0010);
 
-        assert_eq!(2, spec.devices.borrow().len());
+        assert_eq!(2, spec.devices.read().unwrap().len());
         assert_eq!("00_7E31", device_spec.device_id);
         assert_eq!(0x00, device_spec.channel);
         assert_eq!(0x7E31, device_spec.self_address);
@@ -1045,7 +2315,7 @@ mod tests {
 
         let device_spec = spec.get_device_spec(0x00, 0x7E11, 0x0010);
 
-        assert_eq!(3, spec.devices.borrow().len());
+        assert_eq!(3, spec.devices.read().unwrap().len());
         assert_eq!("00_7E11", device_spec.device_id);
         assert_eq!(0x00, device_spec.channel);
         assert_eq!(0x7E11, device_spec.self_address);
@@ -1059,11 +2329,11 @@ mod tests {
 
         let spec = Specification::from_file(spec_file, Language::En);
 
-        assert_eq!(0, spec.packets.borrow().len());
+        assert_eq!(0, spec.packets.read().unwrap().len());
 
         let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
 
-        assert_eq!(1, spec.packets.borrow().len());
+        assert_eq!(1, spec.packets.read().unwrap().len());
         assert_eq!("01_0010_7E31_10_0100", packet_spec.packet_id);
         assert_eq!(0x01, packet_spec.channel);
         assert_eq!(0x0010, packet_spec.destination_address);
@@ -1088,12 +2358,12 @@ mod tests {
 
         let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
 
-        assert_eq!(1, spec.packets.borrow().len());
+        assert_eq!(1, spec.packets.read().unwrap().len());
         assert_eq!("01_0010_7E31_10_0100", packet_spec.packet_id);
 
         let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E31, 0x0100);
 
-        assert_eq!(2, spec.packets.borrow().len());
+        assert_eq!(2, spec.packets.read().unwrap().len());
         assert_eq!("00_0010_7E31_10_0100", packet_spec.packet_id);
         assert_eq!(0x00, packet_spec.channel);
         assert_eq!(0x0010, packet_spec.destination_address);
@@ -1106,7 +2376,7 @@ mod tests {
 
         let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
 
-        assert_eq!(3, spec.packets.borrow().len());
+        assert_eq!(3, spec.packets.read().unwrap().len());
         assert_eq!("00_0010_7E11_10_0100", packet_spec.packet_id);
         assert_eq!(0x00, packet_spec.channel);
         assert_eq!(0x0010, packet_spec.destination_address);
@@ -1118,13 +2388,415 @@ mod tests {
         assert_eq!(0, packet_spec.fields.len());
     }
 
+    fn layer_spec_file() -> SpecificationFile {
+        use specification_file::{
+            LocalizedText,
+            LocalizedTextIndex,
+            TextIndex,
+            Unit,
+            UnitFamilyId,
+            DeviceTemplate,
+            PacketTemplate,
+            TypeId,
+        };
+
+        let texts = [
+            "",
+            " °C",
+            "000_4_0",
+            "100_2_0",
+            "DegreesCelsius",
+            "Heat quantity (corrected)",
+            "In-house controller",
+            "None",
+            "Temperature sensor 1",
+            "WMZ override",
+        ].iter().map(|text| text.to_string()).collect();
+
+        let localized_text = |index| LocalizedText {
+            text_index_en: TextIndex(index),
+            text_index_de: TextIndex(index),
+            text_index_fr: TextIndex(index),
+        };
+
+        let part = |offset, factor| PacketTemplateFieldPart {
+            offset,
+            bit_pos: 0,
+            mask: 0xFF,
+            is_signed: false,
+            factor,
+        };
+
+        SpecificationFile {
+            datecode: 20170101,
+            texts,
+            localized_texts: vec![
+                localized_text(5),
+                localized_text(6),
+                localized_text(8),
+                localized_text(9),
+            ],
+            units: vec![
+                Unit {
+                    unit_id: UnitId(-1),
+                    unit_family_id: UnitFamilyId(-1),
+                    unit_code_text_index: TextIndex(7),
+                    unit_text_text_index: TextIndex(0),
+                },
+                Unit {
+                    unit_id: UnitId(62),
+                    unit_family_id: UnitFamilyId(0),
+                    unit_code_text_index: TextIndex(4),
+                    unit_text_text_index: TextIndex(1),
+                },
+            ],
+            device_templates: vec![
+                DeviceTemplate {
+                    self_address: 0x7E31,
+                    self_mask: 0xFFFF,
+                    peer_address: 0x0000,
+                    peer_mask: 0x0000,
+                    name_localized_text_index: LocalizedTextIndex(3),
+                },
+                DeviceTemplate {
+                    self_address: 0x7E11,
+                    self_mask: 0xFFFF,
+                    peer_address: 0x0000,
+                    peer_mask: 0x0000,
+                    name_localized_text_index: LocalizedTextIndex(1),
+                },
+            ],
+            packet_templates: vec![
+                PacketTemplate {
+                    destination_address: 0x0010,
+                    destination_mask: 0xFFFF,
+                    source_address: 0x7E31,
+                    source_mask: 0xFFFF,
+                    command: 0x0100,
+                    fields: vec![
+                        PacketTemplateField {
+                            id_text_index: TextIndex(2),
+                            name_localized_text_index: LocalizedTextIndex(0),
+                            unit_id: UnitId(-1),
+                            precision: 0,
+                            type_id: TypeId(1),
+                            parts: vec![ part(0, 1), part(1, 256) ],
+                        },
+                        PacketTemplateField {
+                            id_text_index: TextIndex(3),
+                            name_localized_text_index: LocalizedTextIndex(2),
+                            unit_id: UnitId(62),
+                            precision: 1,
+                            type_id: TypeId(1),
+                            parts: vec![ part(100, 1), part(101, 256) ],
+                        },
+                    ],
+                },
+                PacketTemplate {
+                    destination_address: 0x0010,
+                    destination_mask: 0xFFFF,
+                    source_address: 0x7E11,
+                    source_mask: 0xFFFF,
+                    command: 0x0100,
+                    fields: vec![
+                        PacketTemplateField {
+                            id_text_index: TextIndex(3),
+                            name_localized_text_index: LocalizedTextIndex(2),
+                            unit_id: UnitId(62),
+                            precision: 1,
+                            type_id: TypeId(1),
+                            parts: vec![ part(100, 1), part(101, 256) ],
+                        },
+                    ],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Specification>();
+        assert_send_sync::<Arc<PacketSpec>>();
+    }
+
+    #[test]
+    fn test_concurrent_lookups() {
+        use std::thread;
+
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Arc::new(Specification::from_file(spec_file, Language::En));
+
+        let threads = (0..8).map(|index| {
+            let spec = spec.clone();
+            thread::spawn(move || {
+                (0..100).map(|round| {
+                    let channel = ((index + round) % 4) as u8;
+                    spec.get_packet_spec(channel, 0x0010, 0x7E31, 0x0100)
+                }).collect::<Vec<_>>()
+            })
+        }).collect::<Vec<_>>();
+
+        for thread in threads {
+            for packet_spec in thread.join().unwrap() {
+                let other_packet_spec = spec.get_packet_spec(packet_spec.channel, 0x0010, 0x7E31, 0x0100);
+                assert!(Arc::ptr_eq(&packet_spec, &other_packet_spec));
+                assert_eq!(8, packet_spec.fields.len());
+            }
+        }
+
+        assert_eq!(4, spec.packets.read().unwrap().len());
+        assert_eq!(8, spec.devices.read().unwrap().len());
+    }
+
+    #[test]
+    fn test_from_files() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_files(vec![ spec_file, layer_spec_file() ], Language::En);
+
+        assert_eq!(2, spec.files().len());
+
+        let device_spec = spec.get_device_spec(0x00, 0x0010, 0x7E31);
+        assert_eq!("DFA", device_spec.name);
+
+        let device_spec = spec.get_device_spec(0x00, 0x7E31, 0x0010);
+        assert_eq!("WMZ override", device_spec.name);
+
+        let device_spec = spec.get_device_spec(0x00, 0x7E11, 0x0010);
+        assert_eq!("In-house controller", device_spec.name);
+
+        let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E31, 0x0100);
+        assert_eq!("WMZ override", packet_spec.name);
+        assert_eq!(9, packet_spec.fields.len());
+
+        let field_spec = &packet_spec.fields [0];
+        assert_eq!("000_4_0", field_spec.field_id);
+        assert_eq!("Heat quantity (corrected)", field_spec.name);
+        assert_eq!("None", field_spec.unit_code);
+        assert_eq!(2, field_spec.parts.len());
+
+        let field_spec = &packet_spec.fields [1];
+        assert_eq!("008_4_0", field_spec.field_id);
+        assert_eq!("WattHours", field_spec.unit_code);
+
+        let field_spec = &packet_spec.fields [8];
+        assert_eq!("100_2_0", field_spec.field_id);
+        assert_eq!("00_0010_7E31_10_0100_100_2_0", field_spec.packet_field_id);
+        assert_eq!("Temperature sensor 1", field_spec.name);
+        assert_eq!(" °C", field_spec.unit_text);
+
+        let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+        assert_eq!("In-house controller", packet_spec.name);
+        assert_eq!(1, packet_spec.fields.len());
+        assert_eq!("100_2_0", packet_spec.fields [0].field_id);
+    }
+
+    #[test]
+    fn test_push_file() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let mut spec = Specification::from_file(spec_file, Language::En);
+
+        let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E31, 0x0100);
+        assert_eq!("DeltaSol MX [WMZ #1]", packet_spec.name);
+        assert_eq!(8, packet_spec.fields.len());
+        assert_eq!(1, spec.packets.read().unwrap().len());
+
+        spec.push_file(layer_spec_file());
+
+        assert_eq!(0, spec.devices.read().unwrap().len());
+        assert_eq!(0, spec.packets.read().unwrap().len());
+
+        let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E31, 0x0100);
+        assert_eq!("WMZ override", packet_spec.name);
+        assert_eq!(9, packet_spec.fields.len());
+    }
+
+    #[test]
+    fn test_device_and_packet_specs() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let packet_template_count = spec_file.packet_templates.len();
+
+        let spec = Specification::from_files(vec![ spec_file, layer_spec_file() ], Language::En);
+
+        let device_specs = spec.device_specs(0x01);
+        let device_ids = device_specs.iter().map(|device_spec| device_spec.device_id.as_str()).collect::<HashSet<_>>();
+        assert_eq!(device_specs.len(), device_ids.len());
+        assert!(device_ids.contains("01_7E31"));
+
+        let device_spec = device_specs.iter().find(|device_spec| device_spec.device_id == "01_7E11").unwrap();
+        assert_eq!("VBus 1: In-house controller", device_spec.name);
+
+        let packet_specs = spec.packet_specs(0x01);
+        let packet_ids = packet_specs.iter().map(|packet_spec| packet_spec.packet_id.as_str()).collect::<HashSet<_>>();
+        assert_eq!(packet_specs.len(), packet_ids.len());
+        assert!(packet_specs.len() >= packet_template_count);
+
+        let packet_spec = packet_specs.iter().find(|packet_spec| packet_spec.packet_id == "01_0010_7E31_10_0100").unwrap();
+        assert_eq!(9, packet_spec.fields.len());
+
+        let packet_specs = spec.find_packet_specs(0x01, "WMZ OVERRIDE");
+        assert_eq!(1, packet_specs.len());
+        assert_eq!("01_0010_7E31_10_0100", packet_specs [0].packet_id);
+
+        assert_eq!(0, spec.find_packet_specs(0x01, "unknown packet").len());
+
+        let fields = spec.find_packet_fields(0x01, &PacketFieldQuery {
+            name: Some("Temperature SENSOR 1".to_owned()),
+            unit_family: Some(UnitFamily::Temperature),
+            typ: Some(Type::Number),
+        });
+        assert!(fields.iter().all(|field| field.field_spec().name.contains("Temperature sensor 1")));
+
+        let field = fields.iter().find(|field| field.packet_spec.packet_id == "01_0010_7E31_10_0100").unwrap();
+        assert_eq!(8, field.field_index);
+        assert_eq!("100_2_0", field.field_spec().field_id);
+
+        let fields = spec.find_packet_fields(0x01, &PacketFieldQuery {
+            unit_family: Some(UnitFamily::Temperature),
+            typ: Some(Type::DateTime),
+            ..PacketFieldQuery::default()
+        });
+        assert_eq!(0, fields.len());
+
+        let field_count = spec.packet_specs(0x01).iter().map(|packet_spec| packet_spec.fields.len()).sum::<usize>();
+        assert_eq!(field_count, spec.find_packet_fields(0x01, &PacketFieldQuery::default()).len());
+    }
+
+    #[test]
+    fn test_push_translations() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let mut spec = Specification::from_file(spec_file, Language::Nl);
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
+        assert_eq!("VBus 1: DeltaSol MX [WMZ #1]", packet_spec.name);
+        assert_eq!("Heat quantity", packet_spec.fields [0].name);
+
+        let device_spec = spec.get_device_spec(0x00, 0x7F31, 0x0010);
+        assert_eq!("Onbekend apparaat 0x7F31", device_spec.name);
+
+        let mut ignored_table = TranslationTable::new(Language::It);
+        ignored_table.insert("7E31", "DeltaSol MX [WMZ #1] (it)");
+
+        let table = TranslationTable::parse(Language::Nl, "
+            7E31 = DeltaSol MX [WMZ #1] (nl)
+            0010_7E31_10_0100_000_4_0 = Warmtehoeveelheid
+        ").unwrap();
+
+        spec.push_translations(ignored_table);
+        spec.push_translations(table);
+
+        assert_eq!(2, spec.translations().len());
+        assert_eq!(0, spec.devices.read().unwrap().len());
+        assert_eq!(0, spec.packets.read().unwrap().len());
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
+        assert_eq!("VBus 1: DeltaSol MX [WMZ #1] (nl)", packet_spec.name);
+        assert_eq!("01_0010_7E31_10_0100_000_4_0", packet_spec.fields [0].packet_field_id);
+        assert_eq!("Warmtehoeveelheid", packet_spec.fields [0].name);
+        assert_eq!("Heat quantity today", packet_spec.fields [1].name);
+
+        let mut override_table = TranslationTable::new(Language::Nl);
+        override_table.insert("7E31", "WMZ");
+
+        spec.push_translations(override_table);
+
+        let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E31, 0x0100);
+        assert_eq!("WMZ", packet_spec.name);
+        assert_eq!("Warmtehoeveelheid", packet_spec.fields [0].name);
+    }
+
+    #[test]
+    fn test_push_extension() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let mut spec = Specification::from_file(spec_file, Language::De);
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
+        let field_spec = packet_spec.get_field_spec("000_4_0").unwrap();
+        assert_eq!(Type::Number, field_spec.typ);
+        assert_eq!(None, field_spec.enum_type);
+
+        let ext = SpecificationExtension::parse("
+            [enum State]
+            0 = Off
+            0.de = Aus
+            1 = On
+
+            [flags Errors]
+            1 = Sensor 2 defective
+            1.de = Sensor 2 defekt
+
+            [fields]
+            0010_7E31_10_0100_000_4_0 = State
+            0010_7E31_10_0100_008_4_0 = Errors
+        ").unwrap();
+
+        spec.push_extension(ext);
+
+        assert_eq!(1, spec.extensions().len());
+        assert_eq!(0, spec.devices.read().unwrap().len());
+        assert_eq!(0, spec.packets.read().unwrap().len());
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
+
+        let field_spec = packet_spec.get_field_spec("000_4_0").unwrap();
+        assert_eq!(Type::Number, field_spec.typ);
+        assert_eq!("State", field_spec.enum_type.as_ref().unwrap().name);
+        assert_eq!(EnumKind::Enum, field_spec.enum_type.as_ref().unwrap().kind);
+        assert_eq!("Aus", format!("{}", field_spec.fmt_raw_value(Some(0), true)));
+        assert_eq!("On", format!("{}", field_spec.fmt_raw_value(Some(1), true)));
+        assert_eq!("2", format!("{}", field_spec.fmt_raw_value(Some(2), true)));
+        assert_eq!("", format!("{}", field_spec.fmt_raw_value(None, true)));
+
+        let field_spec = packet_spec.get_field_spec("008_4_0").unwrap();
+        assert_eq!(Type::Number, field_spec.typ);
+        assert_eq!(EnumKind::BitFlags, field_spec.enum_type.as_ref().unwrap().kind);
+        assert_eq!("Sensor 2 defekt, Bit 2", format!("{}", field_spec.fmt_raw_value(Some(6), true)));
+        assert_eq!("Sensor 2 defekt", format!("{}", field_spec.fmt_raw_value_in_unit_system(Some(2), true, UnitSystem::Si)));
+
+        let field_spec = &packet_spec.fields [2];
+        assert_eq!(Type::Number, field_spec.typ);
+        assert_eq!(None, field_spec.enum_type);
+
+        let mut override_ext = SpecificationExtension::new();
+        override_ext.add_enum_type(EnumType::new("Override", EnumKind::BitFlags));
+        assert!(override_ext.assign_field("0010_7E31_10_0100_000_4_0", "Override"));
+
+        spec.push_extension(override_ext);
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
+        assert_eq!("Override", packet_spec.get_field_spec("000_4_0").unwrap().enum_type.as_ref().unwrap().name);
+        assert_eq!("Errors", packet_spec.get_field_spec("008_4_0").unwrap().enum_type.as_ref().unwrap().name);
+    }
+
+    #[test]
+    fn test_fmt_raw_value_bit_flags() {
+        let mut enum_type = EnumType::new("Errors", EnumKind::BitFlags);
+        enum_type.insert_label(7, Language::En, "Sensor 8 defective");
+
+        let mut field_spec = field_spec_from_parts(vec![ part(0, 0, 0xFF, true, 1) ]);
+        field_spec.enum_type = Some(Arc::new(enum_type));
+
+        // sign-extended raw values only list the bits covered by the field
+        let raw_value = field_spec.raw_value_i64(&[ 0x81 ]);
+        assert_eq!(Some(-127), raw_value);
+        assert_eq!("Bit 0, Sensor 8 defective", format!("{}", field_spec.fmt_raw_value(raw_value, true)));
+        assert_eq!("Bit 0, Bit 1, Bit 2, Bit 3, Bit 4, Bit 5, Bit 6, Sensor 8 defective", format!("{}", field_spec.fmt_raw_value(Some(-1), true)));
+    }
+
     #[test]
     fn test_get_field_spec() {
         let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
 
         let spec = Specification::from_file(spec_file, Language::En);
 
-        assert_eq!(0, spec.packets.borrow().len());
+        assert_eq!(0, spec.packets.read().unwrap().len());
 
         let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
 
@@ -1149,7 +2821,7 @@ mod tests {
 
         let spec = Specification::from_file(spec_file, Language::En);
 
-        assert_eq!(0, spec.packets.borrow().len());
+        assert_eq!(0, spec.packets.read().unwrap().len());
 
         let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7F61, 0x0100);
 
@@ -1174,7 +2846,7 @@ mod tests {
 
         let spec = Specification::from_file(spec_file, Language::En);
 
-        assert_eq!(0, spec.packets.borrow().len());
+        assert_eq!(0, spec.packets.read().unwrap().len());
 
         let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7F61, 0x0100);
 
@@ -1193,6 +2865,167 @@ mod tests {
         assert_eq!(None, packet_spec.get_field_spec("000_4_0").unwrap().raw_value_f64(&buf [0..0]));
     }
 
+    fn field_spec_from_parts(parts: Vec<PacketTemplateFieldPart>) -> PacketFieldSpec {
+        PacketFieldSpec {
+            field_id: "000_1_0".to_owned(),
+            packet_field_id: "00_0010_7E11_10_0100_000_1_0".to_owned(),
+            name: "Field".to_owned(),
+            unit_id: UnitId(-1),
+            unit_family: UnitFamily::None,
+            unit_code: "None".to_owned(),
+            unit_text: "".to_owned(),
+            precision: 0,
+            typ: Type::Number,
+            parts,
+            language: Language::En,
+            enum_type: None,
+        }
+    }
+
+    fn part(offset: i32, bit_pos: u8, mask: u8, is_signed: bool, factor: i64) -> PacketTemplateFieldPart {
+        PacketTemplateFieldPart {
+            offset,
+            bit_pos,
+            mask,
+            is_signed,
+            factor,
+        }
+    }
+
+    #[test]
+    fn test_set_raw_value_i64() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_file(spec_file, Language::En);
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7F61, 0x0100);
+
+        let mut buf = [0u8; 12];
+
+        packet_spec.get_field_spec("000_4_0").unwrap().set_raw_value_i64(&mut buf, 0x12345678).unwrap();
+        packet_spec.get_field_spec("004_4_0").unwrap().set_raw_value_i64(&mut buf, 8888).unwrap();
+        packet_spec.get_field_spec("008_4_0").unwrap().set_raw_value_i64(&mut buf, -8888).unwrap();
+
+        assert_eq!(&[
+            0x78, 0x56, 0x34, 0x12,
+            0xB8, 0x22, 0x00, 0x00,
+            0x48, 0xDD, 0xFF, 0xFF,
+        ], &buf);
+
+        let field_spec = packet_spec.get_field_spec("004_4_0").unwrap();
+        assert_eq!((-0x80000000, 0x7FFFFFFF), field_spec.raw_value_range());
+        assert_eq!(Err(EncodeError::OutOfRange), field_spec.set_raw_value_i64(&mut buf, 0x80000000));
+        assert_eq!(Err(EncodeError::OutOfRange), field_spec.set_raw_value_i64(&mut buf, -0x80000001));
+        assert_eq!(Err(EncodeError::BufferTooSmall), field_spec.set_raw_value_i64(&mut buf [0..7], 0));
+
+        // bit-packed fields sharing a byte
+        let bit0 = field_spec_from_parts(vec![ part(0, 0, 0x01, true, 1) ]);
+        let bit2 = field_spec_from_parts(vec![ part(0, 2, 0x04, true, 1) ]);
+        let nibble = field_spec_from_parts(vec![ part(0, 4, 0xF0, false, 1) ]);
+
+        let mut buf = [0u8; 1];
+        bit2.set_raw_value_i64(&mut buf, 1).unwrap();
+        nibble.set_raw_value_i64(&mut buf, 0xA).unwrap();
+        bit0.set_raw_value_i64(&mut buf, 1).unwrap();
+        assert_eq!([0xA5], buf);
+        bit2.set_raw_value_i64(&mut buf, 0).unwrap();
+        assert_eq!([0xA1], buf);
+
+        assert_eq!((0, 1), bit0.raw_value_range());
+        assert_eq!((0, 15), nibble.raw_value_range());
+        assert_eq!(Err(EncodeError::OutOfRange), nibble.set_raw_value_i64(&mut buf, 16));
+
+        // two 16-bit groups combined with a factor of 1000
+        let field_spec = field_spec_from_parts(vec![
+            part(0, 0, 0xFF, false, 1),
+            part(1, 0, 0xFF, false, 256),
+            part(2, 0, 0xFF, false, 1000),
+            part(3, 0, 0xFF, false, 256000),
+        ]);
+
+        let mut buf = [0u8; 4];
+        field_spec.set_raw_value_i64(&mut buf, 123456).unwrap();
+        assert_eq!(Some(123456), field_spec.raw_value_i64(&buf));
+        assert_eq!((0, 65535 + 65535000), field_spec.raw_value_range());
+
+        // signed upper group combined with unsigned lower groups
+        let field_spec = field_spec_from_parts(vec![
+            part(0, 0, 0xFF, false, 1),
+            part(1, 0, 0xFF, false, 256),
+            part(2, 0, 0xFF, false, 1000),
+            part(3, 0, 0xFF, true, 256000),
+        ]);
+
+        assert_eq!((-32768000, 65535 + 32767000), field_spec.raw_value_range());
+        for &raw_value in [ -32768000, -1001, -1000, -500, -1, 0, 1, 999, 1000, 123456, 65535 + 32767000 ].iter() {
+            let mut buf = [0u8; 4];
+            field_spec.set_raw_value_i64(&mut buf, raw_value).unwrap();
+            assert_eq!(Some(raw_value), field_spec.raw_value_i64(&buf));
+        }
+
+        // parts that cannot represent odd values
+        let field_spec = field_spec_from_parts(vec![ part(0, 0, 0xFF, false, 2) ]);
+        assert_eq!(Err(EncodeError::NotRepresentable), field_spec.set_raw_value_i64(&mut buf, 3));
+
+        // parts with a factor of zero
+        let field_spec = field_spec_from_parts(vec![
+            part(0, 0, 0xFF, true, 0),
+            part(1, 0, 0xFF, true, 1),
+            part(2, 0, 0xFF, false, 0),
+        ]);
+
+        let mut buf = [0x55u8; 3];
+        assert_eq!((-128, 127), field_spec.raw_value_range());
+        field_spec.set_raw_value_i64(&mut buf, -100).unwrap();
+        assert_eq!([0x55, 0x9C, 0x55], buf);
+        assert_eq!(Some(-100), field_spec.raw_value_i64(&buf));
+
+        let field_spec = field_spec_from_parts(vec![ part(0, 0, 0xFF, false, 0) ]);
+        assert_eq!((0, 0), field_spec.raw_value_range());
+        assert_eq!(Ok(()), field_spec.set_raw_value_i64(&mut buf, 0));
+        assert_eq!(Err(EncodeError::OutOfRange), field_spec.set_raw_value_i64(&mut buf, 1));
+        assert_eq!([0x55, 0x9C, 0x55], buf);
+    }
+
+    #[test]
+    fn test_set_raw_value_i64_round_trip() {
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        for packet_template in &spec.files() [0].packet_templates {
+            let packet_spec = spec.get_packet_spec(0x00, packet_template.destination_address, packet_template.source_address, packet_template.command);
+            for field_spec in &packet_spec.fields {
+                let (min, max) = field_spec.raw_value_range();
+                let step = field_spec.parts.iter().map(|part| part.factor).min().unwrap();
+                let mid = (min / 2 + max / 2) / step * step;
+                for &raw_value in [ min, min + step, mid - step, mid, mid + step, max - step, max ].iter().filter(|&&value| value >= min && value <= max) {
+                    let mut buf = [0u8; 508];
+                    field_spec.set_raw_value_i64(&mut buf, raw_value).unwrap();
+                    assert_eq!(Some(raw_value), field_spec.raw_value_i64(&buf), "{}", field_spec.packet_field_id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_raw_value_f64() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_file(spec_file, Language::En);
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7F61, 0x0100);
+
+        let field_spec = packet_spec.get_field_spec("004_4_0").unwrap();
+
+        let mut buf = [0u8; 12];
+        field_spec.set_raw_value_f64(&mut buf, 888.8).unwrap();
+        assert_eq!(Some(8888), field_spec.raw_value_i64(&buf));
+        field_spec.set_raw_value_f64(&mut buf, -888.84).unwrap();
+        assert_eq!(Some(-8888), field_spec.raw_value_i64(&buf));
+
+        assert_eq!(Err(EncodeError::OutOfRange), field_spec.set_raw_value_f64(&mut buf, 1e12));
+        assert_eq!(Err(EncodeError::NotRepresentable), field_spec.set_raw_value_f64(&mut buf, f64::NAN));
+    }
+
     #[test]
     fn test_fmt_raw_value() {
         let fake_field_spec = |precision, typ, unit_text: &str| {
@@ -1208,6 +3041,7 @@ mod tests {
                 typ: typ,
                 parts: Vec::new(),
                 language: Language::En,
+                enum_type: None,
             }
         };
 
@@ -1250,6 +3084,88 @@ mod tests {
         assert_eq!("22/12/2013 15:17:42", fmt_raw_value(&field_spec, 409418262, true));
     }
 
+    #[test]
+    fn test_fmt_raw_value_in_unit_system() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_file(spec_file, Language::En);
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7E31, 0x0100);
+
+        let field_spec = packet_spec.get_field_spec("000_4_0").unwrap();
+        assert_eq!("WattHours", field_spec.unit_code);
+
+        let fmt = |raw_value, unit_system| {
+            format!("{}", field_spec.fmt_raw_value_in_unit_system(raw_value, true, unit_system))
+        };
+
+        assert_eq!("1000 Wh", fmt(Some(1000), UnitSystem::Metric));
+        assert_eq!("3412 BTU", fmt(Some(1000), UnitSystem::UsCustomary));
+        assert_eq!("3600000 J", fmt(Some(1000), UnitSystem::Si));
+        assert_eq!("", fmt(None, UnitSystem::Si));
+
+        let packet_spec = spec.get_packet_spec(0x01, 0x0010, 0x7F61, 0x0100);
+
+        let field_spec = packet_spec.get_field_spec("004_4_0").unwrap();
+        assert_eq!(1, field_spec.precision);
+
+        let buf = &[ 0x00, 0x00, 0x00, 0x00, 0xB8, 0x22, 0x00, 0x00 ];
+        assert_eq!(Some(888.8000000000001), field_spec.raw_value_f64(buf));
+        assert_eq!(Some(888.8000000000001), field_spec.raw_value_f64_in_unit(buf, field_spec.unit_code.as_str()));
+        assert_eq!(None, field_spec.raw_value_f64_in_unit(buf, "Furlongs"));
+    }
+
+    #[test]
+    fn test_field_value() {
+        use chrono::{Duration, NaiveTime, Weekday};
+
+        let mut rr = RecordingReader::new(RECORDING_2);
+
+        let data_set = rr.read_data_set().unwrap().unwrap();
+
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_file(spec_file, Language::En);
+
+        let fields = spec.fields_in_data_set(&data_set).collect::<Vec<_>>();
+
+        let expected_value = FieldValue::Number {
+            value: 0.0,
+            precision: 0,
+            unit_family: UnitFamily::Energy,
+            unit_code: "WattHours",
+            unit_text: " Wh",
+        };
+        assert_eq!(Some(expected_value), fields [0].field_value());
+
+        let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+
+        let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x1001, 0x0100);
+        let field_spec = packet_spec.get_field_spec("000_4_0").unwrap();
+        assert_eq!(Type::DateTime, field_spec.typ);
+        assert_eq!(FieldValue::DateTime(UTC.timestamp(1387725462, 0)), field_spec.field_value(409418262));
+
+        let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x1040, 0x0100);
+        let field_spec = packet_spec.get_field_spec("004_2_0").unwrap();
+        assert_eq!(Type::Time, field_spec.typ);
+        assert_eq!(FieldValue::Time(Duration::minutes(721)), field_spec.field_value(721));
+
+        let mut field_spec = field_spec_from_parts(Vec::new());
+        field_spec.typ = Type::WeekTime;
+        assert_eq!(FieldValue::WeekTime(Weekday::Sun, NaiveTime::from_hms(23, 59, 0)), field_spec.field_value(7 * 1440 - 1));
+
+        let mut field_spec = field_spec_from_parts(vec![ part(0, 0, 0xFF, true, 1) ]);
+
+        let enum_type = Arc::new(EnumType::new("State", EnumKind::Enum));
+        field_spec.enum_type = Some(enum_type.clone());
+        assert_eq!(FieldValue::Enum(-1, &enum_type), field_spec.field_value(-1));
+
+        let enum_type = Arc::new(EnumType::new("Errors", EnumKind::BitFlags));
+        field_spec.enum_type = Some(enum_type.clone());
+        assert_eq!(FieldValue::BitFlags(3, &enum_type), field_spec.field_value(3));
+        assert_eq!(FieldValue::BitFlags(0x80, &enum_type), field_spec.field_value(-128));
+    }
+
     #[test]
     fn test_fields_in_data_set() {
         let mut rr = RecordingReader::new(RECORDING_2);
@@ -1334,4 +3250,63 @@ mod tests {
         assert_eq!("0", format!("{}", field.fmt_raw_value(false)));
         assert_eq!("0 l", format!("{}", field.fmt_raw_value(true)));
     }
+
+    #[test]
+    fn test_to_snapshot() {
+        let mut rr = RecordingReader::new(RECORDING_2);
+
+        let data_set = rr.read_data_set().unwrap().unwrap();
+
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_file(spec_file, Language::En);
+
+        let field = spec.fields_in_data_set(&data_set).nth(4).unwrap();
+
+        let snapshot = field.to_snapshot();
+        assert_eq!(data_set.as_data_slice() [1].as_header().timestamp, snapshot.timestamp);
+        assert_eq!(PacketId(0x00, 0x0010, 0x7E31, 0x0100), snapshot.packet_id);
+        assert_eq!("016_4_0", snapshot.field_id);
+        assert_eq!("00_0010_7E31_10_0100_016_4_0", snapshot.packet_field_id);
+        assert_eq!(field.packet_spec().name, snapshot.packet_name);
+        assert_eq!(field.field_spec().name, snapshot.field_name);
+        assert_eq!("Liters", snapshot.unit_code);
+        assert_eq!(" l", snapshot.unit_text);
+        assert_eq!(UnitFamily::Volume, snapshot.unit_family);
+        assert_eq!(0, snapshot.precision);
+        assert_eq!(Type::Number, snapshot.typ);
+        assert_eq!(Some(0), snapshot.raw_value);
+        assert_eq!(Some(0f64), snapshot.value);
+        assert_eq!("0", snapshot.text);
+        assert!(!snapshot.stale);
+    }
+
+    #[test]
+    fn test_fields_in_data_set_with_staleness() {
+        let mut rr = RecordingReader::new(RECORDING_2);
+
+        let data_set = rr.read_data_set().unwrap().unwrap();
+
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_file(spec_file, Language::En);
+
+        let timestamp = data_set.as_data_slice() [1].as_header().timestamp;
+
+        let mut policy = StalenessPolicy::new();
+        assert!(spec.fields_in_data_set(&data_set).all(|field| !field.is_stale()));
+        assert!(spec.fields_in_data_set(&data_set).with_staleness(&policy, timestamp + Duration::days(1)).all(|field| !field.is_stale()));
+
+        policy.set_packet_ttl(PacketId(0x00, 0x0010, 0x7E31, 0x0100), Duration::seconds(10));
+
+        let fields = spec.fields_in_data_set(&data_set).with_staleness(&policy, timestamp + Duration::seconds(10)).collect::<Vec<_>>();
+        assert_eq!(8, fields.len());
+        assert!(fields.iter().all(|field| !field.is_stale()));
+
+        let fields = spec.fields_in_data_set(&data_set).with_staleness(&policy, timestamp + Duration::seconds(11)).collect::<Vec<_>>();
+        assert_eq!(8, fields.len());
+        assert!(fields.iter().all(|field| field.is_stale()));
+        assert_eq!(Some(0f64), fields [0].raw_value_f64());
+        assert!(fields [0].to_snapshot().stale);
+    }
 }
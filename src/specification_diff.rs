@@ -0,0 +1,472 @@
+//! A module that compares two `SpecificationFile`s.
+//!
+//! Devices, packets and fields are matched by the same IDs that are used for `DeviceSpec`,
+//! `PacketSpec` and `PacketFieldSpec` values (without the channel prefix), so that the reported
+//! `Change`s can be related to the IDs stored in recordings or configuration files.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::SpecificationFile;
+//! use resol_vbus::specification_diff::{diff, Change};
+//!
+//! let old_spec_file = SpecificationFile::new_default();
+//!
+//! let mut new_spec_file = SpecificationFile::new_default();
+//! new_spec_file.packet_templates.retain(|packet_template| packet_template.source_address != 0x7E11);
+//!
+//! let changes = diff(&old_spec_file, &new_spec_file);
+//! assert!(changes.contains(&Change::PacketRemoved { packet_id: "0010_7E11_10_0100".to_owned() }));
+//! assert_eq!("- packet 0010_7E11_10_0100", format!("{}", changes [0]));
+//! ```
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fmt;
+
+use specification_file::{
+    DeviceTemplate,
+    Language,
+    LocalizedTextIndex,
+    PacketTemplate,
+    PacketTemplateField,
+    SpecificationFile,
+};
+
+
+/// A property of a device, packet or field that has changed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Property {
+    /// The name in the given `Language`.
+    Name(Language),
+
+    /// The address masks of a device or packet template.
+    Masks,
+
+    /// The offsets, bit masks, bit positions and signedness of the parts of a field.
+    Parts,
+
+    /// The factors of the parts of a field.
+    Factors,
+
+    /// The precision of a field.
+    Precision,
+
+    /// The unit code of a field.
+    Unit,
+
+    /// The `Type` of a field.
+    Type,
+}
+
+
+/// A difference between two `SpecificationFile`s.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// A device template was added.
+    DeviceAdded {
+        /// The device ID (e.g. "7E11" or "7E11_0010").
+        device_id: String,
+
+        /// The English name of the device.
+        name: String,
+    },
+
+    /// A device template was removed.
+    DeviceRemoved {
+        /// The device ID.
+        device_id: String,
+
+        /// The English name of the device.
+        name: String,
+    },
+
+    /// A property of a device template changed.
+    DeviceChanged {
+        /// The device ID.
+        device_id: String,
+
+        /// The changed `Property`.
+        property: Property,
+
+        /// The old value of the property.
+        old_value: String,
+
+        /// The new value of the property.
+        new_value: String,
+    },
+
+    /// A packet template was added.
+    PacketAdded {
+        /// The packet ID (e.g. "0010_7E11_10_0100").
+        packet_id: String,
+    },
+
+    /// A packet template was removed.
+    PacketRemoved {
+        /// The packet ID.
+        packet_id: String,
+    },
+
+    /// A property of a packet template changed.
+    PacketChanged {
+        /// The packet ID.
+        packet_id: String,
+
+        /// The changed `Property`.
+        property: Property,
+
+        /// The old value of the property.
+        old_value: String,
+
+        /// The new value of the property.
+        new_value: String,
+    },
+
+    /// A field was added to a packet template.
+    FieldAdded {
+        /// The packet ID.
+        packet_id: String,
+
+        /// The field ID (e.g. "000_2_0").
+        field_id: String,
+
+        /// The English name of the field.
+        name: String,
+    },
+
+    /// A field was removed from a packet template.
+    FieldRemoved {
+        /// The packet ID.
+        packet_id: String,
+
+        /// The field ID.
+        field_id: String,
+
+        /// The English name of the field.
+        name: String,
+    },
+
+    /// A property of a field changed.
+    FieldChanged {
+        /// The packet ID.
+        packet_id: String,
+
+        /// The field ID.
+        field_id: String,
+
+        /// The changed `Property`.
+        property: Property,
+
+        /// The old value of the property.
+        old_value: String,
+
+        /// The new value of the property.
+        new_value: String,
+    },
+}
+
+
+impl fmt::Display for Change {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Change::DeviceAdded { ref device_id, ref name } => {
+                write!(f, "+ device {}: {}", device_id, name)
+            }
+            Change::DeviceRemoved { ref device_id, ref name } => {
+                write!(f, "- device {}: {}", device_id, name)
+            }
+            Change::DeviceChanged { ref device_id, property, ref old_value, ref new_value } => {
+                write!(f, "~ device {}: {:?} {:?} -> {:?}", device_id, property, old_value, new_value)
+            }
+            Change::PacketAdded { ref packet_id } => {
+                write!(f, "+ packet {}", packet_id)
+            }
+            Change::PacketRemoved { ref packet_id } => {
+                write!(f, "- packet {}", packet_id)
+            }
+            Change::PacketChanged { ref packet_id, property, ref old_value, ref new_value } => {
+                write!(f, "~ packet {}: {:?} {:?} -> {:?}", packet_id, property, old_value, new_value)
+            }
+            Change::FieldAdded { ref packet_id, ref field_id, ref name } => {
+                write!(f, "+ field {}_{}: {}", packet_id, field_id, name)
+            }
+            Change::FieldRemoved { ref packet_id, ref field_id, ref name } => {
+                write!(f, "- field {}_{}: {}", packet_id, field_id, name)
+            }
+            Change::FieldChanged { ref packet_id, ref field_id, property, ref old_value, ref new_value } => {
+                write!(f, "~ field {}_{}: {:?} {:?} -> {:?}", packet_id, field_id, property, old_value, new_value)
+            }
+        }
+    }
+
+}
+
+
+const LANGUAGES: [Language; 3] = [
+    Language::En,
+    Language::De,
+    Language::Fr,
+];
+
+
+fn device_id(device_template: &DeviceTemplate) -> String {
+    if device_template.peer_mask == 0 {
+        format!("{:04X}", device_template.self_address)
+    } else {
+        format!("{:04X}_{:04X}", device_template.self_address, device_template.peer_address)
+    }
+}
+
+
+/// Index the items by their ID, keeping the order of the first occurrence of each ID.
+fn index_by_id<T, F: Fn(&T) -> String>(items: &[T], f: F) -> (Vec<String>, HashMap<String, &T>) {
+    let mut ids = Vec::new();
+    let mut map = HashMap::new();
+    for item in items {
+        if let Entry::Vacant(entry) = map.entry(f(item)) {
+            ids.push(entry.key().clone());
+            entry.insert(item);
+        }
+    }
+    (ids, map)
+}
+
+
+/// Calls `f` with the old and new value of each name language that differs.
+fn diff_names<F: FnMut(Property, String, String)>(old_file: &SpecificationFile, old_index: &LocalizedTextIndex, new_file: &SpecificationFile, new_index: &LocalizedTextIndex, mut f: F) {
+    for &language in &LANGUAGES {
+        let old_name = old_file.localized_text_by_index(old_index, language);
+        let new_name = new_file.localized_text_by_index(new_index, language);
+        if old_name != new_name {
+            f(Property::Name(language), old_name.to_owned(), new_name.to_owned());
+        }
+    }
+}
+
+
+fn diff_device_templates(old_file: &SpecificationFile, new_file: &SpecificationFile, changes: &mut Vec<Change>) {
+    let (old_ids, old_map) = index_by_id(&old_file.device_templates, device_id);
+    let (new_ids, new_map) = index_by_id(&new_file.device_templates, device_id);
+
+    for device_id in &old_ids {
+        let old_device = old_map [device_id];
+        match new_map.get(device_id) {
+            None => changes.push(Change::DeviceRemoved {
+                device_id: device_id.clone(),
+                name: old_file.localized_text_by_index(&old_device.name_localized_text_index, Language::En).to_owned(),
+            }),
+            Some(new_device) => {
+                let mut push_change = |property, old_value, new_value| changes.push(Change::DeviceChanged {
+                    device_id: device_id.clone(),
+                    property,
+                    old_value,
+                    new_value,
+                });
+
+                diff_names(old_file, &old_device.name_localized_text_index, new_file, &new_device.name_localized_text_index, &mut push_change);
+
+                let old_masks = format!("0x{:04X}, 0x{:04X}", old_device.self_mask, old_device.peer_mask);
+                let new_masks = format!("0x{:04X}, 0x{:04X}", new_device.self_mask, new_device.peer_mask);
+                if old_masks != new_masks {
+                    push_change(Property::Masks, old_masks, new_masks);
+                }
+            }
+        }
+    }
+
+    for device_id in &new_ids {
+        if !old_map.contains_key(device_id) {
+            changes.push(Change::DeviceAdded {
+                device_id: device_id.clone(),
+                name: new_file.localized_text_by_index(&new_map [device_id].name_localized_text_index, Language::En).to_owned(),
+            });
+        }
+    }
+}
+
+
+fn fmt_parts(field: &PacketTemplateField) -> String {
+    field.parts.iter().map(|part| {
+        let mut s = format!("{}/0x{:02X}", part.offset, part.mask);
+        if part.bit_pos > 0 {
+            s.push_str(&format!(">>{}", part.bit_pos));
+        }
+        if part.is_signed {
+            s.push_str(" (signed)");
+        }
+        s
+    }).collect::<Vec<_>>().join(", ")
+}
+
+
+fn fmt_factors(field: &PacketTemplateField) -> String {
+    field.parts.iter().map(|part| format!("{}", part.factor)).collect::<Vec<_>>().join(", ")
+}
+
+
+fn diff_fields(packet_id: &str, old_file: &SpecificationFile, old_packet: &PacketTemplate, new_file: &SpecificationFile, new_packet: &PacketTemplate, changes: &mut Vec<Change>) {
+    let (old_ids, old_map) = index_by_id(&old_packet.fields, |field| old_file.text_by_index(&field.id_text_index).to_owned());
+    let (new_ids, new_map) = index_by_id(&new_packet.fields, |field| new_file.text_by_index(&field.id_text_index).to_owned());
+
+    for field_id in &old_ids {
+        let old_field = old_map [field_id];
+        match new_map.get(field_id) {
+            None => changes.push(Change::FieldRemoved {
+                packet_id: packet_id.to_owned(),
+                field_id: field_id.clone(),
+                name: old_file.localized_text_by_index(&old_field.name_localized_text_index, Language::En).to_owned(),
+            }),
+            Some(new_field) => {
+                let mut push_change = |property, old_value: String, new_value: String| if old_value != new_value {
+                    changes.push(Change::FieldChanged {
+                        packet_id: packet_id.to_owned(),
+                        field_id: field_id.clone(),
+                        property,
+                        old_value,
+                        new_value,
+                    });
+                };
+
+                diff_names(old_file, &old_field.name_localized_text_index, new_file, &new_field.name_localized_text_index, &mut push_change);
+
+                push_change(Property::Parts, fmt_parts(old_field), fmt_parts(new_field));
+                push_change(Property::Factors, fmt_factors(old_field), fmt_factors(new_field));
+                push_change(Property::Precision, format!("{}", old_field.precision), format!("{}", new_field.precision));
+
+                let old_unit = old_file.units.iter().find(|unit| unit.unit_id == old_field.unit_id);
+                let new_unit = new_file.units.iter().find(|unit| unit.unit_id == new_field.unit_id);
+                let old_unit_code = old_unit.map_or("", |unit| old_file.text_by_index(&unit.unit_code_text_index));
+                let new_unit_code = new_unit.map_or("", |unit| new_file.text_by_index(&unit.unit_code_text_index));
+                push_change(Property::Unit, old_unit_code.to_owned(), new_unit_code.to_owned());
+
+                push_change(Property::Type, format!("{:?}", old_file.type_by_id(&old_field.type_id)), format!("{:?}", new_file.type_by_id(&new_field.type_id)));
+            }
+        }
+    }
+
+    for field_id in &new_ids {
+        if !old_map.contains_key(field_id) {
+            changes.push(Change::FieldAdded {
+                packet_id: packet_id.to_owned(),
+                field_id: field_id.clone(),
+                name: new_file.localized_text_by_index(&new_map [field_id].name_localized_text_index, Language::En).to_owned(),
+            });
+        }
+    }
+}
+
+
+fn diff_packet_templates(old_file: &SpecificationFile, new_file: &SpecificationFile, changes: &mut Vec<Change>) {
+    let (old_ids, old_map) = index_by_id(&old_file.packet_templates, PacketTemplate::id_string);
+    let (new_ids, new_map) = index_by_id(&new_file.packet_templates, PacketTemplate::id_string);
+
+    for packet_id in &old_ids {
+        let old_packet = old_map [packet_id];
+        match new_map.get(packet_id) {
+            None => changes.push(Change::PacketRemoved {
+                packet_id: packet_id.clone(),
+            }),
+            Some(new_packet) => {
+                let old_masks = format!("0x{:04X}, 0x{:04X}", old_packet.destination_mask, old_packet.source_mask);
+                let new_masks = format!("0x{:04X}, 0x{:04X}", new_packet.destination_mask, new_packet.source_mask);
+                if old_masks != new_masks {
+                    changes.push(Change::PacketChanged {
+                        packet_id: packet_id.clone(),
+                        property: Property::Masks,
+                        old_value: old_masks,
+                        new_value: new_masks,
+                    });
+                }
+
+                diff_fields(packet_id, old_file, old_packet, new_file, new_packet, changes);
+            }
+        }
+    }
+
+    for packet_id in &new_ids {
+        if !old_map.contains_key(packet_id) {
+            changes.push(Change::PacketAdded {
+                packet_id: packet_id.clone(),
+            });
+        }
+    }
+}
+
+
+/// Compare two `SpecificationFile`s and return the list of `Change`s from the old to the new one.
+///
+/// Packet changes are reported before device changes. Within each table, removed and changed
+/// entries are reported in the order of the old file, followed by added entries in the order of
+/// the new file.
+pub fn diff(old_file: &SpecificationFile, new_file: &SpecificationFile) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_packet_templates(old_file, new_file, &mut changes);
+    diff_device_templates(old_file, new_file, &mut changes);
+    changes
+}
+
+
+#[cfg(test)]
+mod tests {
+    use specification_file::TextIndex;
+
+    use test_data::SPEC_FILE_1;
+
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old_spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+        assert_eq!(Vec::<Change>::new(), diff(&old_spec_file, &old_spec_file));
+
+        let mut new_spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        new_spec_file.texts.push("Heat quantity total".to_owned());
+        let text_index = TextIndex(new_spec_file.texts.len() as i32 - 1);
+
+        let other_unit_id = new_spec_file.units.iter().find(|unit| unit.unit_id != new_spec_file.packet_templates [0].fields [0].unit_id).unwrap().unit_id;
+
+        {
+            let packet_template = &mut new_spec_file.packet_templates [0];
+            packet_template.fields.remove(1);
+            packet_template.source_mask = 0xFFFF;
+
+            let field = &mut packet_template.fields [0];
+            field.parts [3].is_signed = false;
+            field.parts [3].factor = 1;
+            field.precision = 1;
+            field.unit_id = other_unit_id;
+        }
+
+        let localized_text_index = new_spec_file.packet_templates [0].fields [0].name_localized_text_index.0 as usize;
+        new_spec_file.localized_texts [localized_text_index].text_index_en = text_index;
+
+        new_spec_file.packet_templates [1].command = 0x0200;
+
+        new_spec_file.device_templates.pop();
+
+        let changes = diff(&old_spec_file, &new_spec_file);
+        let messages = changes.iter().map(|change| format!("{}", change)).collect::<Vec<_>>();
+
+        assert_eq!(Change::PacketChanged {
+            packet_id: "0010_7E30_10_0100".to_owned(),
+            property: Property::Masks,
+            old_value: "0xFFFF, 0xFFF0".to_owned(),
+            new_value: "0xFFFF, 0xFFFF".to_owned(),
+        }, changes [0]);
+        assert_eq!("~ field 0010_7E30_10_0100_000_4_0: Name(En) \"Heat quantity\" -> \"Heat quantity total\"", messages [1]);
+        assert_eq!("~ field 0010_7E30_10_0100_000_4_0: Parts \"0/0xFF, 1/0xFF, 2/0xFF, 3/0xFF (signed), 36/0xFF, 37/0xFF, 38/0xFF, 39/0xFF (signed)\" -> \"0/0xFF, 1/0xFF, 2/0xFF, 3/0xFF, 36/0xFF, 37/0xFF, 38/0xFF, 39/0xFF (signed)\"", messages [2]);
+        assert_eq!("~ field 0010_7E30_10_0100_000_4_0: Factors \"1, 256, 65536, 16777216, 1000000000, 256000000000, 65536000000000, 16777216000000000\" -> \"1, 256, 65536, 1, 1000000000, 256000000000, 65536000000000, 16777216000000000\"", messages [3]);
+        assert_eq!("~ field 0010_7E30_10_0100_000_4_0: Precision \"0\" -> \"1\"", messages [4]);
+        match changes [5] {
+            Change::FieldChanged { property: Property::Unit, .. } => {}
+            ref change => panic!("Unexpected change {:?}", change),
+        }
+        assert_eq!("- field 0010_7E30_10_0100_008_4_0: Heat quantity today", messages [6]);
+
+        assert!(messages.iter().any(|message| message.starts_with("- packet ")));
+        assert!(messages.iter().any(|message| message.starts_with("+ packet ") && message.ends_with("_10_0200")));
+        assert!(messages.last().unwrap().starts_with("- device "));
+    }
+}
@@ -0,0 +1,220 @@
+//! A module that renders reference documentation for all packets known to a `Specification`.
+//!
+//! The generated document lists every packet template of the `SpecificationFile` layers with its
+//! packet ID, source and destination device names and a table of its fields, including the
+//! offsets, bit masks and factors of their parts, their units and types. All names are
+//! rendered in the `Language` of the `Specification`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{Specification, SpecificationFile, Language};
+//! use resol_vbus::specification_docs::{DocFormat, docs_to_string};
+//!
+//! let spec = Specification::from_file(SpecificationFile::new_default(), Language::En);
+//!
+//! let markdown = docs_to_string(&spec, DocFormat::Markdown);
+//! assert!(markdown.contains("## `0010_7E11_10_0100`: DeltaSol MX [Regler]"));
+//! assert!(markdown.contains("| `000_2_0` | Temperature sensor 1 | 0, 1 | 0xFF, 0xFF | 1, 256 (signed) | °C | Number |"));
+//! ```
+use std::io::{Result, Write};
+
+use specification::{PacketSpec, PacketFieldSpec, Specification};
+
+
+/// The output formats supported by the documentation generator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocFormat {
+    /// A Markdown document.
+    Markdown,
+
+    /// A standalone HTML document.
+    Html,
+}
+
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+
+fn join_parts<F: Fn(&::specification_file::PacketTemplateFieldPart) -> String>(field_spec: &PacketFieldSpec, f: F) -> String {
+    field_spec.parts.iter().map(f).collect::<Vec<_>>().join(", ")
+}
+
+
+struct FieldColumns {
+    field_id: String,
+    name: String,
+    offsets: String,
+    masks: String,
+    factors: String,
+    unit: String,
+    typ: String,
+}
+
+
+const COLUMN_TITLES: [&str; 7] = [
+    "ID",
+    "Name",
+    "Offsets",
+    "Bit masks",
+    "Factors",
+    "Unit",
+    "Type",
+];
+
+
+impl FieldColumns {
+
+    fn new(field_spec: &PacketFieldSpec) -> FieldColumns {
+        FieldColumns {
+            field_id: field_spec.field_id.clone(),
+            name: field_spec.name.clone(),
+            offsets: join_parts(field_spec, |part| format!("{}", part.offset)),
+            masks: join_parts(field_spec, |part| format!("0x{:02X}", part.mask)),
+            factors: join_parts(field_spec, |part| if part.is_signed {
+                format!("{} (signed)", part.factor)
+            } else {
+                format!("{}", part.factor)
+            }),
+            unit: field_spec.unit_text.trim().to_owned(),
+            typ: format!("{:?}", field_spec.typ),
+        }
+    }
+
+}
+
+
+fn packet_key(packet_spec: &PacketSpec) -> &str {
+    // omit the channel, the documentation applies to all channels
+    &packet_spec.packet_id [3..]
+}
+
+
+fn write_markdown<W: Write>(w: &mut W, spec: &Specification) -> Result<()> {
+    writeln!(w, "# VBus Packet List")?;
+    writeln!(w)?;
+    for file in spec.files() {
+        writeln!(w, "- Specification datecode: {}", file.datecode)?;
+    }
+
+    for packet_spec in spec.packet_specs(0) {
+        writeln!(w)?;
+        writeln!(w)?;
+        writeln!(w, "## `{}`: {}", packet_key(&packet_spec), escape_markdown(&packet_spec.name))?;
+        writeln!(w)?;
+        writeln!(w, "- Source: {} (0x{:04X})", escape_markdown(&packet_spec.source_device.name), packet_spec.source_address)?;
+        writeln!(w, "- Destination: {} (0x{:04X})", escape_markdown(&packet_spec.destination_device.name), packet_spec.destination_address)?;
+        writeln!(w, "- Command: 0x{:04X}", packet_spec.command)?;
+
+        if !packet_spec.fields.is_empty() {
+            writeln!(w)?;
+            writeln!(w, "| {} |", COLUMN_TITLES.join(" | "))?;
+            writeln!(w, "|{}", "---|".repeat(COLUMN_TITLES.len()))?;
+            for field_spec in &packet_spec.fields {
+                let c = FieldColumns::new(field_spec);
+                writeln!(w, "| `{}` | {} | {} | {} | {} | {} | {} |", c.field_id, escape_markdown(&c.name), c.offsets, c.masks, c.factors, escape_markdown(&c.unit), c.typ)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+fn write_html<W: Write>(w: &mut W, spec: &Specification) -> Result<()> {
+    writeln!(w, "<!DOCTYPE html>")?;
+    writeln!(w, "<html>")?;
+    writeln!(w, "<head>")?;
+    writeln!(w, "<meta charset=\"utf-8\">")?;
+    writeln!(w, "<title>VBus Packet List</title>")?;
+    writeln!(w, "</head>")?;
+    writeln!(w, "<body>")?;
+    writeln!(w, "<h1>VBus Packet List</h1>")?;
+    writeln!(w, "<ul>")?;
+    for file in spec.files() {
+        writeln!(w, "<li>Specification datecode: {}</li>", file.datecode)?;
+    }
+    writeln!(w, "</ul>")?;
+
+    for packet_spec in spec.packet_specs(0) {
+        let key = packet_key(&packet_spec);
+        writeln!(w, "<h2 id=\"{}\"><code>{}</code>: {}</h2>", key, key, escape_html(&packet_spec.name))?;
+        writeln!(w, "<ul>")?;
+        writeln!(w, "<li>Source: {} (0x{:04X})</li>", escape_html(&packet_spec.source_device.name), packet_spec.source_address)?;
+        writeln!(w, "<li>Destination: {} (0x{:04X})</li>", escape_html(&packet_spec.destination_device.name), packet_spec.destination_address)?;
+        writeln!(w, "<li>Command: 0x{:04X}</li>", packet_spec.command)?;
+        writeln!(w, "</ul>")?;
+
+        if !packet_spec.fields.is_empty() {
+            writeln!(w, "<table>")?;
+            writeln!(w, "<tr><th>{}</th></tr>", COLUMN_TITLES.join("</th><th>"))?;
+            for field_spec in &packet_spec.fields {
+                let c = FieldColumns::new(field_spec);
+                writeln!(w, "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>", c.field_id, escape_html(&c.name), c.offsets, c.masks, c.factors, escape_html(&c.unit), c.typ)?;
+            }
+            writeln!(w, "</table>")?;
+        }
+    }
+
+    writeln!(w, "</body>")?;
+    writeln!(w, "</html>")?;
+
+    Ok(())
+}
+
+
+/// Write the packet reference documentation for a `Specification` in the given `DocFormat`.
+pub fn write_docs<W: Write>(w: &mut W, spec: &Specification, format: DocFormat) -> Result<()> {
+    match format {
+        DocFormat::Markdown => write_markdown(w, spec),
+        DocFormat::Html => write_html(w, spec),
+    }
+}
+
+
+/// Render the packet reference documentation for a `Specification` in the given `DocFormat`.
+pub fn docs_to_string(spec: &Specification, format: DocFormat) -> String {
+    let mut buf = Vec::new();
+    write_docs(&mut buf, spec, format).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use specification_file::{Language, SpecificationFile};
+
+    use test_data::SPEC_FILE_1;
+
+    use super::*;
+
+    #[test]
+    fn test_docs_to_string() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let spec = Specification::from_file(spec_file, Language::De);
+
+        let markdown = docs_to_string(&spec, DocFormat::Markdown);
+        assert!(markdown.starts_with("# VBus Packet List\n\n- Specification datecode: "));
+        assert!(markdown.contains("\n\n## `0010_7E30_10_0100`: DeltaSol MX [WMZ #0]\n\n- Source: DeltaSol MX [WMZ #0] (0x7E30)\n- Destination: DFA (0x0010)\n- Command: 0x0100\n\n| ID | Name | Offsets | Bit masks | Factors | Unit | Type |\n|---|---|---|---|---|---|---|\n| `000_4_0` | Wärmemenge | "));
+
+        let html = docs_to_string(&spec, DocFormat::Html);
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<h2 id=\"0010_7E30_10_0100\"><code>0010_7E30_10_0100</code>: DeltaSol MX [WMZ #0]</h2>"));
+        assert!(html.contains("<tr><td><code>000_4_0</code></td><td>Wärmemenge</td>"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+
+        assert_eq!("a \\| b", escape_markdown("a | b"));
+        assert_eq!("&lt;a href=&quot;&amp;&quot;&gt;", escape_html("<a href=\"&\">"));
+    }
+}
@@ -0,0 +1,416 @@
+//! A module that provides enumeration and bit-flag types for packet fields.
+//!
+//! VSF1 specification files describe all packet fields as numbers, times or dates. A
+//! `SpecificationExtension` adds `EnumType` values that map raw values (for `EnumKind::Enum`) or
+//! the indices of set bits (for `EnumKind::BitFlags`) to localized labels and assigns them to
+//! packet fields. The `Type` of those fields is left unchanged, the `EnumType` is stored in
+//! `PacketFieldSpec::enum_type` instead.
+//!
+//! Extension files are stored as plain text next to the VSF file. They consist of sections
+//! containing one `<key> = <value>` pair per line. Empty lines and lines starting with `#` are
+//! ignored.
+//!
+//! - `[enum <name>]` and `[flags <name>]` sections define an `EnumType`. Each line maps a raw
+//!   value or bit index to its English label. Labels for other languages are added by appending
+//!   the language code to the value (e.g. `1.de = An`).
+//! - the `[fields]` section assigns `EnumType` values to packet fields. The keys are the packet
+//!   field IDs without the VBus channel prefix (e.g. `0010_7E11_10_0100_076_1_0`).
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{Specification, SpecificationFile, SpecificationExtension, Language};
+//! use resol_vbus::specification_extension::EnumKind;
+//!
+//! let ext = SpecificationExtension::parse("
+//!     [enum RelayState]
+//!     0 = Off
+//!     0.de = Aus
+//!     100 = On
+//!     100.de = An
+//!
+//!     [fields]
+//!     0010_7E11_10_0100_076_1_0 = RelayState
+//! ").unwrap();
+//!
+//! let mut spec = Specification::from_file(SpecificationFile::new_default(), Language::De);
+//! spec.push_extension(ext);
+//!
+//! let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+//! let field_spec = packet_spec.get_field_spec("076_1_0").unwrap();
+//!
+//! assert_eq!(EnumKind::Enum, field_spec.enum_type.as_ref().unwrap().kind);
+//! assert_eq!("An", format!("{}", field_spec.fmt_raw_value(Some(100), true)));
+//! assert_eq!("42", format!("{}", field_spec.fmt_raw_value(Some(42), true)));
+//! ```
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use specification_file::Language;
+
+
+/// A list of errors that can occur if a `SpecificationExtension` cannot be parsed.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The line with the given 1-based number is neither a section header nor a `<key> = <value>` pair.
+    InvalidLine(usize),
+
+    /// The line with the given 1-based number appears outside of a section.
+    MissingSection(usize),
+
+    /// The line with the given 1-based number uses an unknown language code.
+    UnknownLanguage(usize),
+
+    /// The line with the given 1-based number defines an `EnumType` whose name is already used.
+    DuplicateEnumType(usize),
+
+    /// The line with the given 1-based number references an undefined `EnumType`.
+    UnknownEnumType(usize),
+}
+
+
+/// A specialized Result for this module.
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+
+/// The way an `EnumType` interprets raw values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EnumKind {
+    /// Enumeration, formatted as the localized label of the raw value.
+    Enum,
+
+    /// Bit flags, formatted as the list of localized labels of all set bits.
+    BitFlags,
+}
+
+
+/// A single value of an `EnumType` and its localized labels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+    /// The raw value (for `EnumKind::Enum`) or bit index (for `EnumKind::BitFlags`).
+    pub value: i64,
+
+    /// The labels of the variant by `Language`.
+    pub labels: Vec<(Language, String)>,
+}
+
+
+/// An enumeration or bit-flag type that maps values to localized labels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumType {
+    /// The name of the type.
+    pub name: String,
+
+    /// The way the type interprets raw values.
+    pub kind: EnumKind,
+
+    /// The known values of the type.
+    pub variants: Vec<EnumVariant>,
+}
+
+
+/// A set of `EnumType` values and their assignments to packet fields.
+#[derive(Debug, Clone, Default)]
+pub struct SpecificationExtension {
+    enum_types: Vec<Arc<EnumType>>,
+    fields: HashMap<String, Arc<EnumType>>,
+}
+
+
+pub(crate) fn language_by_code(code: &str) -> Option<Language> {
+    match code {
+        "en" => Some(Language::En),
+        "de" => Some(Language::De),
+        "fr" => Some(Language::Fr),
+        "it" => Some(Language::It),
+        "es" => Some(Language::Es),
+        "nl" => Some(Language::Nl),
+        _ => None,
+    }
+}
+
+
+impl EnumVariant {
+
+    /// Get the label for the given `Language`, falling back to English.
+    pub fn label(&self, language: Language) -> &str {
+        let label = self.labels.iter().find(|(label_language, _)| *label_language == language)
+            .or_else(|| self.labels.iter().find(|(label_language, _)| *label_language == Language::En))
+            .or_else(|| self.labels.first());
+
+        match label {
+            Some((_, label)) => label,
+            None => "",
+        }
+    }
+
+}
+
+
+impl EnumType {
+
+    /// Construct an empty `EnumType`.
+    pub fn new(name: &str, kind: EnumKind) -> EnumType {
+        EnumType {
+            name: name.to_owned(),
+            kind,
+            variants: Vec::new(),
+        }
+    }
+
+    /// Get the `EnumVariant` for the given raw value or bit index.
+    pub fn variant(&self, value: i64) -> Option<&EnumVariant> {
+        self.variants.iter().find(|variant| variant.value == value)
+    }
+
+    /// Add or replace the label of a value for the given `Language`.
+    pub fn insert_label(&mut self, value: i64, language: Language, label: &str) {
+        let index = match self.variants.iter().position(|variant| variant.value == value) {
+            Some(index) => index,
+            None => {
+                self.variants.push(EnumVariant {
+                    value,
+                    labels: Vec::new(),
+                });
+                self.variants.len() - 1
+            }
+        };
+
+        let labels = &mut self.variants [index].labels;
+        match labels.iter().position(|(label_language, _)| *label_language == language) {
+            Some(index) => labels [index].1 = label.to_owned(),
+            None => labels.push((language, label.to_owned())),
+        }
+    }
+
+}
+
+
+impl SpecificationExtension {
+
+    /// Construct an empty `SpecificationExtension`.
+    pub fn new() -> SpecificationExtension {
+        SpecificationExtension::default()
+    }
+
+    /// Parse a `SpecificationExtension` from its text representation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{Language, SpecificationExtension};
+    /// use resol_vbus::specification_extension::{EnumKind, Error};
+    ///
+    /// let ext = SpecificationExtension::parse("
+    ///     [flags SensorErrors]
+    ///     0 = Sensor 1 defective
+    ///     0.fr = Sonde 1 défectueuse
+    /// ").unwrap();
+    ///
+    /// let enum_type = ext.enum_type_by_name("SensorErrors").unwrap();
+    /// assert_eq!(EnumKind::BitFlags, enum_type.kind);
+    /// assert_eq!("Sonde 1 défectueuse", enum_type.variant(0).unwrap().label(Language::Fr));
+    /// assert_eq!("Sensor 1 defective", enum_type.variant(0).unwrap().label(Language::Nl));
+    ///
+    /// assert_eq!(Error::UnknownEnumType(2), SpecificationExtension::parse("[fields]\n0010_7E11_10_0100_076_1_0 = Unknown").unwrap_err());
+    /// ```
+    pub fn parse(input: &str) -> Result<SpecificationExtension> {
+        let mut ext = SpecificationExtension::new();
+
+        let mut enum_types: Vec<EnumType> = Vec::new();
+        let mut fields: Vec<(usize, &str, &str)> = Vec::new();
+
+        // `None` before the first section, `Some(None)` in the `[fields]` section and
+        // `Some(Some(index))` in the section of the `EnumType` at `index`.
+        let mut section: Option<Option<usize>> = None;
+
+        for (index, line) in input.lines().enumerate() {
+            let line_number = index + 1;
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                if !line.ends_with(']') {
+                    return Err(Error::InvalidLine(line_number));
+                }
+
+                let header = line [1..(line.len() - 1)].trim();
+                let mut words = header.split_whitespace();
+                let kind = match words.next() {
+                    Some("fields") => None,
+                    Some("enum") => Some(EnumKind::Enum),
+                    Some("flags") => Some(EnumKind::BitFlags),
+                    _ => return Err(Error::InvalidLine(line_number)),
+                };
+
+                section = match (kind, words.next(), words.next()) {
+                    (None, None, None) => Some(None),
+                    (Some(kind), Some(name), None) => {
+                        if enum_types.iter().any(|enum_type| enum_type.name == name) {
+                            return Err(Error::DuplicateEnumType(line_number));
+                        }
+                        enum_types.push(EnumType::new(name, kind));
+                        Some(Some(enum_types.len() - 1))
+                    }
+                    _ => return Err(Error::InvalidLine(line_number)),
+                };
+                continue;
+            }
+
+            let separator = match line.find('=') {
+                Some(separator) => separator,
+                None => return Err(Error::InvalidLine(line_number)),
+            };
+
+            let key = line [..separator].trim();
+            let value = line [(separator + 1)..].trim();
+
+            match section {
+                None => return Err(Error::MissingSection(line_number)),
+                Some(None) => fields.push((line_number, key, value)),
+                Some(Some(enum_type_index)) => {
+                    let (raw_value, language) = match key.find('.') {
+                        Some(dot) => match language_by_code(&key [(dot + 1)..]) {
+                            Some(language) => (&key [..dot], language),
+                            None => return Err(Error::UnknownLanguage(line_number)),
+                        },
+                        None => (key, Language::En),
+                    };
+
+                    let raw_value = match raw_value.parse() {
+                        Ok(raw_value) => raw_value,
+                        Err(_) => return Err(Error::InvalidLine(line_number)),
+                    };
+
+                    enum_types [enum_type_index].insert_label(raw_value, language, value);
+                }
+            }
+        }
+
+        for enum_type in enum_types {
+            ext.add_enum_type(enum_type);
+        }
+
+        for (line_number, key, name) in fields {
+            if !ext.assign_field(key, name) {
+                return Err(Error::UnknownEnumType(line_number));
+            }
+        }
+
+        Ok(ext)
+    }
+
+    /// Add an `EnumType`, replacing any previously added type with the same name.
+    pub fn add_enum_type(&mut self, enum_type: EnumType) -> Arc<EnumType> {
+        let enum_type = Arc::new(enum_type);
+        match self.enum_types.iter().position(|other| other.name == enum_type.name) {
+            Some(index) => self.enum_types [index] = enum_type.clone(),
+            None => self.enum_types.push(enum_type.clone()),
+        }
+        enum_type
+    }
+
+    /// Assign the `EnumType` with the given name to a packet field.
+    ///
+    /// The key is the packet field ID without the VBus channel prefix. Returns `false` if no
+    /// `EnumType` with that name exists.
+    pub fn assign_field(&mut self, key: &str, name: &str) -> bool {
+        match self.enum_type_by_name(name).cloned() {
+            Some(enum_type) => {
+                self.fields.insert(key.to_owned(), enum_type);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Get the list of `EnumType` values.
+    pub fn enum_types(&self) -> &[Arc<EnumType>] {
+        &self.enum_types
+    }
+
+    /// Get an `EnumType` by its name.
+    pub fn enum_type_by_name(&self, name: &str) -> Option<&Arc<EnumType>> {
+        self.enum_types.iter().find(|enum_type| enum_type.name == name)
+    }
+
+    /// Get the `EnumType` assigned to a packet field.
+    ///
+    /// The key is the packet field ID without the VBus channel prefix.
+    pub fn enum_type_for_field(&self, key: &str) -> Option<&Arc<EnumType>> {
+        self.fields.get(key)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let ext = SpecificationExtension::parse("
+            # comment
+            [fields]
+            0010_7E11_10_0100_076_1_0 = RelayState
+            0010_7E11_10_0100_077_1_0 = RelayState
+            0010_7E11_10_0100_020_4_0 = Errors
+
+            [enum RelayState]
+            0 = Off
+            0.de = Aus
+            -1 = Unknown = ?
+
+            [ flags  Errors ]
+            3 = Sensor 4 defective
+        ").unwrap();
+
+        assert_eq!(2, ext.enum_types().len());
+
+        let enum_type = ext.enum_type_for_field("0010_7E11_10_0100_076_1_0").unwrap();
+        assert_eq!("RelayState", enum_type.name);
+        assert_eq!(EnumKind::Enum, enum_type.kind);
+        assert_eq!(2, enum_type.variants.len());
+        assert_eq!(vec![ (Language::En, "Off".to_owned()), (Language::De, "Aus".to_owned()) ], enum_type.variant(0).unwrap().labels);
+        assert_eq!("Unknown = ?", enum_type.variant(-1).unwrap().label(Language::De));
+        assert_eq!(None, enum_type.variant(1));
+
+        assert!(Arc::ptr_eq(enum_type, ext.enum_type_for_field("0010_7E11_10_0100_077_1_0").unwrap()));
+
+        let enum_type = ext.enum_type_for_field("0010_7E11_10_0100_020_4_0").unwrap();
+        assert_eq!("Errors", enum_type.name);
+        assert_eq!(EnumKind::BitFlags, enum_type.kind);
+
+        assert_eq!(None, ext.enum_type_for_field("0010_7E11_10_0100_000_2_0"));
+
+        let parse_err = |input| SpecificationExtension::parse(input).unwrap_err();
+
+        assert_eq!(Error::MissingSection(1), parse_err("0 = Off"));
+        assert_eq!(Error::InvalidLine(1), parse_err("[enum]"));
+        assert_eq!(Error::InvalidLine(1), parse_err("[unknown Name]"));
+        assert_eq!(Error::InvalidLine(2), parse_err("[enum Name]\nOff"));
+        assert_eq!(Error::InvalidLine(2), parse_err("[enum Name]\nx = Off"));
+        assert_eq!(Error::UnknownLanguage(2), parse_err("[enum Name]\n0.xx = Off"));
+        assert_eq!(Error::DuplicateEnumType(2), parse_err("[enum Name]\n[flags Name]"));
+    }
+
+    #[test]
+    fn test_label() {
+        let mut enum_type = EnumType::new("Name", EnumKind::Enum);
+        enum_type.insert_label(1, Language::De, "Eins");
+
+        assert_eq!("Eins", enum_type.variant(1).unwrap().label(Language::Fr));
+
+        enum_type.insert_label(1, Language::En, "One");
+        enum_type.insert_label(1, Language::De, "Ein");
+
+        assert_eq!("One", enum_type.variant(1).unwrap().label(Language::Fr));
+        assert_eq!("Ein", enum_type.variant(1).unwrap().label(Language::De));
+        assert_eq!(1, enum_type.variants.len());
+        assert_eq!(2, enum_type.variants [0].labels.len());
+    }
+}
@@ -6,6 +6,8 @@
 //! See the [RESOL VBus Specification File Format v1](http://danielwippermann.github.io/resol-vbus/vbus-specification-file-format-v1.html)
 //! for details.
 use std;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
 
 use byteorder::{LittleEndian, ByteOrder};
 
@@ -99,7 +101,21 @@ fn slice_table_entry(buf: &[u8], offset: usize, length: usize, index: usize) ->
 }
 
 
-/// Languages supported by VSF1 specification.
+fn slice_entry_mut(buf: &mut [u8], offset: usize, length: usize) -> &mut [u8] {
+    &mut buf [offset..(offset + length)]
+}
+
+
+fn slice_table_entry_mut(buf: &mut [u8], offset: usize, length: usize, index: usize) -> &mut [u8] {
+    let table_entry_offset = offset + (index * length);
+    slice_entry_mut(buf, table_entry_offset, length)
+}
+
+
+/// Languages supported by `Specification` values.
+///
+/// VSF1 specification files only contain English, German and French texts. Other languages
+/// fall back to English unless a `TranslationTable` provides the texts.
 ///
 /// # Examples
 ///
@@ -120,6 +136,9 @@ fn slice_table_entry(buf: &[u8], offset: usize, length: usize, index: usize) ->
 /// assert_eq!("Temperature sensor 1", get_loc_text(Language::En));
 /// assert_eq!("Temperatur Sensor 1", get_loc_text(Language::De));
 /// assert_eq!("Température sonde 1", get_loc_text(Language::Fr));
+///
+/// // Languages not contained in VSF1 files fall back to English
+/// assert_eq!("Temperature sensor 1", get_loc_text(Language::It));
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Language {
@@ -131,12 +150,30 @@ pub enum Language {
 
     /// French language
     Fr,
+
+    /// Italian language
+    ///
+    /// VSF1 files do not contain Italian texts, so localized texts fall back
+    /// to English unless a `TranslationTable` provides them.
+    It,
+
+    /// Spanish language
+    ///
+    /// VSF1 files do not contain Spanish texts, so localized texts fall back
+    /// to English unless a `TranslationTable` provides them.
+    Es,
+
+    /// Dutch language
+    ///
+    /// VSF1 files do not contain Dutch texts, so localized texts fall back
+    /// to English unless a `TranslationTable` provides them.
+    Nl,
 }
 
 
 /// A numeric reference to a `Text`.
 #[derive(Clone, Copy, Debug)]
-pub struct TextIndex(i32);
+pub struct TextIndex(pub i32);
 
 
 /// Combines three `TextIndex` values for each of the supported languages to form a localized text.
@@ -155,7 +192,7 @@ pub struct LocalizedText {
 
 /// A numeric reference to a `LocalizedText` instance.
 #[derive(Clone, Copy, Debug)]
-pub struct LocalizedTextIndex(i32);
+pub struct LocalizedTextIndex(pub i32);
 
 
 /// A numeric reference to an `UnitFamily` instance.
@@ -165,6 +202,7 @@ pub struct UnitFamilyId(pub i32);
 
 /// One of the unit families supported by the VSF1 specification.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum UnitFamily {
     /// Not associated with a unit family.
     None,
@@ -259,6 +297,7 @@ pub struct PacketTemplate {
 
 /// A type to describe different data types within the packet fields.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Type {
     /// Floating-point number, supporting precision and an optional unit.
     Number,
@@ -345,6 +384,90 @@ pub struct SpecificationFile {
 }
 
 
+/// An index over the templates of a `SpecificationFile` to speed up template lookups.
+///
+/// Templates are grouped by their address masks. Within each group the templates are stored in
+/// a `HashMap` keyed by their masked addresses. Since a `SpecificationFile` typically only uses a
+/// handful of different masks, a lookup only needs a few hash lookups instead of scanning all
+/// templates. If several templates match, the one appearing first in the file is returned, just
+/// like `SpecificationFile::find_device_template` and `SpecificationFile::find_packet_template`.
+///
+/// The index does not borrow the `SpecificationFile`, so it must be rebuilt if the templates
+/// of the file are modified.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::SpecificationFile;
+/// use resol_vbus::specification_file::TemplateIndex;
+///
+/// let spec_file = SpecificationFile::new_default();
+/// let index = TemplateIndex::new(&spec_file);
+///
+/// let packet_template = index.find_packet_template(&spec_file, 0x0010, 0x7E11, 0x0100).unwrap();
+/// assert_eq!(0x7E11, packet_template.source_address);
+/// ```
+#[derive(Debug)]
+pub struct TemplateIndex {
+    device_templates: Vec<(u16, u16, DeviceTemplateMap)>,
+    packet_templates: Vec<(u16, u16, PacketTemplateMap)>,
+}
+
+
+/// Maps masked self and peer addresses to the index of the first matching `DeviceTemplate`.
+type DeviceTemplateMap = HashMap<u64, usize, BuildHasherDefault<TemplateKeyHasher>>;
+
+
+/// Maps masked destination and source addresses and the command to the index of the first matching `PacketTemplate`.
+type PacketTemplateMap = HashMap<u64, usize, BuildHasherDefault<TemplateKeyHasher>>;
+
+
+/// A fast hasher for the packed address keys used by the `TemplateIndex`.
+///
+/// The default SipHash is DoS-resistant, but its overhead dominates lookups with keys of this size.
+#[derive(Default)]
+struct TemplateKeyHasher(u64);
+
+
+impl Hasher for TemplateKeyHasher {
+
+    fn finish(&self) -> u64 {
+        self.0.wrapping_mul(0x9E37_79B9_7F4A_7C15).rotate_left(32)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 << 8) ^ (self.0 >> 56) ^ byte as u64;
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
+    }
+
+}
+
+
+fn device_template_key(self_address: u16, peer_address: u16) -> u64 {
+    ((self_address as u64) << 16) | peer_address as u64
+}
+
+
+fn packet_template_key(destination_address: u16, source_address: u16, command: u16) -> u64 {
+    ((destination_address as u64) << 32) | ((source_address as u64) << 16) | command as u64
+}
+
+
+impl PacketTemplate {
+
+    /// Get the packet ID of the template without the VBus channel prefix (e.g. `0010_7E11_10_0100`).
+    pub(crate) fn id_string(&self) -> String {
+        format!("{:04X}_{:04X}_10_{:04X}", self.destination_address, self.source_address, self.command)
+    }
+
+}
+
+
 impl SpecificationFile {
 
     /// Construct a new `SpecificationFile` from a byte slice of VSF1 data.
@@ -391,6 +514,153 @@ impl SpecificationFile {
         }
     }
 
+    /// Convert this `SpecificationFile` into a byte vector of VSF1 data.
+    ///
+    /// The result can be parsed using `SpecificationFile::from_bytes` again.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::SpecificationFile;
+    ///
+    /// let spec_file = SpecificationFile::new_default();
+    ///
+    /// let bytes = spec_file.to_bytes();
+    ///
+    /// let other_spec_file = SpecificationFile::from_bytes(&bytes).unwrap();
+    /// assert_eq!(spec_file.packet_templates.len(), other_spec_file.packet_templates.len());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let field_count = self.packet_templates.iter().fold(0, |memo, packet_template| {
+            memo + packet_template.fields.len()
+        });
+
+        let part_count = self.packet_templates.iter().fold(0, |memo, packet_template| {
+            packet_template.fields.iter().fold(memo, |memo, field| memo + field.parts.len())
+        });
+
+        let string_length = self.texts.iter().fold(0, |memo, text| memo + text.len() + 1);
+
+        let specification_offset = 0x10;
+        let text_table_offset = specification_offset + 0x2C;
+        let localized_text_table_offset = text_table_offset + self.texts.len() * 0x04;
+        let unit_table_offset = localized_text_table_offset + self.localized_texts.len() * 0x0C;
+        let device_template_table_offset = unit_table_offset + self.units.len() * 0x10;
+        let packet_template_table_offset = device_template_table_offset + self.device_templates.len() * 0x0C;
+        let field_table_offset = packet_template_table_offset + self.packet_templates.len() * 0x14;
+        let part_table_offset = field_table_offset + field_count * 0x1C;
+        let string_offset = part_table_offset + part_count * 0x10;
+        let total_length = string_offset + string_length;
+
+        let mut bytes = vec![0u8; total_length];
+
+        {
+            let block = slice_entry_mut(&mut bytes, specification_offset, 0x2C);
+            LittleEndian::write_i32(&mut block [0x00..0x04], self.datecode);
+            LittleEndian::write_i32(&mut block [0x04..0x08], self.texts.len() as i32);
+            LittleEndian::write_i32(&mut block [0x08..0x0C], text_table_offset as i32);
+            LittleEndian::write_i32(&mut block [0x0C..0x10], self.localized_texts.len() as i32);
+            LittleEndian::write_i32(&mut block [0x10..0x14], localized_text_table_offset as i32);
+            LittleEndian::write_i32(&mut block [0x14..0x18], self.units.len() as i32);
+            LittleEndian::write_i32(&mut block [0x18..0x1C], unit_table_offset as i32);
+            LittleEndian::write_i32(&mut block [0x1C..0x20], self.device_templates.len() as i32);
+            LittleEndian::write_i32(&mut block [0x20..0x24], device_template_table_offset as i32);
+            LittleEndian::write_i32(&mut block [0x24..0x28], self.packet_templates.len() as i32);
+            LittleEndian::write_i32(&mut block [0x28..0x2C], packet_template_table_offset as i32);
+        }
+
+        let mut next_string_offset = string_offset;
+        for (index, text) in self.texts.iter().enumerate() {
+            {
+                let block = slice_table_entry_mut(&mut bytes, text_table_offset, 0x04, index);
+                LittleEndian::write_i32(&mut block [0x00..0x04], next_string_offset as i32);
+            }
+
+            let text_bytes = text.as_bytes();
+            let text_length = text_bytes.len();
+            bytes [next_string_offset..(next_string_offset + text_length)].copy_from_slice(text_bytes);
+            next_string_offset += text_length + 1;
+        }
+
+        for (index, localized_text) in self.localized_texts.iter().enumerate() {
+            let block = slice_table_entry_mut(&mut bytes, localized_text_table_offset, 0x0C, index);
+            LittleEndian::write_i32(&mut block [0x00..0x04], localized_text.text_index_en.0);
+            LittleEndian::write_i32(&mut block [0x04..0x08], localized_text.text_index_de.0);
+            LittleEndian::write_i32(&mut block [0x08..0x0C], localized_text.text_index_fr.0);
+        }
+
+        for (index, unit) in self.units.iter().enumerate() {
+            let block = slice_table_entry_mut(&mut bytes, unit_table_offset, 0x10, index);
+            LittleEndian::write_i32(&mut block [0x00..0x04], unit.unit_id.0);
+            LittleEndian::write_i32(&mut block [0x04..0x08], unit.unit_family_id.0);
+            LittleEndian::write_i32(&mut block [0x08..0x0C], unit.unit_code_text_index.0);
+            LittleEndian::write_i32(&mut block [0x0C..0x10], unit.unit_text_text_index.0);
+        }
+
+        for (index, device_template) in self.device_templates.iter().enumerate() {
+            let block = slice_table_entry_mut(&mut bytes, device_template_table_offset, 0x0C, index);
+            LittleEndian::write_u16(&mut block [0x00..0x02], device_template.self_address);
+            LittleEndian::write_u16(&mut block [0x02..0x04], device_template.self_mask);
+            LittleEndian::write_u16(&mut block [0x04..0x06], device_template.peer_address);
+            LittleEndian::write_u16(&mut block [0x06..0x08], device_template.peer_mask);
+            LittleEndian::write_i32(&mut block [0x08..0x0C], device_template.name_localized_text_index.0);
+        }
+
+        let mut field_index = 0;
+        let mut part_index = 0;
+        for (index, packet_template) in self.packet_templates.iter().enumerate() {
+            {
+                let block = slice_table_entry_mut(&mut bytes, packet_template_table_offset, 0x14, index);
+                LittleEndian::write_u16(&mut block [0x00..0x02], packet_template.destination_address);
+                LittleEndian::write_u16(&mut block [0x02..0x04], packet_template.destination_mask);
+                LittleEndian::write_u16(&mut block [0x04..0x06], packet_template.source_address);
+                LittleEndian::write_u16(&mut block [0x06..0x08], packet_template.source_mask);
+                LittleEndian::write_u16(&mut block [0x08..0x0A], packet_template.command);
+                LittleEndian::write_i32(&mut block [0x0C..0x10], packet_template.fields.len() as i32);
+                LittleEndian::write_i32(&mut block [0x10..0x14], (field_table_offset + field_index * 0x1C) as i32);
+            }
+
+            for field in packet_template.fields.iter() {
+                {
+                    let block = slice_table_entry_mut(&mut bytes, field_table_offset, 0x1C, field_index);
+                    LittleEndian::write_i32(&mut block [0x00..0x04], field.id_text_index.0);
+                    LittleEndian::write_i32(&mut block [0x04..0x08], field.name_localized_text_index.0);
+                    LittleEndian::write_i32(&mut block [0x08..0x0C], field.unit_id.0);
+                    LittleEndian::write_i32(&mut block [0x0C..0x10], field.precision);
+                    LittleEndian::write_i32(&mut block [0x10..0x14], field.type_id.0);
+                    LittleEndian::write_i32(&mut block [0x14..0x18], field.parts.len() as i32);
+                    LittleEndian::write_i32(&mut block [0x18..0x1C], (part_table_offset + part_index * 0x10) as i32);
+                }
+
+                for part in field.parts.iter() {
+                    let block = slice_table_entry_mut(&mut bytes, part_table_offset, 0x10, part_index);
+                    LittleEndian::write_i32(&mut block [0x00..0x04], part.offset);
+                    block [0x04] = part.bit_pos;
+                    block [0x05] = part.mask;
+                    block [0x06] = if part.is_signed { 1 } else { 0 };
+                    LittleEndian::write_i64(&mut block [0x08..0x10], part.factor);
+
+                    part_index += 1;
+                }
+
+                field_index += 1;
+            }
+        }
+
+        {
+            let fileheader = slice_entry_mut(&mut bytes, 0, 0x10);
+            LittleEndian::write_i32(&mut fileheader [0x04..0x08], total_length as i32);
+            LittleEndian::write_i32(&mut fileheader [0x08..0x0C], 1);
+            LittleEndian::write_i32(&mut fileheader [0x0C..0x10], specification_offset as i32);
+        }
+
+        let checksum = calc_crc16(&bytes [0x04..total_length]);
+        LittleEndian::write_u16(&mut bytes [0x00..0x02], checksum);
+        LittleEndian::write_u16(&mut bytes [0x02..0x04], checksum);
+
+        bytes
+    }
+
     /// Construct a new `SpecificationFile` from the embedded default VSF data.
     pub fn new_default() -> SpecificationFile {
         Self::from_bytes(include_bytes!("../res/vbus_specification.vsf")).unwrap()
@@ -403,10 +673,12 @@ impl SpecificationFile {
     }
 
     /// Get localized text by its index and language.
+    ///
+    /// Languages not contained in VSF1 files fall back to English.
     pub fn localized_text_by_index(&self, idx: &LocalizedTextIndex, language: Language) -> &str {
         let localized_text = &self.localized_texts [idx.0 as usize];
         let text_index = match language {
-            Language::En => &localized_text.text_index_en,
+            Language::En | Language::It | Language::Es | Language::Nl => &localized_text.text_index_en,
             Language::De => &localized_text.text_index_de,
             Language::Fr => &localized_text.text_index_fr,
         };
@@ -724,6 +996,71 @@ impl SpecificationFile {
 }
 
 
+impl TemplateIndex {
+
+    /// Construct a new `TemplateIndex` for the templates of a `SpecificationFile`.
+    pub fn new(file: &SpecificationFile) -> TemplateIndex {
+        let mut device_templates: Vec<(u16, u16, DeviceTemplateMap)> = Vec::new();
+        for (index, device_template) in file.device_templates.iter().enumerate() {
+            let self_mask = device_template.self_mask;
+            let peer_mask = device_template.peer_mask;
+
+            let group_index = match device_templates.iter().position(|group| group.0 == self_mask && group.1 == peer_mask) {
+                Some(group_index) => group_index,
+                None => {
+                    device_templates.push((self_mask, peer_mask, DeviceTemplateMap::default()));
+                    device_templates.len() - 1
+                }
+            };
+
+            let key = device_template_key(device_template.self_address & self_mask, device_template.peer_address & peer_mask);
+            device_templates [group_index].2.entry(key).or_insert(index);
+        }
+
+        let mut packet_templates: Vec<(u16, u16, PacketTemplateMap)> = Vec::new();
+        for (index, packet_template) in file.packet_templates.iter().enumerate() {
+            let destination_mask = packet_template.destination_mask;
+            let source_mask = packet_template.source_mask;
+
+            let group_index = match packet_templates.iter().position(|group| group.0 == destination_mask && group.1 == source_mask) {
+                Some(group_index) => group_index,
+                None => {
+                    packet_templates.push((destination_mask, source_mask, PacketTemplateMap::default()));
+                    packet_templates.len() - 1
+                }
+            };
+
+            let key = packet_template_key(packet_template.destination_address & destination_mask, packet_template.source_address & source_mask, packet_template.command);
+            packet_templates [group_index].2.entry(key).or_insert(index);
+        }
+
+        TemplateIndex {
+            device_templates,
+            packet_templates,
+        }
+    }
+
+    /// Find a `DeviceTemplate` matching the self and peer addresses.
+    ///
+    /// The `SpecificationFile` must be the same one this index was constructed from.
+    pub fn find_device_template<'a>(&self, file: &'a SpecificationFile, self_address: u16, peer_address: u16) -> Option<&'a DeviceTemplate> {
+        self.device_templates.iter().filter_map(|&(self_mask, peer_mask, ref templates)| {
+            templates.get(&device_template_key(self_address & self_mask, peer_address & peer_mask))
+        }).min().map(|&index| &file.device_templates [index])
+    }
+
+    /// Find a `PacketTemplate` matching the destination and source addresses as well as the command.
+    ///
+    /// The `SpecificationFile` must be the same one this index was constructed from.
+    pub fn find_packet_template<'a>(&self, file: &'a SpecificationFile, destination_address: u16, source_address: u16, command: u16) -> Option<&'a PacketTemplate> {
+        self.packet_templates.iter().filter_map(|&(destination_mask, source_mask, ref templates)| {
+            templates.get(&packet_template_key(destination_address & destination_mask, source_address & source_mask, command))
+        }).min().map(|&index| &file.packet_templates [index])
+    }
+
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1124,4 +1461,61 @@ mod tests {
     fn test_new_default() {
         let _spec_file = SpecificationFile::new_default();
     }
+
+    #[test]
+    fn test_template_index() {
+        let spec_file = SpecificationFile::new_default();
+
+        let index = TemplateIndex::new(&spec_file);
+
+        let ptr = |template: Option<&DeviceTemplate>| template.map(|template| template as *const _);
+
+        for self_address in (0..0x10000).step_by(7) {
+            let self_address = self_address as u16;
+            for &peer_address in &[ 0x0000, 0x0010, 0x0015, 0x7E11 ] {
+                let expected = ptr(spec_file.find_device_template(self_address, peer_address));
+                assert_eq!(expected, ptr(index.find_device_template(&spec_file, self_address, peer_address)));
+            }
+        }
+
+        let ptr = |template: Option<&PacketTemplate>| template.map(|template| template as *const _);
+
+        for packet_template in &spec_file.packet_templates {
+            for &(destination_xor, source_xor) in &[ (0x0000, 0x0000), (0x0000, 0x000F), (0x000F, 0x0000), (0x0100, 0x0100) ] {
+                let destination_address = packet_template.destination_address ^ destination_xor;
+                let source_address = packet_template.source_address ^ source_xor;
+                for &command in &[ packet_template.command, packet_template.command ^ 0x0001 ] {
+                    let expected = ptr(spec_file.find_packet_template(destination_address, source_address, command));
+                    assert_eq!(expected, ptr(index.find_packet_template(&spec_file, destination_address, source_address, command)));
+                }
+            }
+        }
+
+        assert!(index.find_packet_template(&spec_file, 0x0010, 0x7E11, 0x0100).is_some());
+        assert!(index.find_packet_template(&spec_file, 0x0010, 0x7E11, 0x7777).is_none());
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let bytes = spec_file.to_bytes();
+
+        let spec_file = SpecificationFile::from_bytes(&bytes).unwrap();
+
+        check_spec_file_fixture(&spec_file);
+
+        assert_eq!(bytes, spec_file.to_bytes());
+
+        let spec_file = SpecificationFile::new_default();
+
+        let bytes = spec_file.to_bytes();
+
+        let other_spec_file = SpecificationFile::from_bytes(&bytes).unwrap();
+
+        assert_eq!(spec_file.datecode, other_spec_file.datecode);
+        assert_eq!(spec_file.texts, other_spec_file.texts);
+        assert_eq!(spec_file.packet_templates.len(), other_spec_file.packet_templates.len());
+        assert_eq!(bytes, other_spec_file.to_bytes());
+    }
 }
@@ -0,0 +1,458 @@
+//! A module that checks a `SpecificationFile` for semantic problems.
+//!
+//! `SpecificationFile::from_bytes` only verifies that the tables and indices of the VSF1 data are
+//! in range. The `lint` function of this module additionally reports problems that would lead to
+//! wrong or missing values at runtime, like field parts that do not fit into the frame data of a
+//! packet or packet templates that are never selected because an earlier template matches first.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::SpecificationFile;
+//! use resol_vbus::specification_lint::{lint, Severity};
+//!
+//! let spec_file = SpecificationFile::new_default();
+//!
+//! let issues = lint(&spec_file);
+//! assert!(issues.iter().all(|issue| issue.severity() == Severity::Warning));
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+
+use specification_file::{Language, PacketTemplate, SpecificationFile, UnitFamily};
+use unit_conversion::find_unit_definition;
+
+
+/// The maximum length of the frame data of a `Packet` in bytes.
+const MAX_FRAME_DATA_LENGTH: i32 = 508;
+
+
+/// The severity of an `Issue`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The problem does not prevent decoding, but may be unintended.
+    Warning,
+
+    /// The problem leads to wrong or missing values.
+    Error,
+}
+
+
+/// A semantic problem found in a `SpecificationFile`.
+///
+/// Packet templates are referenced by their packet ID (without channel), fields by their field ID
+/// and both additionally by their index in the respective table.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// A part of a field is located outside of the maximum frame data length.
+    PartOutOfBounds {
+        /// The packet ID of the packet template.
+        packet_id: String,
+
+        /// The field ID of the field.
+        field_id: String,
+
+        /// The offset of the part.
+        offset: i32,
+    },
+
+    /// Two parts of a field read the same bits of the frame data.
+    OverlappingParts {
+        /// The packet ID of the packet template.
+        packet_id: String,
+
+        /// The field ID of the field.
+        field_id: String,
+
+        /// The offset at which the parts overlap.
+        offset: i32,
+    },
+
+    /// The bits of two fields partially overlap without one field containing the other.
+    OverlappingFields {
+        /// The packet ID of the packet template.
+        packet_id: String,
+
+        /// The field ID of the first field.
+        field_id: String,
+
+        /// The field ID of the second field.
+        other_field_id: String,
+    },
+
+    /// Two fields of a packet template share the same field ID.
+    DuplicateFieldId {
+        /// The packet ID of the packet template.
+        packet_id: String,
+
+        /// The duplicate field ID.
+        field_id: String,
+    },
+
+    /// A field references a unit ID that is not part of the unit table.
+    UnknownUnit {
+        /// The packet ID of the packet template.
+        packet_id: String,
+
+        /// The field ID of the field.
+        field_id: String,
+
+        /// The unknown unit ID.
+        unit_id: i32,
+    },
+
+    /// A unit is not known to the `unit_conversion` module and cannot be converted.
+    UnconvertibleUnit {
+        /// The unit code of the unit.
+        unit_code: String,
+    },
+
+    /// A packet template is (at least partly) shadowed by an earlier template whose masked
+    /// addresses also match.
+    ShadowedPacketTemplate {
+        /// The packet ID of the shadowed packet template.
+        packet_id: String,
+
+        /// The index of the shadowed packet template.
+        packet_template_index: usize,
+
+        /// The packet ID of the earlier packet template.
+        shadowed_by_packet_id: String,
+
+        /// The index of the earlier packet template.
+        shadowed_by_packet_template_index: usize,
+    },
+
+    /// A localized text has an English text, but an empty text for another language.
+    MissingTranslation {
+        /// The English text.
+        text: String,
+
+        /// The language of the missing translation.
+        language: Language,
+    },
+}
+
+
+impl Issue {
+
+    /// Get the `Severity` of this `Issue`.
+    pub fn severity(&self) -> Severity {
+        match *self {
+            Issue::PartOutOfBounds { .. } |
+            Issue::OverlappingParts { .. } |
+            Issue::DuplicateFieldId { .. } |
+            Issue::UnknownUnit { .. } |
+            Issue::ShadowedPacketTemplate { .. } => Severity::Error,
+            Issue::OverlappingFields { .. } |
+            Issue::UnconvertibleUnit { .. } |
+            Issue::MissingTranslation { .. } => Severity::Warning,
+        }
+    }
+
+}
+
+
+impl fmt::Display for Issue {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Issue::PartOutOfBounds { ref packet_id, ref field_id, offset } => {
+                write!(f, "{}: field {} has a part at offset {} outside of the frame data", packet_id, field_id, offset)
+            }
+            Issue::OverlappingParts { ref packet_id, ref field_id, offset } => {
+                write!(f, "{}: field {} has overlapping parts at offset {}", packet_id, field_id, offset)
+            }
+            Issue::OverlappingFields { ref packet_id, ref field_id, ref other_field_id } => {
+                write!(f, "{}: fields {} and {} partially overlap", packet_id, field_id, other_field_id)
+            }
+            Issue::DuplicateFieldId { ref packet_id, ref field_id } => {
+                write!(f, "{}: field ID {} is used more than once", packet_id, field_id)
+            }
+            Issue::UnknownUnit { ref packet_id, ref field_id, unit_id } => {
+                write!(f, "{}: field {} references unknown unit ID {}", packet_id, field_id, unit_id)
+            }
+            Issue::UnconvertibleUnit { ref unit_code } => {
+                write!(f, "unit {} is unknown to the unit conversion", unit_code)
+            }
+            Issue::ShadowedPacketTemplate { ref packet_id, packet_template_index, ref shadowed_by_packet_id, shadowed_by_packet_template_index } => {
+                write!(f, "{} (#{}): shadowed by {} (#{})", packet_id, packet_template_index, shadowed_by_packet_id, shadowed_by_packet_template_index)
+            }
+            Issue::MissingTranslation { ref text, language } => {
+                write!(f, "{:?} has no {:?} translation", text, language)
+            }
+        }
+    }
+
+}
+
+
+fn lint_packet_template_fields(file: &SpecificationFile, packet_template: &PacketTemplate, issues: &mut Vec<Issue>) {
+    let packet_id = packet_template.id_string();
+
+    let mut field_ids = HashMap::new();
+
+    // the field ID and the offsets and masks of the parts of each field
+    let mut field_bits: Vec<(&str, Vec<(i32, u8)>)> = Vec::new();
+
+    for field in &packet_template.fields {
+        let field_id = file.text_by_index(&field.id_text_index);
+
+        let count = field_ids.entry(field_id).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            issues.push(Issue::DuplicateFieldId {
+                packet_id: packet_id.clone(),
+                field_id: field_id.to_owned(),
+            });
+        }
+
+        if !file.units.iter().any(|unit| unit.unit_id == field.unit_id) {
+            issues.push(Issue::UnknownUnit {
+                packet_id: packet_id.clone(),
+                field_id: field_id.to_owned(),
+                unit_id: field.unit_id.0,
+            });
+        }
+
+        let mut used_bits: Vec<(i32, u8)> = Vec::new();
+
+        for part in &field.parts {
+            if part.offset < 0 || part.offset >= MAX_FRAME_DATA_LENGTH {
+                issues.push(Issue::PartOutOfBounds {
+                    packet_id: packet_id.clone(),
+                    field_id: field_id.to_owned(),
+                    offset: part.offset,
+                });
+                continue;
+            }
+
+            if used_bits.iter().any(|(offset, mask)| *offset == part.offset && mask & part.mask != 0) {
+                issues.push(Issue::OverlappingParts {
+                    packet_id: packet_id.clone(),
+                    field_id: field_id.to_owned(),
+                    offset: part.offset,
+                });
+            }
+            used_bits.push((part.offset, part.mask));
+        }
+
+        if !used_bits.is_empty() {
+            // fields that contain each other are alternative views of the same data (e.g. single
+            // bits of a status byte), only partial overlaps are reported
+            let other = field_bits.iter().find(|(_, other_bits)| {
+                shares_bits(&used_bits, other_bits) &&
+                !contains_bits(&used_bits, other_bits) &&
+                !contains_bits(other_bits, &used_bits)
+            });
+
+            if let Some(&(other_field_id, _)) = other {
+                issues.push(Issue::OverlappingFields {
+                    packet_id: packet_id.clone(),
+                    field_id: other_field_id.to_owned(),
+                    other_field_id: field_id.to_owned(),
+                });
+            }
+
+            field_bits.push((field_id, used_bits));
+        }
+    }
+}
+
+
+fn mask_at(bits: &[(i32, u8)], offset: i32) -> u8 {
+    bits.iter().filter(|&&(bits_offset, _)| bits_offset == offset).fold(0, |mask, &(_, bits_mask)| mask | bits_mask)
+}
+
+
+fn shares_bits(bits: &[(i32, u8)], other_bits: &[(i32, u8)]) -> bool {
+    other_bits.iter().any(|&(offset, mask)| mask_at(bits, offset) & mask != 0)
+}
+
+
+fn contains_bits(bits: &[(i32, u8)], other_bits: &[(i32, u8)]) -> bool {
+    other_bits.iter().all(|&(offset, mask)| mask_at(bits, offset) & mask == mask)
+}
+
+
+fn matches_masked(a_address: u16, a_mask: u16, b_address: u16, b_mask: u16) -> bool {
+    ((a_address ^ b_address) & a_mask & b_mask) == 0
+}
+
+
+fn lint_packet_templates(file: &SpecificationFile, issues: &mut Vec<Issue>) {
+    for (index, packet_template) in file.packet_templates.iter().enumerate() {
+        lint_packet_template_fields(file, packet_template, issues);
+
+        let shadowed_by = file.packet_templates [..index].iter().position(|other| {
+            other.command == packet_template.command &&
+            matches_masked(other.destination_address, other.destination_mask, packet_template.destination_address, packet_template.destination_mask) &&
+            matches_masked(other.source_address, other.source_mask, packet_template.source_address, packet_template.source_mask)
+        });
+
+        if let Some(other_index) = shadowed_by {
+            issues.push(Issue::ShadowedPacketTemplate {
+                packet_id: packet_template.id_string(),
+                packet_template_index: index,
+                shadowed_by_packet_id: file.packet_templates [other_index].id_string(),
+                shadowed_by_packet_template_index: other_index,
+            });
+        }
+    }
+}
+
+
+fn lint_units(file: &SpecificationFile, issues: &mut Vec<Issue>) {
+    for unit in &file.units {
+        let unit_family = file.unit_family_by_id(&unit.unit_family_id);
+        let unit_code = file.text_by_index(&unit.unit_code_text_index);
+        if unit_family != UnitFamily::None && find_unit_definition(unit_family, unit_code).is_none() {
+            issues.push(Issue::UnconvertibleUnit {
+                unit_code: unit_code.to_owned(),
+            });
+        }
+    }
+}
+
+
+fn lint_localized_texts(file: &SpecificationFile, issues: &mut Vec<Issue>) {
+    for localized_text in &file.localized_texts {
+        let text = file.text_by_index(&localized_text.text_index_en);
+        if text.is_empty() {
+            continue;
+        }
+
+        for &(language, text_index) in &[(Language::De, &localized_text.text_index_de), (Language::Fr, &localized_text.text_index_fr)] {
+            if file.text_by_index(text_index).is_empty() {
+                issues.push(Issue::MissingTranslation {
+                    text: text.to_owned(),
+                    language,
+                });
+            }
+        }
+    }
+}
+
+
+/// Check a `SpecificationFile` for semantic problems.
+///
+/// The returned `Issue`s are ordered by the tables they were found in: packet templates, units
+/// and localized texts.
+pub fn lint(file: &SpecificationFile) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    lint_packet_templates(file, &mut issues);
+    lint_units(file, &mut issues);
+    lint_localized_texts(file, &mut issues);
+    issues
+}
+
+
+/// Check a `SpecificationFile` and return all `Issue`s if at least one of them is an error.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::SpecificationFile;
+/// use resol_vbus::specification_lint::check;
+///
+/// assert!(check(&SpecificationFile::new_default()).is_ok());
+/// ```
+pub fn check(file: &SpecificationFile) -> Result<(), Vec<Issue>> {
+    let issues = lint(file);
+    if issues.iter().any(|issue| issue.severity() == Severity::Error) {
+        Err(issues)
+    } else {
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use specification_file::{TextIndex, Unit, UnitFamilyId, UnitId};
+
+    use test_data::SPEC_FILE_1;
+
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+        assert_eq!(Vec::<Issue>::new(), lint(&spec_file));
+        assert!(check(&spec_file).is_ok());
+
+        let mut spec_file = spec_file;
+
+        {
+            let packet_template = &mut spec_file.packet_templates [0];
+            let field = packet_template.fields [0].clone();
+            packet_template.fields.push(field);
+            packet_template.fields [1].parts [0].offset = 508;
+            packet_template.fields [2].unit_id = UnitId(9999);
+            packet_template.fields [3].parts [1].offset = 20;
+            packet_template.fields [4].parts [3].offset = 20;
+        }
+
+        let packet_template = spec_file.packet_templates [0].clone();
+        spec_file.packet_templates.push(packet_template);
+
+        spec_file.texts.push("".to_owned());
+        spec_file.texts.push("Bogons".to_owned());
+        let empty_text_index = TextIndex(spec_file.texts.len() as i32 - 2);
+        spec_file.localized_texts [0].text_index_fr = empty_text_index;
+        spec_file.units.push(Unit {
+            unit_id: UnitId(9998),
+            unit_family_id: UnitFamilyId(1),
+            unit_code_text_index: TextIndex(spec_file.texts.len() as i32 - 1),
+            unit_text_text_index: empty_text_index,
+        });
+
+        let issues = lint(&spec_file);
+        let messages = issues.iter().map(|issue| format!("{}", issue)).collect::<Vec<_>>();
+        assert_eq!(vec![
+            "0010_7E30_10_0100: field 008_4_0 has a part at offset 508 outside of the frame data",
+            "0010_7E30_10_0100: field 012_4_0 references unknown unit ID 9999",
+            "0010_7E30_10_0100: field 020_4_0 has overlapping parts at offset 20",
+            "0010_7E30_10_0100: fields 020_4_0 and 016_4_0 partially overlap",
+            "0010_7E30_10_0100: field ID 000_4_0 is used more than once",
+        ], &messages [..5]);
+        assert_eq!(Severity::Error, issues [0].severity());
+        assert_eq!(Severity::Warning, issues [3].severity());
+
+        assert_eq!(Issue::ShadowedPacketTemplate {
+            packet_id: "0010_7E30_10_0100".to_owned(),
+            packet_template_index: spec_file.packet_templates.len() - 1,
+            shadowed_by_packet_id: "0010_7E30_10_0100".to_owned(),
+            shadowed_by_packet_template_index: 0,
+        }, issues [issues.len() - 3]);
+        assert_eq!("unit Bogons is unknown to the unit conversion", messages [messages.len() - 2]);
+        assert_eq!(Issue::MissingTranslation {
+            text: spec_file.text_by_index(&spec_file.localized_texts [0].text_index_en).to_owned(),
+            language: Language::Fr,
+        }, issues [issues.len() - 1]);
+
+        assert_eq!(issues, check(&spec_file).unwrap_err());
+    }
+
+    #[test]
+    fn test_lint_interleaved_fields() {
+        let spec_file = SpecificationFile::new_default();
+
+        let packet_template = spec_file.packet_templates.iter().find(|packet_template| {
+            packet_template.id_string() == "0000_4010_10_0100"
+        }).unwrap();
+
+        let field_offsets = |field_id| {
+            let field = packet_template.fields.iter().find(|field| spec_file.text_by_index(&field.id_text_index) == field_id).unwrap();
+            field.parts.iter().map(|part| part.offset).collect::<Vec<_>>()
+        };
+
+        // the parts of the two fields are interleaved without sharing any bits
+        assert_eq!(vec![ 2, 3, 0, 1, 12, 13 ], field_offsets("002_2_0"));
+        assert_eq!(vec![ 6, 14 ], field_offsets("006_1_0"));
+
+        let mut issues = Vec::new();
+        lint_packet_template_fields(&spec_file, packet_template, &mut issues);
+        assert_eq!(Vec::<Issue>::new(), issues);
+    }
+}
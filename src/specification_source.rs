@@ -0,0 +1,699 @@
+//! A module that provides a human-editable text representation of a `SpecificationFile`.
+//!
+//! The binary VSF1 format is hard to edit and impossible to review in diffs. The types in this
+//! module describe the same information (units, device templates and packet templates with their
+//! fields and parts) in a JSON document, which can be compiled into a `SpecificationFile` and
+//! decompiled from one.
+//!
+//! Texts are stored inline and referenced by value (e.g. fields reference their unit by its unit
+//! code), the tables of texts and localized texts are generated during compilation.
+//!
+//! This module is only available if the `serde` feature is enabled.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::SpecificationFile;
+//! use resol_vbus::specification_source::SpecificationSource;
+//!
+//! // Decompile the embedded VSF into its JSON representation
+//! let source = SpecificationSource::from_file(&SpecificationFile::new_default());
+//! let json = source.to_json().unwrap();
+//!
+//! // Compile the JSON representation back into a `SpecificationFile`
+//! let source = SpecificationSource::from_json(&json).unwrap();
+//! let spec_file = source.to_file().unwrap();
+//!
+//! assert_eq!(SpecificationFile::new_default().packet_templates.len(), spec_file.packet_templates.len());
+//! ```
+use std;
+use std::collections::HashMap;
+
+use serde_json;
+
+use specification_file::{
+    SpecificationFile,
+    LocalizedText,
+    LocalizedTextIndex,
+    TextIndex,
+    Unit,
+    UnitId,
+    UnitFamily,
+    UnitFamilyId,
+    DeviceTemplate,
+    PacketTemplate,
+    PacketTemplateField,
+    PacketTemplateFieldPart,
+    Language,
+    Type,
+    TypeId,
+};
+
+
+/// A list of errors that can occur if a `SpecificationSource` cannot be parsed or compiled.
+#[derive(Debug)]
+pub enum Error {
+    /// The JSON data could not be parsed or generated.
+    Json(serde_json::Error),
+
+    /// Two units share the same ID.
+    DuplicateUnitId(i32),
+
+    /// Two units share the same unit code.
+    DuplicateUnitCode(String),
+
+    /// A field references a unit code that is not defined.
+    UnknownUnitCode(String),
+}
+
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Json(err)
+    }
+}
+
+
+/// A specialized Result for this module.
+pub type Result<T> = std::result::Result<T, Error>;
+
+
+mod hex_u16 {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(value: &u16, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{:04X}", value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u16, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let digits = s.trim_start_matches("0x").trim_start_matches("0X");
+        u16::from_str_radix(digits, 16).map_err(|_| D::Error::custom(format!("Invalid hex value {:?}", s)))
+    }
+}
+
+
+mod hex_u8 {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer>(value: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("0x{:02X}", value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let digits = s.trim_start_matches("0x").trim_start_matches("0X");
+        u8::from_str_radix(digits, 16).map_err(|_| D::Error::custom(format!("Invalid hex value {:?}", s)))
+    }
+}
+
+
+fn default_mask() -> u8 {
+    0xFF
+}
+
+
+fn default_factor() -> i64 {
+    1
+}
+
+
+/// The text representation of a text in all languages supported by VSF1.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LocalizedTextSource {
+    /// The english text.
+    pub en: String,
+
+    /// The german text.
+    pub de: String,
+
+    /// The french text.
+    pub fr: String,
+}
+
+
+/// The text representation of a `Unit`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnitSource {
+    /// The numeric ID of the unit.
+    pub id: i32,
+
+    /// The `UnitFamily` of the unit.
+    pub family: UnitFamily,
+
+    /// The machine-readable unit code, used by fields to reference this unit.
+    pub code: String,
+
+    /// The human-readable unit text.
+    pub text: String,
+}
+
+
+/// The text representation of a `DeviceTemplate`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeviceSource {
+    /// The VBus address of the device itself.
+    #[serde(with = "hex_u16")]
+    pub self_address: u16,
+
+    /// The mask applied to the VBus address of the device itself.
+    #[serde(with = "hex_u16")]
+    pub self_mask: u16,
+
+    /// The VBus address of a potential peer device.
+    #[serde(with = "hex_u16")]
+    pub peer_address: u16,
+
+    /// The mask applied to the VBus address of the potential peer device.
+    #[serde(with = "hex_u16")]
+    pub peer_mask: u16,
+
+    /// The name of the device.
+    pub name: LocalizedTextSource,
+}
+
+
+/// The text representation of a `PacketTemplate`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PacketSource {
+    /// The VBus address of the destination device.
+    #[serde(with = "hex_u16")]
+    pub destination_address: u16,
+
+    /// The mask applied to the VBus address of the destination device.
+    #[serde(with = "hex_u16")]
+    pub destination_mask: u16,
+
+    /// The VBus address of the source device.
+    #[serde(with = "hex_u16")]
+    pub source_address: u16,
+
+    /// The mask applied to the VBus address of the source device.
+    #[serde(with = "hex_u16")]
+    pub source_mask: u16,
+
+    /// The VBus command of the packet.
+    #[serde(with = "hex_u16")]
+    pub command: u16,
+
+    /// The list of fields contained in the frame data payload.
+    pub fields: Vec<FieldSource>,
+}
+
+
+/// The text representation of a `PacketTemplateField`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FieldSource {
+    /// The field's ID.
+    pub id: String,
+
+    /// The field's name.
+    pub name: LocalizedTextSource,
+
+    /// The unit code of the field's unit.
+    pub unit: String,
+
+    /// The number of fractional digits.
+    pub precision: i32,
+
+    /// The `Type` of the field.
+    #[serde(rename = "type")]
+    pub typ: Type,
+
+    /// The list of parts that make up the field's value.
+    pub parts: Vec<PartSource>,
+}
+
+
+/// The text representation of a `PacketTemplateFieldPart`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PartSource {
+    /// The offset into the frame data payload.
+    pub offset: i32,
+
+    /// The bit position from which the part starts, defaults to 0.
+    #[serde(default)]
+    pub bit_pos: u8,
+
+    /// The bit mask that is applied to this part's value, defaults to 0xFF.
+    #[serde(with = "hex_u8", default = "default_mask")]
+    pub mask: u8,
+
+    /// Whether this part is signed (= sign-extended) or not (= zero-extended), defaults to `false`.
+    #[serde(default)]
+    pub is_signed: bool,
+
+    /// The factor this part is multiplied with, defaults to 1.
+    #[serde(default = "default_factor")]
+    pub factor: i64,
+}
+
+
+/// The text representation of a `SpecificationFile`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpecificationSource {
+    /// Date of VSF creation in format 'YYYYMMDD'
+    pub datecode: i32,
+
+    /// List of units.
+    pub units: Vec<UnitSource>,
+
+    /// List of device templates.
+    pub devices: Vec<DeviceSource>,
+
+    /// List of packet templates.
+    pub packets: Vec<PacketSource>,
+}
+
+
+fn unit_family_id_by_unit_family(unit_family: UnitFamily) -> UnitFamilyId {
+    match unit_family {
+        UnitFamily::None => UnitFamilyId(-1),
+        UnitFamily::Temperature => UnitFamilyId(0),
+        UnitFamily::Energy => UnitFamilyId(1),
+        UnitFamily::VolumeFlow => UnitFamilyId(2),
+        UnitFamily::Pressure => UnitFamilyId(3),
+        UnitFamily::Volume => UnitFamilyId(4),
+        UnitFamily::Time => UnitFamilyId(5),
+        UnitFamily::Power => UnitFamilyId(6),
+    }
+}
+
+
+fn type_id_by_type(typ: Type) -> TypeId {
+    match typ {
+        Type::Number => TypeId(1),
+        Type::Time => TypeId(3),
+        Type::WeekTime => TypeId(4),
+        Type::DateTime => TypeId(5),
+    }
+}
+
+
+type LocalizedTextKey = (String, String, String);
+
+
+struct TextTables {
+    texts: Vec<String>,
+    text_indices: HashMap<String, TextIndex>,
+    localized_texts: Vec<LocalizedText>,
+    localized_text_indices: HashMap<LocalizedTextKey, LocalizedTextIndex>,
+}
+
+
+#[derive(Default)]
+struct TextTableBuilder {
+    texts: Vec<String>,
+    localized_texts: Vec<LocalizedTextKey>,
+    localized_text_indices: HashMap<LocalizedTextKey, usize>,
+}
+
+
+impl TextTableBuilder {
+
+    fn add_text(&mut self, text: &str) {
+        self.texts.push(text.to_owned());
+    }
+
+    fn add_localized_text(&mut self, text: &LocalizedTextSource) {
+        self.add_text(&text.en);
+        self.add_text(&text.de);
+        self.add_text(&text.fr);
+
+        let key = (text.en.clone(), text.de.clone(), text.fr.clone());
+        if !self.localized_text_indices.contains_key(&key) {
+            self.localized_text_indices.insert(key.clone(), self.localized_texts.len());
+            self.localized_texts.push(key);
+        }
+    }
+
+    fn finish(mut self) -> TextTables {
+        self.texts.sort();
+        self.texts.dedup();
+
+        let text_indices = self.texts.iter().enumerate().map(|(index, text)| {
+            (text.clone(), TextIndex(index as i32))
+        }).collect::<HashMap<_, _>>();
+
+        let localized_texts = self.localized_texts.iter().map(|(en, de, fr)| {
+            LocalizedText {
+                text_index_en: text_indices [en],
+                text_index_de: text_indices [de],
+                text_index_fr: text_indices [fr],
+            }
+        }).collect();
+
+        let localized_text_indices = self.localized_text_indices.into_iter().map(|(key, index)| {
+            (key, LocalizedTextIndex(index as i32))
+        }).collect();
+
+        TextTables {
+            texts: self.texts,
+            text_indices,
+            localized_texts,
+            localized_text_indices,
+        }
+    }
+
+}
+
+
+fn localized_text_key(text: &LocalizedTextSource) -> LocalizedTextKey {
+    (text.en.clone(), text.de.clone(), text.fr.clone())
+}
+
+
+impl SpecificationSource {
+
+    /// Decompile a `SpecificationFile` into its text representation.
+    pub fn from_file(file: &SpecificationFile) -> SpecificationSource {
+        let localized_text = |idx: &LocalizedTextIndex| {
+            LocalizedTextSource {
+                en: file.localized_text_by_index(idx, Language::En).to_owned(),
+                de: file.localized_text_by_index(idx, Language::De).to_owned(),
+                fr: file.localized_text_by_index(idx, Language::Fr).to_owned(),
+            }
+        };
+
+        let units = file.units.iter().map(|unit| {
+            UnitSource {
+                id: unit.unit_id.0,
+                family: file.unit_family_by_id(&unit.unit_family_id),
+                code: file.text_by_index(&unit.unit_code_text_index).to_owned(),
+                text: file.text_by_index(&unit.unit_text_text_index).to_owned(),
+            }
+        }).collect();
+
+        let devices = file.device_templates.iter().map(|device_template| {
+            DeviceSource {
+                self_address: device_template.self_address,
+                self_mask: device_template.self_mask,
+                peer_address: device_template.peer_address,
+                peer_mask: device_template.peer_mask,
+                name: localized_text(&device_template.name_localized_text_index),
+            }
+        }).collect();
+
+        let packets = file.packet_templates.iter().map(|packet_template| {
+            let fields = packet_template.fields.iter().map(|field| {
+                let parts = field.parts.iter().map(|part| {
+                    PartSource {
+                        offset: part.offset,
+                        bit_pos: part.bit_pos,
+                        mask: part.mask,
+                        is_signed: part.is_signed,
+                        factor: part.factor,
+                    }
+                }).collect();
+
+                let unit = file.unit_by_id(&field.unit_id);
+
+                FieldSource {
+                    id: file.text_by_index(&field.id_text_index).to_owned(),
+                    name: localized_text(&field.name_localized_text_index),
+                    unit: file.text_by_index(&unit.unit_code_text_index).to_owned(),
+                    precision: field.precision,
+                    typ: file.type_by_id(&field.type_id),
+                    parts,
+                }
+            }).collect();
+
+            PacketSource {
+                destination_address: packet_template.destination_address,
+                destination_mask: packet_template.destination_mask,
+                source_address: packet_template.source_address,
+                source_mask: packet_template.source_mask,
+                command: packet_template.command,
+                fields,
+            }
+        }).collect();
+
+        SpecificationSource {
+            datecode: file.datecode,
+            units,
+            devices,
+            packets,
+        }
+    }
+
+    /// Compile the text representation into a `SpecificationFile`.
+    pub fn to_file(&self) -> Result<SpecificationFile> {
+        let mut unit_ids = HashMap::new();
+        for unit in self.units.iter() {
+            if unit_ids.values().any(|&id| id == unit.id) {
+                return Err(Error::DuplicateUnitId(unit.id));
+            }
+            if unit_ids.insert(unit.code.as_str(), unit.id).is_some() {
+                return Err(Error::DuplicateUnitCode(unit.code.clone()));
+            }
+        }
+
+        let mut builder = TextTableBuilder::default();
+
+        for unit in self.units.iter() {
+            builder.add_text(&unit.code);
+            builder.add_text(&unit.text);
+        }
+
+        for device in self.devices.iter() {
+            builder.add_localized_text(&device.name);
+        }
+
+        for packet in self.packets.iter() {
+            for field in packet.fields.iter() {
+                if !unit_ids.contains_key(field.unit.as_str()) {
+                    return Err(Error::UnknownUnitCode(field.unit.clone()));
+                }
+
+                builder.add_text(&field.id);
+                builder.add_localized_text(&field.name);
+            }
+        }
+
+        let TextTables {
+            texts,
+            text_indices,
+            localized_texts,
+            localized_text_indices,
+        } = builder.finish();
+
+        let units = self.units.iter().map(|unit| {
+            Unit {
+                unit_id: UnitId(unit.id),
+                unit_family_id: unit_family_id_by_unit_family(unit.family),
+                unit_code_text_index: text_indices [&unit.code],
+                unit_text_text_index: text_indices [&unit.text],
+            }
+        }).collect();
+
+        let device_templates = self.devices.iter().map(|device| {
+            DeviceTemplate {
+                self_address: device.self_address,
+                self_mask: device.self_mask,
+                peer_address: device.peer_address,
+                peer_mask: device.peer_mask,
+                name_localized_text_index: localized_text_indices [&localized_text_key(&device.name)],
+            }
+        }).collect();
+
+        let packet_templates = self.packets.iter().map(|packet| {
+            let fields = packet.fields.iter().map(|field| {
+                let parts = field.parts.iter().map(|part| {
+                    PacketTemplateFieldPart {
+                        offset: part.offset,
+                        bit_pos: part.bit_pos,
+                        mask: part.mask,
+                        is_signed: part.is_signed,
+                        factor: part.factor,
+                    }
+                }).collect();
+
+                PacketTemplateField {
+                    id_text_index: text_indices [&field.id],
+                    name_localized_text_index: localized_text_indices [&localized_text_key(&field.name)],
+                    unit_id: UnitId(unit_ids [field.unit.as_str()]),
+                    precision: field.precision,
+                    type_id: type_id_by_type(field.typ),
+                    parts,
+                }
+            }).collect();
+
+            PacketTemplate {
+                destination_address: packet.destination_address,
+                destination_mask: packet.destination_mask,
+                source_address: packet.source_address,
+                source_mask: packet.source_mask,
+                command: packet.command,
+                fields,
+            }
+        }).collect();
+
+        Ok(SpecificationFile {
+            datecode: self.datecode,
+            texts,
+            localized_texts,
+            units,
+            device_templates,
+            packet_templates,
+        })
+    }
+
+    /// Parse the text representation from a JSON string.
+    pub fn from_json(json: &str) -> Result<SpecificationSource> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Generate a pretty-printed JSON string from the text representation.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_data::SPEC_FILE_1;
+
+    #[test]
+    fn test_from_file() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let source = SpecificationSource::from_file(&spec_file);
+
+        assert_eq!(48, source.units.len());
+        assert_eq!(UnitSource {
+            id: 55,
+            family: UnitFamily::Pressure,
+            code: "Bars".to_owned(),
+            text: " bar".to_owned(),
+        }, source.units [0]);
+
+        assert_eq!(18, source.devices.len());
+        assert_eq!(0x0010, source.devices [0].self_address);
+        assert_eq!("DFA", source.devices [0].name.en);
+
+        assert_eq!(2, source.packets.len());
+
+        let packet = &source.packets [0];
+        assert_eq!(0x0010, packet.destination_address);
+        assert_eq!(0x7E30, packet.source_address);
+        assert_eq!(0xFFF0, packet.source_mask);
+        assert_eq!(0x0100, packet.command);
+        assert_eq!(8, packet.fields.len());
+
+        let field = &packet.fields [0];
+        assert_eq!("000_4_0", field.id);
+        assert_eq!("Wärmemenge", field.name.de);
+        assert_eq!("WattHours", field.unit);
+        assert_eq!(0, field.precision);
+        assert_eq!(Type::Number, field.typ);
+        assert_eq!(8, field.parts.len());
+        assert_eq!(PartSource {
+            offset: 3,
+            bit_pos: 0,
+            mask: 0xFF,
+            is_signed: true,
+            factor: 16777216,
+        }, field.parts [3]);
+    }
+
+    #[test]
+    fn test_to_file() {
+        let spec_file = SpecificationFile::from_bytes(SPEC_FILE_1).unwrap();
+
+        let source = SpecificationSource::from_file(&spec_file);
+
+        let other_spec_file = source.to_file().unwrap();
+
+        // unreferenced texts are not part of the text representation
+        assert_eq!(188, spec_file.texts.len());
+        assert_eq!(187, other_spec_file.texts.len());
+        assert_eq!(source, SpecificationSource::from_file(&other_spec_file));
+
+        let other_spec_file = SpecificationFile::from_bytes(&other_spec_file.to_bytes()).unwrap();
+
+        assert_eq!(source, SpecificationSource::from_file(&other_spec_file));
+
+        let mut source = source;
+        source.packets [0].fields [0].unit = "Furlongs".to_owned();
+
+        match source.to_file() {
+            Err(Error::UnknownUnitCode(ref code)) => assert_eq!("Furlongs", code),
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let json = r#"{
+            "datecode": 20170101,
+            "units": [
+                { "id": -1, "family": "None", "code": "None", "text": "" },
+                { "id": 62, "family": "Temperature", "code": "DegreesCelsius", "text": " °C" }
+            ],
+            "devices": [
+                {
+                    "self_address": "0x7E11",
+                    "self_mask": "0xFFFF",
+                    "peer_address": "0x0000",
+                    "peer_mask": "0x0000",
+                    "name": { "en": "Controller", "de": "Regler", "fr": "Régulateur" }
+                }
+            ],
+            "packets": [
+                {
+                    "destination_address": "0x0010",
+                    "destination_mask": "0xFFFF",
+                    "source_address": "0x7E11",
+                    "source_mask": "0xFFFF",
+                    "command": "0x0100",
+                    "fields": [
+                        {
+                            "id": "000_2_0",
+                            "name": { "en": "Temperature sensor 1", "de": "Temperatur Sensor 1", "fr": "Température sonde 1" },
+                            "unit": "DegreesCelsius",
+                            "precision": 1,
+                            "type": "Number",
+                            "parts": [
+                                { "offset": 0 },
+                                { "offset": 1, "is_signed": true, "factor": 256 }
+                            ]
+                        }
+                    ]
+                }
+            ]
+        }"#;
+
+        let source = SpecificationSource::from_json(json).unwrap();
+
+        assert_eq!(20170101, source.datecode);
+        assert_eq!(0x7E11, source.devices [0].self_address);
+        assert_eq!(PartSource {
+            offset: 1,
+            bit_pos: 0,
+            mask: 0xFF,
+            is_signed: true,
+            factor: 256,
+        }, source.packets [0].fields [0].parts [1]);
+
+        let spec_file = source.to_file().unwrap();
+
+        let packet_template = spec_file.find_packet_template(0x0010, 0x7E11, 0x0100).unwrap();
+        let field = &packet_template.fields [0];
+        assert_eq!("000_2_0", spec_file.text_by_index(&field.id_text_index));
+        assert_eq!("Temperatur Sensor 1", spec_file.localized_text_by_index(&field.name_localized_text_index, Language::De));
+        assert_eq!(62, field.unit_id.0);
+        assert_eq!(2, field.parts.len());
+
+        let other_source = SpecificationSource::from_json(&source.to_json().unwrap()).unwrap();
+
+        assert_eq!(source, other_source);
+
+        assert!(SpecificationSource::from_json("{}").is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! A module that decides whether `Data` values are stale based on their age.
+//!
+//! Instead of hiding the payload of old packets (see `DataSet::clear_packets_older_than`), a
+//! `StalenessPolicy` assigns a time-to-live (TTL) to each `Data` value, either by its `DataKind`
+//! or by the `PacketId` of a `Packet`. This applies to `Datagram` and `Telegram` values as well.
+//!
+//! The `DataSetPacketField`s returned by `Specification::fields_in_data_set` can be marked stale
+//! using `DataSetPacketFieldIterator::with_staleness`, so that an exporter can choose to output
+//! the last known value, an empty cell or a marked value for them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{DataSet, PacketId, Specification};
+//! use resol_vbus::chrono::Duration;
+//! use resol_vbus::staleness::{DataKind, StalenessPolicy};
+//!
+//! # #[allow(dead_code)]
+//! fn print_fields(spec: &Specification, data_set: &DataSet) {
+//!     let mut policy = StalenessPolicy::new();
+//!     policy.set_ttl(DataKind::Packet, Duration::minutes(5));
+//!     policy.set_packet_ttl(PacketId(0x00, 0x0010, 0x7E11, 0x0100), Duration::seconds(30));
+//!
+//!     for field in spec.fields_in_data_set(data_set).with_staleness(&policy, data_set.timestamp) {
+//!         if field.is_stale() {
+//!             println!("{}: {} (stale)", field.field_spec().name, field.fmt_raw_value(true));
+//!         } else {
+//!             println!("{}: {}", field.field_spec().name, field.fmt_raw_value(true));
+//!         }
+//!     }
+//! }
+//! ```
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, UTC};
+
+use data::Data;
+use packet::PacketId;
+
+
+/// The kind of a `Data` value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DataKind {
+    /// A `Packet` conforming to VBus protocol version 1.x.
+    Packet,
+
+    /// A `Datagram` conforming to VBus protocol version 2.x.
+    Datagram,
+
+    /// A `Telegram` conforming to VBus protocol version 3.x.
+    Telegram,
+}
+
+
+impl<'a> From<&'a Data> for DataKind {
+    fn from(data: &'a Data) -> DataKind {
+        match *data {
+            Data::Packet(_) => DataKind::Packet,
+            Data::Datagram(_) => DataKind::Datagram,
+            Data::Telegram(_) => DataKind::Telegram,
+        }
+    }
+}
+
+
+/// Assigns TTLs to `Data` values to decide whether they are stale.
+///
+/// A TTL set for a `PacketId` takes precedence over the TTL set for its `DataKind`. `Data`
+/// values without a TTL never become stale.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::{Data, Header, Datagram};
+/// use resol_vbus::chrono::Duration;
+/// use resol_vbus::staleness::{DataKind, StalenessPolicy};
+/// use resol_vbus::utils::utc_timestamp;
+///
+/// let data = Data::Datagram(Datagram {
+///     header: Header {
+///         timestamp: utc_timestamp(1485688933),
+///         channel: 0x11,
+///         destination_address: 0x0000,
+///         source_address: 0x7E11,
+///         protocol_version: 0x20,
+///     },
+///     command: 0x0500,
+///     param16: 0,
+///     param32: 0,
+/// });
+///
+/// let mut policy = StalenessPolicy::new();
+/// assert!(!policy.is_stale(&data, utc_timestamp(1485688993)));
+///
+/// policy.set_ttl(DataKind::Datagram, Duration::seconds(30));
+/// assert!(!policy.is_stale(&data, utc_timestamp(1485688963)));
+/// assert!(policy.is_stale(&data, utc_timestamp(1485688964)));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StalenessPolicy {
+    kind_ttls: HashMap<DataKind, Duration>,
+    packet_ttls: HashMap<PacketId, Duration>,
+}
+
+
+impl StalenessPolicy {
+
+    /// Construct a `StalenessPolicy` without any TTLs.
+    pub fn new() -> StalenessPolicy {
+        StalenessPolicy::default()
+    }
+
+    /// Set the TTL for all `Data` values of a `DataKind`.
+    pub fn set_ttl(&mut self, kind: DataKind, ttl: Duration) {
+        self.kind_ttls.insert(kind, ttl);
+    }
+
+    /// Set the TTL for all `Packet` values with the given `PacketId`.
+    pub fn set_packet_ttl(&mut self, packet_id: PacketId, ttl: Duration) {
+        self.packet_ttls.insert(packet_id, ttl);
+    }
+
+    /// Get the TTL that applies to a `Data` value, if any.
+    pub fn ttl(&self, data: &Data) -> Option<Duration> {
+        let packet_ttl = match *data {
+            Data::Packet(ref packet) => self.packet_ttls.get(&packet.packet_id()),
+            _ => None,
+        };
+
+        packet_ttl.or_else(|| self.kind_ttls.get(&DataKind::from(data))).cloned()
+    }
+
+    /// Get the age of a `Data` value at the given point in time.
+    pub fn age(&self, data: &Data, now: DateTime<UTC>) -> Duration {
+        now.signed_duration_since(data.as_header().timestamp)
+    }
+
+    /// Check whether a `Data` value is older than its TTL at the given point in time.
+    pub fn is_stale(&self, data: &Data, now: DateTime<UTC>) -> bool {
+        self.ttl(data).is_some_and(|ttl| self.age(data, now) > ttl)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, UTC};
+
+    use live_data_decoder::data_from_checked_bytes;
+
+    use super::*;
+
+    use test_data::{LIVE_DATA_1, LIVE_TELEGRAM_1};
+
+    #[test]
+    fn test_ttl() {
+        let timestamp = UTC.timestamp(1485688933, 0);
+        let channel = 0x11;
+
+        let packet = data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [0..]);
+        let other_packet = data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [258..]);
+        let datagram = data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [352..]);
+        let telegram = data_from_checked_bytes(timestamp, channel, &LIVE_TELEGRAM_1 [0..]);
+
+        assert_eq!(DataKind::Packet, DataKind::from(&packet));
+        assert_eq!(DataKind::Datagram, DataKind::from(&datagram));
+        assert_eq!(DataKind::Telegram, DataKind::from(&telegram));
+
+        let mut policy = StalenessPolicy::new();
+        assert_eq!(None, policy.ttl(&packet));
+
+        policy.set_ttl(DataKind::Packet, Duration::seconds(60));
+        policy.set_ttl(DataKind::Telegram, Duration::seconds(10));
+        policy.set_packet_ttl(packet.as_packet().packet_id(), Duration::seconds(5));
+
+        assert_eq!(Some(Duration::seconds(5)), policy.ttl(&packet));
+        assert_eq!(Some(Duration::seconds(60)), policy.ttl(&other_packet));
+        assert_eq!(None, policy.ttl(&datagram));
+        assert_eq!(Some(Duration::seconds(10)), policy.ttl(&telegram));
+
+        let now = timestamp + Duration::seconds(20);
+        assert_eq!(Duration::seconds(20), policy.age(&packet, now));
+        assert!(policy.is_stale(&packet, now));
+        assert!(!policy.is_stale(&other_packet, now));
+        assert!(!policy.is_stale(&datagram, now));
+        assert!(policy.is_stale(&telegram, now));
+    }
+}
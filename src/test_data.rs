@@ -1,3 +1,11 @@
+use chrono::{DateTime, UTC};
+
+use data::Data;
+use data_set::DataSet;
+use live_data_decoder::data_from_checked_bytes;
+use specification::Specification;
+
+
 pub const LIVE_DATA_1: &'static [u8] = &[
     0xaa, 0x10, 0x00, 0x11, 0x7e, 0x10, 0x00, 0x01, 0x1b, 0x34,
     0x37, 0x00, 0x1d, 0x01, 0x00, 0x2a,
@@ -909,3 +917,21 @@ pub const SPEC_FILE_1: &'static [u8] = &[
     0x30, 0x00, 0x00, 0x00, 0x10, 0x0e, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00,
     0x10, 0x11, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xc0, 0x1b, 0x00, 0x00
 ];
+
+
+/// Construct a `DataSet` containing the `LIVE_DATA_1` packet on the given channel.
+///
+/// The temperature field "000_2_0" of the packet is set to the given raw value. `None` removes
+/// the payload of the packet, so that none of its fields have a value.
+pub fn data_set_with_temperature(spec: &Specification, channel: u8, timestamp: DateTime<UTC>, raw_value: Option<i64>) -> DataSet {
+    let mut data = data_from_checked_bytes(timestamp, channel, &LIVE_DATA_1 [0..]);
+    if let Data::Packet(ref mut packet) = data {
+        let packet_spec = spec.get_packet_spec(channel, 0x0010, 0x7E11, 0x0100);
+        match raw_value {
+            Some(raw_value) => packet_spec.get_field_spec("000_2_0").unwrap().set_raw_value_i64(&mut packet.frame_data [..], raw_value).unwrap(),
+            None => packet.frame_count = 0,
+        }
+    }
+
+    DataSet::from_data(timestamp, vec![ data ])
+}
@@ -0,0 +1,166 @@
+//! A module that provides translations of device and field names for languages that are not
+//! contained in VSF1 specification files.
+//!
+//! VSF1 files only contain English, German and French texts. A `TranslationTable` adds the
+//! device and field names for one `Language`, keyed by their IDs without the VBus channel prefix:
+//!
+//! - devices: `<self address>` or `<self address>_<peer address>` (e.g. `7E11`)
+//! - fields: `<destination>_<source>_10_<command>_<field ID>` (e.g. `0010_7E11_10_0100_000_2_0`)
+//!
+//! Translation tables are stored as plain text, one `<key> = <text>` pair per line. Empty lines
+//! and lines starting with `#` are ignored.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::{Specification, SpecificationFile, Language, TranslationTable};
+//!
+//! let table = TranslationTable::parse(Language::It, "
+//!     7E11 = DeltaSol MX [Regolatore]
+//!     0010_7E11_10_0100_000_2_0 = Temperatura sonda 1
+//! ").unwrap();
+//!
+//! let mut spec = Specification::from_file(SpecificationFile::new_default(), Language::It);
+//! spec.push_translations(table);
+//!
+//! let packet_spec = spec.get_packet_spec(0x00, 0x0010, 0x7E11, 0x0100);
+//! assert_eq!("DeltaSol MX [Regolatore]", packet_spec.name);
+//! assert_eq!("Temperatura sonda 1", packet_spec.fields [0].name);
+//!
+//! // Names without translation fall back to English
+//! assert_eq!("Temperature sensor 2", packet_spec.fields [1].name);
+//! ```
+use std::collections::HashMap;
+
+use specification_file::Language;
+
+
+/// A list of errors that can occur if a `TranslationTable` cannot be parsed.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The line with the given 1-based number is not a `<key> = <text>` pair.
+    InvalidLine(usize),
+
+    /// The key on the line with the given 1-based number was already defined.
+    DuplicateKey(usize),
+}
+
+
+/// A specialized Result for this module.
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+
+/// A table of translated device and field names for a single `Language`.
+#[derive(Debug, Clone)]
+pub struct TranslationTable {
+    language: Language,
+    texts: HashMap<String, String>,
+}
+
+
+impl TranslationTable {
+
+    /// Construct an empty `TranslationTable` for the given `Language`.
+    pub fn new(language: Language) -> TranslationTable {
+        TranslationTable {
+            language,
+            texts: HashMap::new(),
+        }
+    }
+
+    /// Parse a `TranslationTable` for the given `Language` from its text representation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::{Language, TranslationTable};
+    /// use resol_vbus::translation_table::Error;
+    ///
+    /// let table = TranslationTable::parse(Language::Nl, "7E11 = DeltaSol MX [Regelaar]").unwrap();
+    /// assert_eq!(Some("DeltaSol MX [Regelaar]"), table.get("7E11"));
+    ///
+    /// assert_eq!(Error::InvalidLine(2), TranslationTable::parse(Language::Nl, "\n7E11").unwrap_err());
+    /// ```
+    pub fn parse(language: Language, input: &str) -> Result<TranslationTable> {
+        let mut table = TranslationTable::new(language);
+
+        for (index, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let separator = match line.find('=') {
+                Some(separator) => separator,
+                None => return Err(Error::InvalidLine(index + 1)),
+            };
+
+            let key = line [..separator].trim();
+            let text = line [(separator + 1)..].trim();
+            if key.is_empty() {
+                return Err(Error::InvalidLine(index + 1));
+            }
+
+            if table.texts.contains_key(key) {
+                return Err(Error::DuplicateKey(index + 1));
+            }
+
+            table.insert(key, text);
+        }
+
+        Ok(table)
+    }
+
+    /// Get the `Language` of this table.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// Add or replace the translation for the given key.
+    pub fn insert(&mut self, key: &str, text: &str) {
+        self.texts.insert(key.to_owned(), text.to_owned());
+    }
+
+    /// Get the translation for the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.texts.get(key).map(|text| text.as_str())
+    }
+
+    /// Get the number of translations in this table.
+    pub fn len(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// Returns `true` if this table contains no translations.
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let table = TranslationTable::parse(Language::Es, "
+            # comment
+
+            7E11 = DeltaSol MX [Regulador]
+            7E11_0010=Regulador
+            0010_7E11_10_0100_000_2_0 = Temperatura sonda 1 = S1
+        ").unwrap();
+
+        assert_eq!(Language::Es, table.language());
+        assert_eq!(3, table.len());
+        assert_eq!(Some("DeltaSol MX [Regulador]"), table.get("7E11"));
+        assert_eq!(Some("Regulador"), table.get("7E11_0010"));
+        assert_eq!(Some("Temperatura sonda 1 = S1"), table.get("0010_7E11_10_0100_000_2_0"));
+        assert_eq!(None, table.get("7E12"));
+
+        assert_eq!(Error::InvalidLine(1), TranslationTable::parse(Language::Es, " = Text").unwrap_err());
+        assert_eq!(Error::DuplicateKey(2), TranslationTable::parse(Language::Es, "7E11 = A\n7E11 = B").unwrap_err());
+    }
+}
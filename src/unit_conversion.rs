@@ -0,0 +1,304 @@
+//! This module provides conversions of packet field values between units of the same `UnitFamily`.
+//!
+//! The VSF only describes the unit a value is transmitted in. This module knows how the units
+//! of each `UnitFamily` relate to each other and can convert values between them, either to an
+//! explicit unit or to the preferred unit of a `UnitSystem`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use resol_vbus::specification_file::UnitFamily;
+//! use resol_vbus::unit_conversion::{convert, UnitSystem};
+//!
+//! let value = convert(100.0, UnitFamily::Temperature, "DegreesCelsius", "DegreesFahrenheit").unwrap();
+//! assert_eq!("212.0", format!("{:.1}", value));
+//!
+//! let unit = UnitSystem::UsCustomary.target_unit(UnitFamily::Volume, "Liters").unwrap();
+//! assert_eq!("Gallons", unit.unit_code);
+//! assert_eq!(" gal", unit.unit_text);
+//! ```
+use specification_file::UnitFamily;
+
+
+/// Describes how a unit relates to the base unit of its dimension.
+///
+/// A value is converted into the base unit by calculating `value * factor + offset`.
+#[derive(Debug, PartialEq)]
+pub struct UnitDefinition {
+    /// The `UnitFamily` of the unit.
+    pub unit_family: UnitFamily,
+
+    /// The machine-readable unit code, matching the unit codes of the VSF.
+    pub unit_code: &'static str,
+
+    /// The human-readable unit text.
+    pub unit_text: &'static str,
+
+    /// The unit code of the base unit. Only units sharing the same base unit can be converted.
+    pub base_unit_code: &'static str,
+
+    /// The factor to convert a value into the base unit.
+    pub factor: f64,
+
+    /// The offset to add after multiplying with the factor to convert a value into the base unit.
+    pub offset: f64,
+}
+
+
+/// A system of units used to select the target unit of a conversion.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitSystem {
+    /// Metric units as commonly used in Europe (°C, Wh, l, bar).
+    Metric,
+
+    /// United States customary units (°F, BTU, gal, psi).
+    UsCustomary,
+
+    /// SI base and derived units (K, J, m³, Pa, s, W).
+    Si,
+}
+
+
+const GALLON: f64 = 0.003_785_411_784;
+
+const BTU: f64 = 1_055.055_852_62;
+
+
+macro_rules! unit {
+    ($family:ident, $code:expr, $text:expr, $base:expr, $factor:expr) => {
+        unit!($family, $code, $text, $base, $factor, 0.0)
+    };
+    ($family:ident, $code:expr, $text:expr, $base:expr, $factor:expr, $offset:expr) => {
+        UnitDefinition {
+            unit_family: UnitFamily::$family,
+            unit_code: $code,
+            unit_text: $text,
+            base_unit_code: $base,
+            factor: $factor,
+            offset: $offset,
+        }
+    };
+}
+
+
+/// The list of all known unit definitions.
+pub static UNIT_DEFINITIONS: &[UnitDefinition] = &[
+    unit!(Temperature, "DegreesKelvin", " K", "DegreesKelvin", 1.0),
+    unit!(Temperature, "DegreesCelsius", " °C", "DegreesKelvin", 1.0, 273.15),
+    unit!(Temperature, "DegreesFahrenheit", " °F", "DegreesKelvin", 5.0 / 9.0, 459.67 * 5.0 / 9.0),
+
+    unit!(Energy, "Joules", " J", "Joules", 1.0),
+    unit!(Energy, "WattHours", " Wh", "Joules", 3_600.0),
+    unit!(Energy, "KilowattHours", " kWh", "Joules", 3_600_000.0),
+    unit!(Energy, "MegawattHours", " MWh", "Joules", 3_600_000_000.0),
+    unit!(Energy, "Btus", " BTU", "Joules", BTU),
+    unit!(Energy, "KiloBtus", " MBTU", "Joules", BTU * 1_000.0),
+    unit!(Energy, "MegaBtus", " MMBTU", "Joules", BTU * 1_000_000.0),
+
+    unit!(Energy, "GramsCO2Oil", " g CO₂ (Oil)", "GramsCO2Oil", 1.0),
+    unit!(Energy, "KilogramsCO2Oil", " kg CO₂ (Oil)", "GramsCO2Oil", 1_000.0),
+    unit!(Energy, "TonsCO2Oil", " t CO₂ (Oil)", "GramsCO2Oil", 1_000_000.0),
+    unit!(Energy, "GramsCO2Gas", " g CO₂ (Gas)", "GramsCO2Gas", 1.0),
+    unit!(Energy, "KilogramsCO2Gas", " kg CO₂ (Gas)", "GramsCO2Gas", 1_000.0),
+    unit!(Energy, "TonsCO2Gas", " t CO₂ (Gas)", "GramsCO2Gas", 1_000_000.0),
+
+    unit!(VolumeFlow, "CubicMetersPerSecond", " m³/s", "CubicMetersPerSecond", 1.0),
+    unit!(VolumeFlow, "CubicMetersPerHour", " m³/h", "CubicMetersPerSecond", 1.0 / 3_600.0),
+    unit!(VolumeFlow, "LitersPerHour", " l/h", "CubicMetersPerSecond", 0.001 / 3_600.0),
+    unit!(VolumeFlow, "LitersPerMinute", " l/min", "CubicMetersPerSecond", 0.001 / 60.0),
+    unit!(VolumeFlow, "GallonsPerHour", " gal/h", "CubicMetersPerSecond", GALLON / 3_600.0),
+    unit!(VolumeFlow, "GallonsPerMinute", " gal/min", "CubicMetersPerSecond", GALLON / 60.0),
+
+    unit!(Pressure, "Pascals", " Pa", "Pascals", 1.0),
+    unit!(Pressure, "Bars", " bar", "Pascals", 100_000.0),
+    unit!(Pressure, "PoundsForcePerSquareInch", " psi", "Pascals", 6_894.757_293_168),
+
+    unit!(Volume, "CubicMeters", " m³", "CubicMeters", 1.0),
+    unit!(Volume, "Liters", " l", "CubicMeters", 0.001),
+    unit!(Volume, "Gallons", " gal", "CubicMeters", GALLON),
+
+    unit!(Time, "Seconds", " s", "Seconds", 1.0),
+    unit!(Time, "Minutes", " min", "Seconds", 60.0),
+    unit!(Time, "Hours", " h", "Seconds", 3_600.0),
+    unit!(Time, "Days", " d", "Seconds", 86_400.0),
+
+    unit!(Power, "Watts", " W", "Watts", 1.0),
+    unit!(Power, "Kilowatts", " kW", "Watts", 1_000.0),
+    unit!(Power, "BtusPerHour", " BTU/h", "Watts", BTU / 3_600.0),
+];
+
+
+/// Find the `UnitDefinition` for a unit code within a `UnitFamily`.
+///
+/// The unit family must match as well, since some unit codes (e.g. "DegreesKelvin") are used
+/// for temperature differences in the VSF and must not be converted like absolute temperatures.
+pub fn find_unit_definition(unit_family: UnitFamily, unit_code: &str) -> Option<&'static UnitDefinition> {
+    UNIT_DEFINITIONS.iter().find(|unit| unit.unit_family == unit_family && unit.unit_code == unit_code)
+}
+
+
+/// Convert a value from one unit into another unit of the same `UnitFamily`.
+///
+/// Returns `None` if either of the units is unknown or if they cannot be converted into each
+/// other.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::specification_file::UnitFamily;
+/// use resol_vbus::unit_conversion::convert;
+///
+/// assert_eq!(Some(3_600.0), convert(1.0, UnitFamily::Energy, "WattHours", "Joules"));
+/// assert_eq!(Some(1.5), convert(1_500.0, UnitFamily::Volume, "Liters", "CubicMeters"));
+/// assert_eq!(None, convert(1.0, UnitFamily::Energy, "WattHours", "GramsCO2Oil"));
+/// ```
+pub fn convert(value: f64, unit_family: UnitFamily, from_unit_code: &str, to_unit_code: &str) -> Option<f64> {
+    let from_unit = find_unit_definition(unit_family, from_unit_code)?;
+    let to_unit = find_unit_definition(unit_family, to_unit_code)?;
+    convert_between(value, from_unit, to_unit)
+}
+
+
+/// Convert a value between two `UnitDefinition`s.
+///
+/// Returns `None` if the units do not share the same base unit.
+pub fn convert_between(value: f64, from_unit: &UnitDefinition, to_unit: &UnitDefinition) -> Option<f64> {
+    if from_unit.base_unit_code != to_unit.base_unit_code {
+        None
+    } else if from_unit.unit_code == to_unit.unit_code {
+        Some(value)
+    } else {
+        let base_value = value * from_unit.factor + from_unit.offset;
+        Some((base_value - to_unit.offset) / to_unit.factor)
+    }
+}
+
+
+impl UnitSystem {
+
+    fn target_unit_code(self, unit_code: &str) -> Option<&'static str> {
+        match self {
+            UnitSystem::Metric => match unit_code {
+                "DegreesKelvin" | "DegreesFahrenheit" => Some("DegreesCelsius"),
+                "Joules" | "Btus" => Some("WattHours"),
+                "KiloBtus" => Some("KilowattHours"),
+                "MegaBtus" => Some("MegawattHours"),
+                "CubicMetersPerSecond" => Some("CubicMetersPerHour"),
+                "GallonsPerHour" => Some("LitersPerHour"),
+                "GallonsPerMinute" => Some("LitersPerMinute"),
+                "Pascals" | "PoundsForcePerSquareInch" => Some("Bars"),
+                "Gallons" => Some("Liters"),
+                "BtusPerHour" => Some("Watts"),
+                _ => None,
+            },
+            UnitSystem::UsCustomary => match unit_code {
+                "DegreesKelvin" | "DegreesCelsius" => Some("DegreesFahrenheit"),
+                "Joules" | "WattHours" => Some("Btus"),
+                "KilowattHours" => Some("KiloBtus"),
+                "MegawattHours" => Some("MegaBtus"),
+                "LitersPerHour" | "CubicMetersPerHour" => Some("GallonsPerHour"),
+                "CubicMetersPerSecond" | "LitersPerMinute" => Some("GallonsPerMinute"),
+                "Pascals" | "Bars" => Some("PoundsForcePerSquareInch"),
+                "CubicMeters" | "Liters" => Some("Gallons"),
+                "Watts" | "Kilowatts" => Some("BtusPerHour"),
+                _ => None,
+            },
+            UnitSystem::Si => None,
+        }
+    }
+
+    /// Get the `UnitDefinition` a value of the given unit should be converted to in this unit system.
+    ///
+    /// Returns `None` if the unit is unknown. Units that have no preferred counterpart in this
+    /// unit system return their own definition.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use resol_vbus::specification_file::UnitFamily;
+    /// use resol_vbus::unit_conversion::UnitSystem;
+    ///
+    /// let target_unit_code = |unit_system: UnitSystem| {
+    ///     unit_system.target_unit(UnitFamily::Temperature, "DegreesCelsius").unwrap().unit_code
+    /// };
+    ///
+    /// assert_eq!("DegreesCelsius", target_unit_code(UnitSystem::Metric));
+    /// assert_eq!("DegreesFahrenheit", target_unit_code(UnitSystem::UsCustomary));
+    /// assert_eq!("DegreesKelvin", target_unit_code(UnitSystem::Si));
+    /// ```
+    pub fn target_unit(self, unit_family: UnitFamily, unit_code: &str) -> Option<&'static UnitDefinition> {
+        let unit = find_unit_definition(unit_family, unit_code)?;
+
+        let target_unit_code = match self {
+            UnitSystem::Si => unit.base_unit_code,
+            _ => self.target_unit_code(unit_code).unwrap_or(unit.unit_code),
+        };
+
+        find_unit_definition(unit_family, target_unit_code)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(expected: f64, actual: Option<f64>) {
+        let actual = actual.unwrap();
+        assert!((expected - actual).abs() < 1e-9 * expected.abs().max(1.0), "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn test_unit_definitions() {
+        for unit in UNIT_DEFINITIONS {
+            let base_unit = find_unit_definition(unit.unit_family, unit.base_unit_code).unwrap();
+            assert_eq!(base_unit.unit_code, base_unit.base_unit_code);
+            assert_eq!(1.0, base_unit.factor);
+            assert_eq!(0.0, base_unit.offset);
+
+            for &unit_system in &[ UnitSystem::Metric, UnitSystem::UsCustomary, UnitSystem::Si ] {
+                let target_unit = unit_system.target_unit(unit.unit_family, unit.unit_code).unwrap();
+                assert_eq!(unit.base_unit_code, target_unit.base_unit_code);
+            }
+        }
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_close(212.0, convert(100.0, UnitFamily::Temperature, "DegreesCelsius", "DegreesFahrenheit"));
+        assert_close(-40.0, convert(-40.0, UnitFamily::Temperature, "DegreesFahrenheit", "DegreesCelsius"));
+        assert_close(273.15, convert(0.0, UnitFamily::Temperature, "DegreesCelsius", "DegreesKelvin"));
+        assert_eq!(None, convert(0.0, UnitFamily::None, "DegreesKelvin", "DegreesCelsius"));
+
+        assert_close(3.412_141_633, convert(1.0, UnitFamily::Energy, "WattHours", "Btus"));
+        assert_close(1.0, convert(1.0, UnitFamily::Energy, "KilowattHours", "KiloBtus").map(|value| value / 3.412_141_633));
+        assert_close(2.5, convert(2_500.0, UnitFamily::Energy, "KilogramsCO2Gas", "TonsCO2Gas"));
+        assert_eq!(None, convert(1.0, UnitFamily::Energy, "KilogramsCO2Gas", "KilogramsCO2Oil"));
+
+        assert_close(1_000.0, convert(GALLON * 1000.0, UnitFamily::Volume, "CubicMeters", "Gallons"));
+        assert_close(60.0, convert(1.0, UnitFamily::VolumeFlow, "LitersPerMinute", "LitersPerHour"));
+        assert_close(14.503_773_773, convert(1.0, UnitFamily::Pressure, "Bars", "PoundsForcePerSquareInch"));
+        assert_close(1.5, convert(90.0, UnitFamily::Time, "Minutes", "Hours"));
+        assert_close(1_000.0, convert(1.0, UnitFamily::Power, "Kilowatts", "Watts"));
+
+        assert_eq!(None, convert(1.0, UnitFamily::Volume, "Liters", "Furlongs"));
+        assert_eq!(None, convert(1.0, UnitFamily::Volume, "Liters", "Watts"));
+    }
+
+    #[test]
+    fn test_target_unit() {
+        let target_unit_code = |unit_system: UnitSystem, unit_family, unit_code| {
+            unit_system.target_unit(unit_family, unit_code).map(|unit| unit.unit_code)
+        };
+
+        assert_eq!(Some("Btus"), target_unit_code(UnitSystem::UsCustomary, UnitFamily::Energy, "WattHours"));
+        assert_eq!(Some("Joules"), target_unit_code(UnitSystem::Si, UnitFamily::Energy, "WattHours"));
+        assert_eq!(Some("WattHours"), target_unit_code(UnitSystem::Metric, UnitFamily::Energy, "WattHours"));
+        assert_eq!(Some("TonsCO2Oil"), target_unit_code(UnitSystem::UsCustomary, UnitFamily::Energy, "TonsCO2Oil"));
+        assert_eq!(Some("GramsCO2Oil"), target_unit_code(UnitSystem::Si, UnitFamily::Energy, "TonsCO2Oil"));
+        assert_eq!(Some("Liters"), target_unit_code(UnitSystem::Metric, UnitFamily::Volume, "Gallons"));
+        assert_eq!(Some("Hours"), target_unit_code(UnitSystem::UsCustomary, UnitFamily::Time, "Hours"));
+        assert_eq!(None, target_unit_code(UnitSystem::Si, UnitFamily::None, "Percent"));
+    }
+}
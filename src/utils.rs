@@ -194,7 +194,27 @@ const CRC16_TABLE: &'static [u16] = &[
 /// assert_eq!(0xF078, calc_crc16(&[ 0x00 ]));
 /// ```
 pub fn calc_crc16(buf: &[u8]) -> u16 {
-    let mut crc = 0xFFFFu16;
+    update_crc16(0x0000, buf)
+}
+
+
+/// Continue a CRC16 checksum calculation with another slice of bytes.
+///
+/// Passing the result of `calc_crc16` over one slice of bytes as `crc` results in the same
+/// checksum as calling `calc_crc16` over the concatenation of both slices.
+///
+/// # Examples
+///
+/// ```rust
+/// use resol_vbus::utils::{calc_crc16, update_crc16};
+///
+/// let crc = calc_crc16(&[ 0x10, 0x00 ]);
+///
+/// assert_eq!(calc_crc16(&[ 0x10, 0x00, 0x11, 0x7E ]), update_crc16(crc, &[ 0x11, 0x7E ]));
+/// assert_eq!(calc_crc16(&[ 0x00 ]), update_crc16(0x0000, &[ 0x00 ]));
+/// ```
+pub fn update_crc16(crc: u16, buf: &[u8]) -> u16 {
+    let mut crc = crc ^ 0xFFFF;
     for byte in buf {
         crc = (crc >> 8) ^ CRC16_TABLE [(crc ^ *byte as u16) as usize & 0xFF];
     }